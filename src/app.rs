@@ -1,8 +1,10 @@
 use std::{
     cmp::min,
     collections::HashMap,
+    fs,
     io::stdout,
     process::{Command, Stdio},
+    time::{Duration, Instant},
 };
 
 use crossterm::{
@@ -15,22 +17,33 @@ use crossterm::{
 };
 use ratatui::{
     layout::{Position, Rect},
-    prelude::CrosstermBackend,
+    prelude::{Backend, CrosstermBackend},
     widgets::{Clear, Paragraph},
     Frame, Terminal,
 };
 use regex::{Regex, RegexBuilder};
+use unicode_width::UnicodeWidthChar as _;
 
 use crate::{
     model::{
         action::{Action, CommandType},
-        app_state::{AppState, InputState, NotifChannel},
-        config::{Button, MappingScope},
+        app_state::{AppState, InputState, MultiLinePrompt, NotifChannel},
+        clipboard::copy_to_clipboard,
+        config::{
+            config_path, parse_gitrs_config, Button, MappingScope, SearchCase, DEFAULT_CONFIG,
+        },
+        editor::editor_command,
         errors::Error,
+        git::{git_current_branch, git_remote_command, is_valid_git_rev, FileStatus},
     },
-    ui::utils::{
-        display_edit_bar, display_menu_bar, display_notifications, search_highlight_style,
-        SPINNER_FRAMES,
+    ui::{
+        help::Help,
+        pager_widget::{adapt_index_in_frame, wrap_positions},
+        palette::Palette,
+        utils::{
+            display_edit_bar, display_menu_bar, display_multi_line_edit, display_notifications,
+            mouse_selection_style, search_highlight_style, SPINNER_FRAMES,
+        },
     },
     views::{
         pager::{PagerApp, PagerCommand},
@@ -40,6 +53,9 @@ use crate::{
 
 pub type FileRevLine = (Option<String>, Option<String>, Option<usize>);
 
+// maximum number of lines scanned per frame when counting search matches
+const SEARCH_COUNT_CHUNK: usize = 10_000;
+
 pub trait GitApp {
     fn draw(&mut self, frame: &mut Frame, rect: Rect);
 
@@ -49,8 +65,19 @@ pub trait GitApp {
     fn loaded(&self) -> bool {
         true
     }
+    // draw the view again even if no event arrived
+    fn needs_redraw(&self) -> bool {
+        false
+    }
     fn reload(&mut self) -> Result<(), Error>;
     fn get_text_line(&self, _idx: usize) -> Option<String>;
+    fn get_search_line(&self, idx: usize) -> Option<String> {
+        self.get_text_line(idx)
+    }
+    // text put in the clipboard by the copy actions
+    fn get_copy_line(&self, idx: usize) -> Option<String> {
+        self.get_text_line(idx)
+    }
 
     fn state(&mut self) -> &mut AppState;
     fn get_state(&self) -> &AppState;
@@ -77,11 +104,45 @@ pub trait GitApp {
         };
     }
 
+    fn reload_config(&mut self) -> Result<(), Error> {
+        // keep the current config if the new one cannot be parsed
+        let config = parse_gitrs_config()?;
+        self.state().config = config;
+        Ok(())
+    }
+
+    fn open_config_file(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> Result<(), Error> {
+        let path = config_path()?;
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, DEFAULT_CONFIG)?;
+            let message = format!("created {} from the default config", path.display());
+            self.notif(NotifChannel::Echo, Some(message));
+        }
+        let editor = std::env::var("EDITOR")
+            .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+        let command = format!(r#"{} "{}""#, editor, path.display());
+        self.run_command(terminal, &CommandType::Sync, command, None, None, None)?;
+        self.reload_config()
+    }
+
     fn search_regex(&self) -> Result<Regex, Error> {
-        let search_string = self.get_state().search_string.clone();
-        let is_case_sensitive = match self.get_state().config.smart_case {
-            true => search_string.chars().any(|c| c.is_uppercase()),
-            false => true,
+        self.case_regex(&self.get_state().search_string)
+    }
+
+    // regex following `search_case`, unless `\c` or `\C` is in `pattern`
+    fn case_regex(&self, pattern: &str) -> Result<Regex, Error> {
+        let (search_string, inline_case) = strip_case_flags(pattern);
+        // `\c` and `\C` in the search override the option
+        let is_case_sensitive = match inline_case.unwrap_or(self.get_state().config.search_case) {
+            SearchCase::Smart => search_string.chars().any(|c| c.is_uppercase()),
+            SearchCase::Sensitive => true,
+            SearchCase::Insensitive => false,
         };
         let regex = RegexBuilder::new(&search_string)
             .case_insensitive(!is_case_sensitive)
@@ -93,7 +154,7 @@ pub trait GitApp {
     fn continue_search(&mut self, mut idx: usize) -> Result<(), Error> {
         let regex = self.search_regex()?;
         loop {
-            let line = match self.get_text_line(idx) {
+            let line = match self.get_search_line(idx) {
                 None => {
                     if !self.loaded() {
                         // if not fully loaded yet, we need to continue the search
@@ -138,7 +199,7 @@ pub trait GitApp {
                 }
                 false => idx += 1,
             }
-            let line = match self.get_text_line(idx) {
+            let line = match self.get_search_line(idx) {
                 None => {
                     if !self.loaded() {
                         assert!(!reversed);
@@ -162,6 +223,62 @@ pub trait GitApp {
         }
     }
 
+    fn reset_search_count(&mut self) {
+        self.state().search_matches.clear();
+        self.state().search_scanned = 0;
+    }
+
+    fn update_search_count(&mut self) {
+        let state = self.get_state();
+        if state.search_string.is_empty() || state.input_state == InputState::Search {
+            self.notif(NotifChannel::Matches, None);
+            return;
+        }
+        let regex = match self.search_regex() {
+            Ok(regex) => regex,
+            Err(_) => return,
+        };
+
+        // only scan the lines that were not counted yet, a chunk at a time
+        let first = self.get_state().search_scanned;
+        // checked before scanning, lines may be loaded in between
+        let loaded = self.loaded();
+        let mut complete = false;
+        let mut idx = first;
+        let mut new_matches = Vec::new();
+        while idx < first + SEARCH_COUNT_CHUNK {
+            match self.get_search_line(idx) {
+                Some(line) => {
+                    if regex.is_match(&line) {
+                        new_matches.push(idx);
+                    }
+                    idx += 1;
+                }
+                None => {
+                    complete = loaded;
+                    break;
+                }
+            }
+        }
+        self.state().search_scanned = idx;
+        self.state().search_matches.extend(new_matches);
+
+        let matches = &self.get_state().search_matches;
+        let mut message = if matches.is_empty() {
+            "no match".to_string()
+        } else {
+            match self.idx().map(|idx| matches.binary_search(&idx)) {
+                Ok(Ok(position)) => format!("match {} of {}", position + 1, matches.len()),
+                _ => format!("{} matches", matches.len()),
+            }
+        };
+        // more lines are still being loaded or counted
+        if !complete {
+            message.push_str(" (so far)");
+        }
+        self.notif(NotifChannel::Matches, Some(message));
+    }
+
     fn buttons(&self) -> Vec<Button> {
         let config = &self.get_state().config;
         if !config.menu_bar {
@@ -181,133 +298,321 @@ pub trait GitApp {
         buttons
     }
 
+    fn wraps_lines(&self) -> bool {
+        false
+    }
+
+    // first and last lines displayed between two screen positions, for views supporting
+    // `mouse_copy`
+    fn lines_between(&self, _anchor: Position, _current: Position) -> Option<(usize, usize)> {
+        None
+    }
+
+    // first and last lines of the visual mode
+    fn visual_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.get_state().visual_anchor?;
+        let idx = self.get_state().list_state.selected()?;
+        Some((min(anchor, idx), anchor.max(idx)))
+    }
+
+    // paint the rows of the visual range, rows being laid out as in `highlight_search`
+    fn highlight_visual(&self, frame: &mut Frame, rect: Rect) {
+        let Some((first, last)) = self.visual_range() else {
+            return;
+        };
+        let wrap = self.wraps_lines();
+        let height = rect.height as usize;
+        let mut idx = self.get_state().list_state.offset();
+        let mut row = 0;
+        while row < height && idx <= last {
+            let rows = match (wrap, self.get_search_line(idx)) {
+                (true, Some(line)) => {
+                    let chars: Vec<char> = line.chars().collect();
+                    let positions = wrap_positions(&chars, Some(rect.width as usize));
+                    positions.last().map_or(1, |(last_row, _)| last_row + 1)
+                }
+                _ => 1,
+            };
+            if idx >= first {
+                let rows_rect = Rect {
+                    y: rect.y + row as u16,
+                    height: rows.min(height - row) as u16,
+                    ..rect
+                };
+                frame
+                    .buffer_mut()
+                    .set_style(rows_rect, mouse_selection_style());
+            }
+            row += rows;
+            idx += 1;
+        }
+    }
+
+    fn copy_mouse_selection(&mut self, anchor: Position, current: Position) -> Result<(), Error> {
+        let Some((first, last)) = self.lines_between(anchor, current) else {
+            return Ok(());
+        };
+        let text = (first..=last)
+            .filter_map(|idx| self.get_copy_line(idx))
+            .collect::<Vec<String>>()
+            .join("\n");
+        copy_to_clipboard(&text, &self.get_state().config)?;
+        let message = match last - first {
+            0 => "copied 1 line".to_string(),
+            count => format!("copied {} lines", count + 1),
+        };
+        self.notif(NotifChannel::Echo, Some(message));
+        Ok(())
+    }
+
     fn highlight_search(&self, frame: &mut Frame, rect: Rect) {
+        // drawn by all the views along with the search results
+        self.highlight_visual(frame, rect);
         if self.get_state().search_string.is_empty() || rect.width == 0 {
             return;
         }
-        let first = self.get_state().list_state.offset();
-        let last = first + rect.height as usize;
-        if let Ok(regex) = self.search_regex() {
-            for idx in first..last {
-                if let Some(line) = self.get_text_line(idx) {
-                    for mat in regex.find_iter(&line) {
-                        let match_start = mat.start() as u16;
-                        let match_width = (mat.end() - mat.start()) as u16;
-                        if match_start >= rect.width {
-                            // result too far on the right
-                            continue;
+        let regex = match self.search_regex() {
+            Ok(regex) => regex,
+            Err(_) => return,
+        };
+        let wrap = self.wraps_lines();
+        let width = rect.width as usize;
+        let height = rect.height as usize;
+
+        let mut idx = self.get_state().list_state.offset();
+        let mut row = 0;
+        while row < height {
+            let line = match self.get_search_line(idx) {
+                Some(line) => line,
+                None => break,
+            };
+            let chars: Vec<char> = line.chars().collect();
+            // columns in terminal cells, wide chars taking two of them
+            let positions = wrap_positions(&chars, wrap.then_some(width));
+            for mat in regex.find_iter(&line) {
+                let start = line[..mat.start()].chars().count();
+                let end = start + mat.as_str().chars().count();
+                // a match may be split over several rows when lines are wrapped
+                let mut segment = start;
+                while segment < end {
+                    let (line_row, x) = positions[segment];
+                    let segment_row = row + line_row;
+                    if x >= width || segment_row >= height {
+                        // result too far on the right or below
+                        break;
+                    }
+                    // chars of the same row fully displayed
+                    let mut segment_end = segment;
+                    let mut segment_width = 0;
+                    while segment_end < end && positions[segment_end].0 == line_row {
+                        let char_width = chars[segment_end].width().unwrap_or(0);
+                        if x + segment_width + char_width > width {
+                            break;
                         }
-                        let x = match_start;
-                        let x2 = min(x + match_width, rect.width);
-                        let width = x2 - x;
-
-                        let draw_rect = Rect {
-                            x: rect.x + x,
-                            y: rect.y + (idx - first) as u16,
-                            width,
-                            height: 1,
-                        };
-                        frame.render_widget(Clear, draw_rect);
-                        frame.render_widget(
-                            Paragraph::new(mat.as_str()).style(search_highlight_style()),
-                            draw_rect,
-                        );
+                        segment_width += char_width;
+                        segment_end += 1;
                     }
+                    if segment_end == segment {
+                        break;
+                    }
+                    let draw_rect = Rect {
+                        x: rect.x + x as u16,
+                        y: rect.y + segment_row as u16,
+                        width: segment_width as u16,
+                        height: 1,
+                    };
+                    let text: String = chars[segment..segment_end].iter().collect();
+                    frame.render_widget(Clear, draw_rect);
+                    frame.render_widget(
+                        Paragraph::new(text).style(search_highlight_style()),
+                        draw_rect,
+                    );
+                    segment = segment_end;
                 }
             }
+            row += match wrap {
+                true => positions.last().map_or(1, |(last_row, _)| last_row + 1),
+                false => 1,
+            };
+            idx += 1;
         }
     }
 
     fn run(
         &mut self,
         terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> Result<(), Error> {
+        let result = self.event_loop(terminal);
+        // also on failure, not to leave the outer view in the directory of this one
+        let exited = self.on_exit();
+        result.and(exited)
+    }
+
+    fn event_loop(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     ) -> Result<(), Error> {
         let mut notif_time = 0;
+        let mut was_animated = false;
         loop {
-            terminal.draw(|frame| {
-                let mut chunk = frame.area();
-                let region_to_action = display_menu_bar(
-                    &self.buttons(),
-                    self.get_state().mouse_position,
-                    self.get_state().mouse_down,
-                    &mut chunk,
-                    frame,
-                );
-
-                self.draw(frame, chunk);
-
-                let state = self.get_state();
-
-                let mut edit_bar_rect = Rect::default();
-                if state.input_state != InputState::App {
-                    let edit_string = match state.input_state {
-                        InputState::Search => &state.search_string,
-                        InputState::Command => &state.command_string,
-                        InputState::App => "",
-                    };
-                    let edit_line_prefix = match state.input_state {
-                        InputState::Search => match state.search_reverse {
-                            false => "/",
-                            true => "?",
-                        },
-                        InputState::Command => ":",
-                        InputState::App => "",
-                    };
-                    edit_bar_rect = display_edit_bar(
-                        edit_string,
-                        edit_line_prefix,
-                        state.edit_cursor,
-                        &mut chunk,
-                        frame,
-                    );
+            let scanned = self.get_state().search_scanned;
+            self.update_search_count();
+            // loading views and searches are animated, otherwise idle loops do not draw
+            let searching = self.get_state().current_search_idx.is_some()
+                || self.get_state().search_scanned != scanned;
+            let animated = !self.loaded() || searching;
+            // one last frame once loaded, for the lines read after the previous one
+            if self.get_state().dirty || animated || was_animated || self.needs_redraw() {
+                self.state().dirty = false;
+                self.draw_frame(terminal, notif_time)?;
+                if animated {
+                    notif_time = (notif_time + 1) % SPINNER_FRAMES.len();
                 }
-
-                display_notifications(
-                    &state.notif,
-                    SPINNER_FRAMES[notif_time],
-                    self.loaded(),
-                    &mut chunk,
-                    frame,
-                );
-                notif_time = (notif_time + 1) % SPINNER_FRAMES.len();
-
-                self.state().edit_bar_rect = edit_bar_rect;
-                self.state().region_to_action = region_to_action;
-            })?;
+            }
+            was_animated = animated;
 
             // continue search if one is active
             if let Some(search_idx) = self.state().current_search_idx {
                 self.continue_search(search_idx)?;
             }
 
-            let opt_action = match self.handle_event() {
-                Err(err) => {
-                    self.notif(NotifChannel::Error, Some(err.to_string()));
-                    None
-                }
-                Ok(opt_action) => opt_action,
+            let opt_action = match self.pending_chord_action() {
+                Some(action) => Some(action),
+                None => match self.handle_event() {
+                    Err(err) => {
+                        self.notif(NotifChannel::Error, Some(err.to_string()));
+                        None
+                    }
+                    Ok(opt_action) => opt_action,
+                },
             };
 
             if let Some(action) = opt_action {
+                self.state().dirty = true;
+                // `120G` and `120gg` go to the line
+                let action = match (self.get_state().count, action) {
+                    (Some(count), Action::First | Action::Last) => Action::GoTo(count - 1),
+                    (_, action) => action,
+                };
+                let confirmed = std::mem::take(&mut self.state().confirmed);
+                if !confirmed {
+                    if let Some(prompt) = self.confirm_prompt(&action) {
+                        self.state().pending_action = Some((action, prompt));
+                        self.state().input_state = InputState::Confirm;
+                        // the prompt may come from a command typed in the edit bar
+                        self.state().edit_cursor = 0;
+                        self.state().count = None;
+                        continue;
+                    }
+                }
                 // stop search in case there is a new action
                 self.state().current_search_idx = None;
+                if !action.is_motion() {
+                    // the displayed lines may change, count matches again
+                    self.reset_search_count();
+                }
                 if let Err(err) = self.run_action(&action, terminal) {
                     self.notif(NotifChannel::Error, Some(err.to_string()))
                 }
+                // actions not using the count drop it
+                self.state().count = None;
                 if self.state().quit {
                     break;
                 }
             }
 
             // display key combination if multiple letters
-            let key_combination = self.state().key_combination.clone();
-            if self.state().notif.is_empty() && !key_combination.is_empty() {
+            let key_combination = match self.get_state().count {
+                Some(count) => format!("{}{}", count, self.get_state().key_combination),
+                None => self.get_state().key_combination.clone(),
+            };
+            // updated while more keys or digits are typed
+            let displayed = self
+                .get_state()
+                .notif
+                .keys()
+                .all(|c| *c == NotifChannel::Keys);
+            if displayed && !key_combination.is_empty() {
                 let message = format!("keys: {}", key_combination);
                 self.notif(NotifChannel::Keys, Some(message));
             }
         }
-        self.on_exit()?;
+        Ok(())
+    }
+
+    fn draw_frame<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        notif_time: usize,
+    ) -> Result<(), Error> {
+        terminal.draw(|frame| {
+            let mut chunk = frame.area();
+            let region_to_action = if self.get_state().config.menu_bar {
+                display_menu_bar(
+                    &self.buttons(),
+                    self.get_state().mouse_position,
+                    self.get_state().mouse_down,
+                    self.get_state().config.menu_bar_position,
+                    &mut chunk,
+                    frame,
+                )
+            } else {
+                Vec::new()
+            };
+
+            // nothing fits, transient while the terminal is resized
+            if !chunk.is_empty() {
+                self.draw(frame, chunk);
+            }
+
+            let state = self.get_state();
+
+            let mut edit_bar_rect = Rect::default();
+            if state.input_state != InputState::App {
+                let edit_string = match state.input_state {
+                    InputState::Search => &state.search_string,
+                    InputState::Command
+                    | InputState::MultiLine(_)
+                    | InputState::StashMessage { .. }
+                    | InputState::TagName
+                    | InputState::CommitRev
+                    | InputState::BlameRev
+                    | InputState::StagePattern
+                    | InputState::FilterLines
+                    | InputState::Palette => &state.command_string,
+                    InputState::Confirm | InputState::App => "",
+                };
+                let display = match state.input_state {
+                    InputState::MultiLine(_) => display_multi_line_edit,
+                    _ => display_edit_bar,
+                };
+                edit_bar_rect = display(
+                    edit_string,
+                    state.edit_line_prefix(),
+                    state.edit_cursor,
+                    &mut chunk,
+                    frame,
+                );
+            }
+
+            display_notifications(
+                &state.notif,
+                SPINNER_FRAMES[notif_time],
+                self.loaded(),
+                &mut chunk,
+                frame,
+            );
+
+            if let Some(palette) = &mut self.state().palette {
+                palette.draw(frame, chunk);
+            }
+            if let Some(help) = &mut self.state().help {
+                help.draw(frame, chunk);
+            }
 
+            self.state().edit_bar_rect = edit_bar_rect;
+            self.state().region_to_action = region_to_action;
+        })?;
         Ok(())
     }
 
@@ -315,7 +620,19 @@ pub trait GitApp {
         let input_state = self.state().input_state.clone();
         match input_state {
             InputState::Search => self.state().search_string.clear(),
-            InputState::Command => self.state().command_string.clear(),
+            InputState::Command
+            | InputState::MultiLine(_)
+            | InputState::StashMessage { .. }
+            | InputState::TagName
+            | InputState::CommitRev
+            | InputState::BlameRev
+            | InputState::StagePattern
+            | InputState::FilterLines => self.state().command_string.clear(),
+            InputState::Palette => {
+                self.state().command_string.clear();
+                self.state().palette = None;
+            }
+            InputState::Confirm => self.state().pending_action = None,
             InputState::App => (),
         }
         self.state().edit_cursor = 0;
@@ -330,8 +647,16 @@ pub trait GitApp {
     ) -> Result<(), Error> {
         match action {
             Action::Reload => self.reload()?,
-            Action::Up => self.state().list_state.select_previous(),
-            Action::Down => self.state().list_state.select_next(),
+            Action::Up | Action::Down => {
+                let count = self.state().count.take().unwrap_or(1);
+                let list_state = &mut self.state().list_state;
+                let idx = match (action, list_state.selected()) {
+                    (_, None) => 0,
+                    (Action::Up, Some(idx)) => idx.saturating_sub(count),
+                    (_, Some(idx)) => idx.saturating_add(count),
+                };
+                list_state.select(Some(idx));
+            }
             Action::First => self.state().list_state.select_first(),
             Action::Last => self.state().list_state.select_last(),
             Action::Quit => self.state().quit = true,
@@ -364,29 +689,182 @@ pub trait GitApp {
                 self.state().search_string = "".to_string();
                 self.state().search_reverse = false;
                 self.state().edit_cursor = 0;
+                self.state().history_idx = None;
                 self.state().input_state = InputState::Search;
             }
             Action::SearchReverse => {
                 self.state().search_string = "".to_string();
                 self.state().search_reverse = true;
                 self.state().edit_cursor = 0;
+                self.state().history_idx = None;
                 self.state().input_state = InputState::Search;
             }
+            Action::ToggleSearchCase => {
+                let search_case = match self.get_state().config.search_case {
+                    SearchCase::Smart => SearchCase::Sensitive,
+                    SearchCase::Sensitive => SearchCase::Insensitive,
+                    SearchCase::Insensitive => SearchCase::Smart,
+                };
+                self.state().config.search_case = search_case;
+                let message = format!("search case: {}", search_case);
+                self.notif(NotifChannel::Echo, Some(message));
+            }
             Action::TypeCommand => {
                 self.state().edit_cursor = 0;
+                self.state().history_idx = None;
                 self.state().command_string = "".to_string();
                 self.state().input_state = InputState::Command;
             }
             Action::NextSearchResult => self.search_result(false)?,
             Action::PreviousSearchResult => self.search_result(true)?,
             Action::GoTo(line) => self.state().list_state.select(Some(*line)),
+            Action::GoToRelative(offset) => {
+                let list_state = &mut self.state().list_state;
+                let idx = list_state.selected().unwrap_or(0);
+                list_state.select(Some(idx.saturating_add_signed(*offset)));
+            }
             Action::None => (),
             Action::Echo(message) => {
                 self.notif(NotifChannel::Echo, Some(format!("echo: {}", message)))
             }
+            Action::EchoCommand(command) => {
+                let (file, rev, line) = self.get_file_rev_line()?;
+                let output = self.command_output(command.to_string(), file, rev, line)?;
+                // on a single line, cut to the width of the terminal
+                let mut message = String::new();
+                let mut width = 0;
+                let max_width = terminal.size()?.width as usize;
+                for c in output
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .chars()
+                {
+                    width += c.width().unwrap_or(0);
+                    if width > max_width {
+                        message.pop();
+                        message.push('…');
+                        break;
+                    }
+                    message.push(c);
+                }
+                self.notif(NotifChannel::Echo, Some(message));
+            }
             Action::Map(line) => self.state().config.parse_map_line(line, false)?,
-            Action::Set(line) => self.state().config.parse_set_line(line)?,
+            Action::Set(line) => {
+                self.state().config.parse_set_line(line)?;
+                match line.split_whitespace().next() {
+                    Some("wrap") => self.state().wrap = self.get_state().config.wrap,
+                    // re-highlight the syntax-highlighted views
+                    Some("theme" | "syntax") => self.reload()?,
+                    _ => (),
+                }
+            }
             Action::Button(line) => self.state().config.parse_button_line(line, false)?,
+            Action::OpenConfigFile => self.open_config_file(terminal)?,
+            Action::ReloadConfig => {
+                self.reload_config()?;
+                let message = format!("reloaded {}", config_path()?.display());
+                self.notif(NotifChannel::Echo, Some(message));
+            }
+            Action::Help => {
+                let scopes = [
+                    self.get_mapping_fields().as_slice(),
+                    &[MappingScope::Global],
+                ]
+                .concat();
+                let help = Help::new(&self.get_state().config, &scopes);
+                self.state().help = Some(help);
+            }
+            Action::CommandPalette => {
+                let scopes = [
+                    self.get_mapping_fields().as_slice(),
+                    &[MappingScope::Global],
+                ]
+                .concat();
+                let palette = Palette::new(&self.get_state().config, &scopes, &self.buttons());
+                self.state().palette = Some(palette);
+                self.state().command_string.clear();
+                self.state().edit_cursor = 0;
+                self.state().input_state = InputState::Palette;
+            }
+            Action::EditFile => {
+                let (file, _, line) = self.get_file_rev_line()?;
+                let file = file.ok_or_else(|| Error::Global("no file on this line".to_string()))?;
+                let command = editor_command(&self.get_state().config, &file);
+                // `run_command` reloads the view once the editor exits
+                self.run_command(
+                    terminal,
+                    &CommandType::Sync,
+                    command,
+                    Some(file),
+                    None,
+                    Some(line.unwrap_or(1)),
+                )?;
+            }
+            Action::CopyCommit => {
+                let (_, rev, _) = self.get_file_rev_line()?;
+                let rev = rev.ok_or_else(|| Error::Global("no commit on this line".to_string()))?;
+                copy_to_clipboard(&rev, &self.get_state().config)?;
+                self.notif(NotifChannel::Echo, Some(format!("copied {}", rev)));
+            }
+            Action::ToggleVisual => {
+                let idx = self.idx()?;
+                let state = self.state();
+                state.visual_anchor = match state.visual_anchor {
+                    Some(_) => None,
+                    None => Some(idx),
+                };
+            }
+            Action::CopyLine => {
+                let (first, last) = match self.visual_range() {
+                    Some(range) => range,
+                    None => (self.idx()?, self.idx()?),
+                };
+                let lines: Vec<String> = (first..=last)
+                    .filter_map(|idx| self.get_copy_line(idx))
+                    .collect();
+                if lines.is_empty() {
+                    return Err(Error::StateIndex);
+                }
+                copy_to_clipboard(&lines.join("\n"), &self.get_state().config)?;
+                self.state().visual_anchor = None;
+                let message = match lines.len() {
+                    1 => "copied 1 line".to_string(),
+                    count => format!("copied {} lines", count),
+                };
+                self.notif(NotifChannel::Echo, Some(message));
+            }
+            Action::CopyPath => {
+                // views run from the repository root, paths are relative to it
+                let (file, _, _) = self.get_file_rev_line()?;
+                let file = file.ok_or_else(|| Error::Global("no file on this line".to_string()))?;
+                copy_to_clipboard(&file, &self.get_state().config)?;
+                self.notif(NotifChannel::Echo, Some(format!("copied {}", file)));
+            }
+            Action::CopyLineRef => {
+                let (file, rev, line) = self.get_file_rev_line()?;
+                let file = file.ok_or_else(|| Error::Global("no file on this line".to_string()))?;
+                // `rev:file:line`, without the parts the line does not tell
+                let reference = [rev, Some(file), line.map(|line| line.to_string())]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(":");
+                copy_to_clipboard(&reference, &self.get_state().config)?;
+                self.notif(NotifChannel::Echo, Some(format!("copied {}", reference)));
+            }
+            Action::Push(parameters) => {
+                self.run_git_remote_command(terminal, "push", parameters)?
+            }
+            Action::Pull(parameters) => {
+                self.run_git_remote_command(terminal, "pull", parameters)?
+            }
+            Action::Fetch(parameters) => {
+                self.run_git_remote_command(terminal, "fetch", parameters)?
+            }
+            Action::CherryPick => self.apply_commit(terminal, "cherry-pick")?,
+            Action::Revert => self.apply_commit(terminal, "revert")?,
             Action::OpenGitShow | Action::OpenShowApp | Action::OpenLogApp => {
                 let (_, rev, _) = self.get_file_rev_line()?;
                 if let Some(rev) = rev {
@@ -404,6 +882,26 @@ pub trait GitApp {
                     terminal.clear()?;
                 };
             }
+            Action::OpenDiff => {
+                let (file, rev, _) = self.get_file_rev_line()?;
+                let file = file.ok_or_else(|| Error::Global("no file selected".to_string()))?;
+                // changes of the file in the commit, or in the working tree
+                let command = match rev {
+                    Some(rev) => PagerCommand::Show(vec![rev, "--".to_string(), file]),
+                    None => PagerCommand::Diff(vec!["--".to_string(), file]),
+                };
+                terminal.clear()?;
+                PagerApp::new(Some(command))?.run(terminal)?;
+                terminal.clear()?;
+            }
+            Action::FileHistory => {
+                let (file, _, _) = self.get_file_rev_line()?;
+                let file = file.ok_or_else(|| Error::Global("no file selected".to_string()))?;
+                let args = ["--follow", "--oneline", "--", &file].map(String::from);
+                terminal.clear()?;
+                PagerApp::new(Some(PagerCommand::Log(args.to_vec())))?.run(terminal)?;
+                terminal.clear()?;
+            }
             action => {
                 return Err(Error::Global(format!(
                     "cannot run `{:?}` in this context",
@@ -417,10 +915,15 @@ pub trait GitApp {
     fn handle_event(&mut self) -> Result<Option<Action>, Error> {
         if event::poll(std::time::Duration::from_millis(100))? {
             let event = event::read()?;
+            self.state().dirty = true;
             match event {
                 // Keyboard
                 Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                     self.state().notif = HashMap::new();
+                    if self.get_state().help.is_some() {
+                        self.handle_help_key(key_event);
+                        return Ok(None);
+                    }
                     let input_state = self.state().input_state.clone();
                     return if input_state == InputState::App {
                         Ok(self.handle_key_event(key_event)?)
@@ -430,15 +933,52 @@ pub trait GitApp {
                 }
                 // Mouse
                 Event::Mouse(mouse_event) => {
+                    let previous_position = self.get_state().mouse_position;
                     self.state().mouse_position =
                         Position::new(mouse_event.column, mouse_event.row);
+                    let mouse_copy = self.get_state().config.mouse_copy
+                        && self.get_state().input_state == InputState::App;
                     match mouse_event.kind {
+                        MouseEventKind::Drag(MouseButton::Left) if mouse_copy => {
+                            let state = self.state();
+                            let anchor = match state.mouse_selection {
+                                Some((anchor, _)) => anchor,
+                                None => previous_position,
+                            };
+                            state.mouse_selection = Some((anchor, state.mouse_position));
+                        }
+                        MouseEventKind::Up(_) => {
+                            self.state().mouse_down = false;
+                            if let Some((anchor, current)) = self.state().mouse_selection.take() {
+                                self.copy_mouse_selection(anchor, current)?;
+                            }
+                        }
                         MouseEventKind::Down(mouse_button) => {
                             return self.handle_click_event(mouse_button)
                         }
-                        MouseEventKind::Up(_) => self.state().mouse_down = false,
-                        MouseEventKind::ScrollUp => self.on_scroll(false),
-                        MouseEventKind::ScrollDown => self.on_scroll(true),
+                        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+                            if self.get_state().help.is_some() =>
+                        {
+                            let step = self.get_state().config.mouse_scroll_step() as isize;
+                            let down = (mouse_event.kind == MouseEventKind::ScrollDown)
+                                != self.get_state().config.natural_scroll;
+                            if let Some(help) = &mut self.state().help {
+                                help.scroll(if down { step } else { -step });
+                            }
+                        }
+                        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                            // mappings follow the wheel, not the direction of the content
+                            let wheel_down = mouse_event.kind == MouseEventKind::ScrollDown;
+                            let mapping = match wheel_down {
+                                true => "<scrolldown>",
+                                false => "<scrollup>",
+                            };
+                            let down = wheel_down != self.get_state().config.natural_scroll;
+                            match self.mouse_binding(mapping) {
+                                Some(action) => return Ok(Some(action)),
+                                None => self.on_scroll(down),
+                            }
+                        }
                         _ => (),
                     };
                 }
@@ -448,7 +988,47 @@ pub trait GitApp {
         Ok(None)
     }
 
+    fn handle_help_key(&mut self, key_event: KeyEvent) {
+        let Some(help) = &mut self.state().help else {
+            return;
+        };
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::F(1) => self.state().help = None,
+            KeyCode::Char('j') | KeyCode::Down => help.scroll(1),
+            KeyCode::Char('k') | KeyCode::Up => help.scroll(-1),
+            KeyCode::Char('d') if ctrl => help.scroll(help.page()),
+            KeyCode::Char('u') if ctrl => help.scroll(-help.page()),
+            KeyCode::PageDown | KeyCode::Char(' ') => help.scroll(help.page()),
+            KeyCode::PageUp => help.scroll(-help.page()),
+            KeyCode::Char('g') | KeyCode::Home => help.scroll(isize::MIN),
+            KeyCode::Char('G') | KeyCode::End => help.scroll(isize::MAX),
+            _ => (),
+        }
+    }
+
+    // queued action, or action of a mapping shadowed by longer ones once the chord timed out
+    fn pending_chord_action(&mut self) -> Option<Action> {
+        if let Some(action) = self.state().queued_action.take() {
+            return Some(action);
+        }
+        let timeout = Duration::from_millis(self.get_state().config.chord_timeout_ms);
+        match &self.get_state().pending_chord {
+            Some((_, time)) if time.elapsed() >= timeout => {
+                self.state().key_combination.clear();
+                self.state().pending_chord.take().map(|(action, _)| action)
+            }
+            _ => None,
+        }
+    }
+
     fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<Option<Action>, Error> {
+        // escape leaves the visual mode before running its mapping
+        if key_event.code == KeyCode::Esc && self.get_state().visual_anchor.is_some() {
+            self.state().visual_anchor = None;
+            self.state().key_combination.clear();
+            return Ok(None);
+        }
         let mut key_str = match key_event.code {
             KeyCode::Up => "up".to_string(),
             KeyCode::Down => "down".to_string(),
@@ -479,63 +1059,300 @@ pub trait GitApp {
         }
 
         let mut potential = false;
-        for field in [
+        let mut matched = None;
+        let mut fallback = None;
+        let fields = [
             self.get_mapping_fields().as_slice(),
             &[MappingScope::Global],
         ]
-        .concat()
-        {
-            for (key_combination, action) in self.state().config.get_bindings(field) {
+        .concat();
+        for field in fields.iter() {
+            for (key_combination, action) in self.state().config.get_bindings(field.clone()) {
+                if key_combination == "<any>" {
+                    fallback = fallback.or(Some(action));
+                    continue;
+                }
                 if action == Action::None {
                     continue;
                 }
                 if *key_combination == keys {
+                    matched = matched.or(Some(action));
+                } else if key_combination.starts_with(&keys) {
+                    potential = true;
+                }
+            }
+        }
+        let timeout = self.get_state().config.chord_timeout_ms;
+        match (matched, potential) {
+            // wait for a longer mapping, see `pending_chord_action`
+            (Some(action), true) if timeout > 0 => {
+                self.state().pending_chord = Some((action, Instant::now()));
+                return Ok(None);
+            }
+            (Some(action), _) => {
+                self.state().key_combination.clear();
+                self.state().pending_chord = None;
+                return Ok(Some(action));
+            }
+            (None, true) => return Ok(None),
+            (None, false) => {
+                // the typed keys do not lead to a longer mapping, fire the shorter one and
+                // handle the last key on its own
+                if let Some((action, _)) = self.state().pending_chord.take() {
                     self.state().key_combination.clear();
-                    return Ok(Some(action.clone()));
+                    self.state().queued_action = self.handle_key_event(key_event)?;
+                    return Ok(Some(action));
                 }
-                if key_combination.starts_with(&keys) {
-                    potential = true;
+                // digits not starting a mapping make up a count, a leading zero does not
+                if let KeyCode::Char(digit @ '0'..='9') = key_event.code {
+                    let count = self.get_state().count;
+                    if keys.len() == 1 && (digit != '0' || count.is_some()) {
+                        let digit = digit as usize - '0' as usize;
+                        let count = count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+                        self.state().count = Some(count);
+                        self.state().key_combination.clear();
+                        return Ok(None);
+                    }
                 }
             }
         }
-        if !potential {
-            self.state().key_combination.clear();
+        self.state().key_combination.clear();
+        self.state().count = None;
+        match fallback {
+            // `map <scope> <any> nop` explicitly ignores unbound keys
+            Some(Action::None) => (),
+            Some(action) => return Ok(Some(action)),
+            None => {
+                if self.get_state().config.unknown_key_hint {
+                    let message = format!("no binding for {} in {}", keys, fields[0]);
+                    self.notif(NotifChannel::Echo, Some(message));
+                }
+            }
         }
         Ok(None)
     }
 
+    fn confirm_prompt(&mut self, action: &Action) -> Option<String> {
+        // the missing config file is only created once accepted
+        if *action == Action::OpenConfigFile {
+            let path = config_path().ok()?;
+            let question = format!("create {} from the default config", path.display());
+            return (!path.exists()).then(|| format!("{}? (y/n)", question));
+        }
+        if !self.get_state().config.confirm_destructive || !action.needs_confirmation() {
+            return None;
+        }
+        let (file, rev, line) = self.get_file_rev_line().unwrap_or((None, None, None));
+        let question = match action {
+            Action::StashDrop => format!("drop {}", rev.unwrap_or_default()),
+            Action::BranchDelete => format!("delete branch {}", rev.unwrap_or_default()),
+            Action::RemoteFetch => format!("fetch {}", rev.unwrap_or_default()),
+            Action::DiscardFile => {
+                // untracked files are deleted
+                let untracked = matches!(
+                    self.get_mapping_fields().first(),
+                    Some(MappingScope::Status(_, Some(FileStatus::New)))
+                );
+                match untracked {
+                    true => format!("delete {}", file.unwrap_or_default()),
+                    false => format!("discard changes to {}", file.unwrap_or_default()),
+                }
+            }
+            Action::CherryPick => format!("cherry-pick {}", rev.unwrap_or_default()),
+            Action::Revert => format!("revert {}", rev.unwrap_or_default()),
+            Action::RemotePull | Action::RemotePush => {
+                let branch = git_current_branch(&self.get_state().config).unwrap_or_default();
+                match action {
+                    Action::RemotePull => {
+                        format!("pull {} from {}", branch, rev.unwrap_or_default())
+                    }
+                    _ => format!("push {} to {}", branch, rev.unwrap_or_default()),
+                }
+            }
+            Action::Command(_, command) | Action::EchoCommand(command) => {
+                format!(
+                    "run `{}`",
+                    self.expand_command(command.clone(), file, rev, line)
+                )
+            }
+            action => action.to_string(),
+        };
+        Some(format!("{}? (y/n)", question))
+    }
+
+    fn handle_confirm(&mut self, key_event: KeyEvent) -> Option<Action> {
+        match key_event.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let (action, _) = self.state().pending_action.take()?;
+                self.state().input_state = InputState::App;
+                self.state().confirmed = true;
+                Some(action)
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.exit_input_line();
+                None
+            }
+            _ => None,
+        }
+    }
+
     fn handle_line_edited(&mut self, key_event: KeyEvent) -> Result<Option<Action>, Error> {
         let input_state = self.state().input_state.clone();
+        if input_state == InputState::Confirm {
+            return Ok(self.handle_confirm(key_event));
+        }
         let mut cursor = self.get_state().edit_cursor;
 
         let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key_event.modifiers.contains(KeyModifiers::ALT);
+        let multi_line = matches!(input_state, InputState::MultiLine(_));
+        // multi-line inputs are submitted with ctrl-s or ctrl/alt-enter, enter inserting a new line
+        let submit = match multi_line {
+            true => {
+                (key_event.code == KeyCode::Enter && (ctrl || alt))
+                    || (key_event.code == KeyCode::Char('s') && ctrl)
+            }
+            false => key_event.code == KeyCode::Enter,
+        };
         let line = match input_state {
             InputState::Search => &mut self.state().search_string,
-            InputState::Command => &mut self.state().command_string,
-            InputState::App => return Ok(None),
+            InputState::Command
+            | InputState::MultiLine(_)
+            | InputState::StashMessage { .. }
+            | InputState::TagName
+            | InputState::CommitRev
+            | InputState::BlameRev
+            | InputState::StagePattern
+            | InputState::FilterLines
+            | InputState::Palette => &mut self.state().command_string,
+            InputState::Confirm | InputState::App => return Ok(None),
         };
         match key_event.code {
-            KeyCode::Enter => match input_state {
+            _ if submit => match input_state {
                 InputState::Command => {
                     let ret = match line.parse::<Action>() {
                         Ok(action) => Ok(Some(action)),
                         Err(error) => Err(error),
                     };
+                    let entry = line.clone();
+                    self.state().history.push(&input_state, &entry);
                     self.state().input_state = InputState::App;
                     return ret;
                 }
+                InputState::MultiLine(prompt) => {
+                    let message = std::mem::take(line).trim_end().to_string();
+                    self.state().edit_cursor = 0;
+                    self.state().input_state = InputState::App;
+                    if message.trim().is_empty() {
+                        return Err(Error::Global(
+                            "aborting commit due to empty commit message".to_string(),
+                        ));
+                    }
+                    return Ok(Some(match prompt {
+                        MultiLinePrompt::Amend => Action::Amend(message),
+                        MultiLinePrompt::Commit => Action::Commit(message),
+                    }));
+                }
+                InputState::StashMessage { include_untracked } => {
+                    let message = Some(std::mem::take(line));
+                    self.state().input_state = InputState::App;
+                    return Ok(Some(match include_untracked {
+                        true => Action::StashPushIncludeUntracked(message),
+                        false => Action::StashPush(message),
+                    }));
+                }
+                InputState::TagName => {
+                    let name = std::mem::take(line);
+                    self.state().input_state = InputState::App;
+                    return Ok(Some(Action::TagCreate(Some(name))));
+                }
+                InputState::CommitRev => {
+                    let rev = std::mem::take(line);
+                    self.state().input_state = InputState::App;
+                    return Ok(Some(Action::GoToCommit(Some(rev))));
+                }
+                InputState::BlameRev => {
+                    let rev = std::mem::take(line);
+                    self.state().input_state = InputState::App;
+                    return Ok(Some(Action::BlameAtRev(Some(rev))));
+                }
+                InputState::StagePattern => {
+                    let pattern = std::mem::take(line);
+                    self.state().input_state = InputState::App;
+                    return Ok(Some(Action::StageByPattern(Some(pattern))));
+                }
+                InputState::FilterLines => {
+                    let pattern = std::mem::take(line);
+                    self.state().input_state = InputState::App;
+                    return Ok(Some(Action::FilterLines(Some(pattern))));
+                }
+                InputState::Palette => {
+                    let action = self
+                        .get_state()
+                        .palette
+                        .as_ref()
+                        .and_then(|p| p.selected_action());
+                    self.exit_input_line();
+                    return Ok(action);
+                }
                 InputState::Search => {
+                    let entry = line.clone();
+                    self.state().history.push(&input_state, &entry);
                     self.state().input_state = InputState::App;
                     return Ok(Some(Action::NextSearchResult));
                 }
-                InputState::App => (),
+                InputState::Confirm | InputState::App => (),
             },
             KeyCode::Esc => self.exit_input_line(),
+            // the palette selection is moved instead of browsing the history
+            KeyCode::Up | KeyCode::Down | KeyCode::Tab | KeyCode::BackTab
+                if input_state == InputState::Palette =>
+            {
+                let delta = match key_event.code {
+                    KeyCode::Up | KeyCode::BackTab => -1,
+                    _ => 1,
+                };
+                if let Some(palette) = &mut self.state().palette {
+                    palette.select(delta);
+                }
+            }
+            // same column in the previous or next line
+            KeyCode::Up | KeyCode::Down if multi_line => {
+                let chars: Vec<char> = line.chars().collect();
+                let line_start = |end: usize| {
+                    chars[..end]
+                        .iter()
+                        .rposition(|&c| c == '\n')
+                        .map_or(0, |idx| idx + 1)
+                };
+                let line_end = |start: usize| {
+                    chars[start..]
+                        .iter()
+                        .position(|&c| c == '\n')
+                        .map_or(chars.len(), |idx| start + idx)
+                };
+                let start = line_start(cursor);
+                let column = cursor - start;
+                match key_event.code {
+                    KeyCode::Up if start > 0 => {
+                        let previous_start = line_start(start - 1);
+                        cursor = previous_start + column.min(start - 1 - previous_start);
+                    }
+                    KeyCode::Down => {
+                        let end = line_end(cursor);
+                        if end < chars.len() {
+                            cursor = end + 1 + column.min(line_end(end + 1) - end - 1);
+                        }
+                    }
+                    _ => (),
+                }
+                self.state().edit_cursor = cursor;
+            }
+            KeyCode::Up => self.recall_history(true),
+            KeyCode::Down => self.recall_history(false),
             KeyCode::Left => {
                 if !ctrl {
-                    if cursor > 0 {
-                        cursor -= 1;
-                    }
+                    cursor = cursor.saturating_sub(1);
                 } else {
                     let chars: Vec<char> = line.chars().collect();
                     while cursor > 0 && chars[cursor - 1].is_whitespace() {
@@ -585,7 +1402,11 @@ pub trait GitApp {
                     self.state().edit_cursor = cursor;
                 }
             }
-            KeyCode::Char(c) => {
+            code @ (KeyCode::Char(_) | KeyCode::Enter) => {
+                let c = match code {
+                    KeyCode::Char(c) => c,
+                    _ => '\n',
+                };
                 let mut new_line: Vec<char> = line.chars().collect();
                 let before = new_line.len();
                 new_line.insert(cursor, c);
@@ -598,30 +1419,67 @@ pub trait GitApp {
                 self.notif(NotifChannel::Error, Some(message));
             }
         }
+        let state = self.state();
+        if let Some(palette) = &mut state.palette {
+            palette.filter(&state.command_string);
+        }
         Ok(None)
     }
 
+    fn recall_history(&mut self, older: bool) {
+        let state = self.state();
+        let entries = state.history.entries(&state.input_state);
+        let idx = match (state.history_idx, older) {
+            (None, true) if entries.is_empty() => return,
+            (None, true) => Some(entries.len() - 1),
+            (None, false) => return,
+            (Some(idx), true) => Some(idx.saturating_sub(1)),
+            (Some(idx), false) => (idx + 1 < entries.len()).then_some(idx + 1),
+        };
+        let line = match state.input_state {
+            InputState::Search => &mut state.search_string,
+            InputState::Command => &mut state.command_string,
+            _ => return,
+        };
+        if state.history_idx.is_none() {
+            state.history_draft = line.clone();
+        }
+        *line = match idx {
+            Some(idx) => entries[idx].clone(),
+            None => std::mem::take(&mut state.history_draft),
+        };
+        state.edit_cursor = line.chars().count();
+        state.history_idx = idx;
+    }
+
     fn on_click(&mut self) {}
     fn handle_click_event(&mut self, mouse_button: MouseButton) -> Result<Option<Action>, Error> {
         // for the time being, cancel line inputs
         let input_state = self.get_state().input_state.clone();
         if input_state != InputState::App {
             let mouse_position = self.get_state().mouse_position;
+            let multi_line = matches!(input_state, InputState::MultiLine(_));
+            if multi_line && self.get_state().edit_bar_rect.contains(mouse_position) {
+                return Ok(None);
+            }
             if self.get_state().edit_bar_rect.contains(mouse_position) {
                 // TODO: line edit should be a proper object, this is not good
-                let cursor = mouse_position.x as usize;
+                let prefix_len = self.get_state().edit_line_prefix().chars().count();
+                let cursor = (mouse_position.x as usize).saturating_sub(prefix_len);
                 let line = match input_state {
                     InputState::Search => &self.state().search_string,
-                    InputState::Command => &self.state().command_string,
-                    InputState::App => return Ok(None),
-                };
-                self.state().edit_cursor = if cursor > line.chars().count() {
-                    line.chars().count()
-                } else if cursor <= 1 {
-                    0
-                } else {
-                    cursor - 1
+                    InputState::Command
+                    | InputState::MultiLine(_)
+                    | InputState::StashMessage { .. }
+                    | InputState::TagName
+                    | InputState::CommitRev
+                    | InputState::BlameRev
+                    | InputState::StagePattern
+                    | InputState::FilterLines
+                    | InputState::Palette => &self.state().command_string,
+                    InputState::Confirm | InputState::App => return Ok(None),
                 };
+                self.state().edit_cursor = min(cursor, line.chars().count());
             } else {
                 self.exit_input_line();
             }
@@ -636,34 +1494,38 @@ pub trait GitApp {
                 return Ok(Some(action));
             }
         }
+        // the clicked line is selected before running the mapped action
         self.on_click();
 
         let mapping = match mouse_button {
+            MouseButton::Left => "<lclick>",
+            MouseButton::Middle => "<mclick>",
             MouseButton::Right => "<rclick>",
-            _ => return Ok(None),
         };
+        Ok(self.mouse_binding(mapping))
+    }
 
+    fn mouse_binding(&self, mapping: &str) -> Option<Action> {
         for field in [
             self.get_mapping_fields().as_slice(),
             &[MappingScope::Global],
         ]
         .concat()
         {
-            for (key_combination, action) in self.state().config.get_bindings(field) {
+            for (key_combination, action) in self.get_state().config.get_bindings(field) {
                 if key_combination == mapping {
-                    return Ok(Some(action.clone()));
+                    return Some(action.clone());
                 }
             }
         }
-
-        Ok(None)
+        None
     }
 
     fn on_scroll(&mut self, down: bool);
     fn on_scroll_generic(&mut self, down: bool, height: usize, len: usize) {
-        let scroll_step = self.get_state().config.scroll_step;
+        let scroll_step = self.get_state().config.mouse_scroll_step();
         let scrolloff = self.get_state().config.scrolloff;
-        let mut index = self.idx().unwrap_or(0);
+        let index = self.idx().unwrap_or(0);
 
         let offset = self.state().list_state.offset_mut();
         match down {
@@ -677,27 +1539,17 @@ pub trait GitApp {
             }
         };
 
-        if *offset + scrolloff >= index {
-            index = *offset + scrolloff;
-        }
-        if index >= len {
-            index = len - 1;
-        }
-        if *offset + height > scrolloff && index >= *offset + height - scrolloff {
-            index = *offset + height - scrolloff - 1;
-        }
+        let index = adapt_index_in_frame(*offset, scrolloff, index, height, len);
         self.state().list_state.select(Some(index));
     }
 
-    fn run_command(
+    fn expand_command(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-        command_type: &CommandType,
         mut command: String,
         file: Option<String>,
         rev: Option<String>,
         line_number: Option<usize>,
-    ) -> Result<(), Error> {
+    ) -> String {
         if let Some(file) = file {
             command = command.replace("%(file)", &file);
         }
@@ -713,7 +1565,26 @@ pub trait GitApp {
             }
         }
         command = command.replace("%(clip)", &self.state().config.clipboard_tool);
-        command = command.replace("%(git)", &self.state().config.git_exe);
+        let config = &self.state().config;
+        let git = std::iter::once(&config.git_exe)
+            .chain(&config.git_args)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        command = command.replace("%(git)", &git);
+        command
+    }
+
+    fn run_command(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+        command_type: &CommandType,
+        command: String,
+        file: Option<String>,
+        rev: Option<String>,
+        line_number: Option<usize>,
+    ) -> Result<(), Error> {
+        let command = self.expand_command(command, file, rev, line_number);
 
         #[cfg(unix)]
         let shell = ("bash", "-c");
@@ -744,19 +1615,11 @@ pub trait GitApp {
                     .expect("Failed to execute command");
             }
             _ => {
-                disable_raw_mode()?;
-                execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-                execute!(stdout(), DisableMouseCapture)?;
-                terminal.show_cursor()?;
-
+                let alt_screen = self.get_state().config.alt_screen;
+                suspend_tui(terminal, alt_screen)?;
                 let mut child = proc.spawn()?;
                 child.wait()?;
-
-                enable_raw_mode()?;
-                execute!(stdout(), EnableMouseCapture)?;
-                execute!(stdout(), EnterAlternateScreen)?;
-                terminal.hide_cursor()?;
-                terminal.clear()?;
+                resume_tui(terminal, alt_screen)?;
             }
         }
 
@@ -768,4 +1631,445 @@ pub trait GitApp {
 
         Ok(())
     }
+
+    // standard output of a command, its standard error being the error when it fails
+    fn command_output(
+        &mut self,
+        command: String,
+        file: Option<String>,
+        rev: Option<String>,
+        line_number: Option<usize>,
+    ) -> Result<String, Error> {
+        let command = self.expand_command(command, file, rev, line_number);
+
+        #[cfg(unix)]
+        let shell = ("bash", "-c");
+
+        #[cfg(windows)]
+        let shell = ("cmd", "/C");
+
+        let output = Command::new(shell.0)
+            .args([shell.1, &command])
+            .stdin(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = stderr.lines().next().unwrap_or("command failed");
+            return Err(Error::Global(message.to_string()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn run_git_remote_command(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+        command: &str,
+        parameters: &str,
+    ) -> Result<(), Error> {
+        let config = &self.get_state().config;
+        // stderr being captured, git would not report its progress otherwise
+        let mut args = vec![command.to_string(), "--progress".to_string()];
+        if parameters.contains("%(branch)") {
+            let branch = git_current_branch(config)?;
+            args.extend(
+                parameters
+                    .split_whitespace()
+                    .map(|arg| arg.replace("%(branch)", &branch)),
+            );
+        } else {
+            args.extend(parameters.split_whitespace().map(String::from));
+        }
+
+        suspend_tui(terminal, config.alt_screen)?;
+        let result = git_remote_command(&args, config);
+        resume_tui(terminal, config.alt_screen)?;
+
+        // reloaded even when git fails, a failed pull possibly leaving conflicts, the error of git
+        // being reported rather than the one of the reload
+        let reloaded = self.reload();
+        self.notif(NotifChannel::Echo, Some(result?));
+        reloaded
+    }
+
+    // `git cherry-pick` or `git revert` of the selected commit
+    fn apply_commit(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+        command: &str,
+    ) -> Result<(), Error> {
+        let (_, rev, _) = self.get_file_rev_line()?;
+        let rev = rev.ok_or_else(|| Error::Global("no commit on this line".to_string()))?;
+        let mut args = vec![command.to_string(), rev.clone()];
+        if command == "revert" {
+            // the output is captured, an editor could not be used
+            args.insert(1, "--no-edit".to_string());
+        }
+
+        let config = &self.get_state().config;
+        suspend_tui(terminal, config.alt_screen)?;
+        let result = git_remote_command(&args, config);
+        resume_tui(terminal, config.alt_screen)?;
+
+        self.reload()?;
+        // the commit is left half applied on conflicts
+        let pending = format!("{}_HEAD", command.replace('-', "_").to_uppercase());
+        if result.is_err() && is_valid_git_rev(&pending) {
+            return Err(Error::Global(format!(
+                "{} of {} stopped on conflicts, resolve them in the status view then run `git {} --continue`",
+                command, rev, command
+            )));
+        }
+        self.notif(NotifChannel::Echo, Some(result?));
+        Ok(())
+    }
+}
+
+// remove the vim like `\c` (insensitive) and `\C` (sensitive) flags, the last one winning
+fn strip_case_flags(search: &str) -> (String, Option<SearchCase>) {
+    let mut stripped = String::with_capacity(search.len());
+    let mut case = None;
+    let mut chars = search.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            stripped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('c') => case = Some(SearchCase::Insensitive),
+            Some('C') => case = Some(SearchCase::Sensitive),
+            // other escapes are left to the regex
+            Some(escaped) => {
+                stripped.push(c);
+                stripped.push(escaped);
+            }
+            None => stripped.push(c),
+        }
+    }
+    (stripped, case)
+}
+
+fn suspend_tui(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    alt_screen: bool,
+) -> Result<(), Error> {
+    disable_raw_mode()?;
+    if alt_screen {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    }
+    execute!(stdout(), DisableMouseCapture)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+fn resume_tui(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    alt_screen: bool,
+) -> Result<(), Error> {
+    enable_raw_mode()?;
+    execute!(stdout(), EnableMouseCapture)?;
+    if alt_screen {
+        execute!(stdout(), EnterAlternateScreen)?;
+    }
+    terminal.hide_cursor()?;
+    terminal.clear()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{config::Config, git::git_clean};
+    use crate::views::{
+        blame::BlameApp,
+        branch::BranchApp,
+        pager::{PagerApp, PagerCommand},
+        remote::RemoteApp,
+        show::ShowApp,
+        stash::StashApp,
+        status::StatusApp,
+        tag::TagApp,
+        tree::TreeApp,
+    };
+    use ratatui::backend::TestBackend;
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process::Command,
+        sync::Mutex,
+    };
+
+    // the tests below change the working directory and the environment of the whole process
+    static PROCESS: Mutex<()> = Mutex::new(());
+
+    fn git(repo: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["-c", "user.name=gitrs", "-c", "user.email=gitrs@test"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    // repository with two commits, a tag, a branch, a stash and a remote, and the config file of
+    // the views
+    fn test_repo(name: &str, config: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("gitrs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let repo = dir.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        fs::write(dir.join("config"), config).unwrap();
+        env::set_var("GITRS_CONFIG", dir.join("config"));
+
+        git(&repo, &["init", "-q", "-b", "main"]);
+        fs::write(repo.join("main.rs"), "fn main() {}\n").unwrap();
+        git(&repo, &["add", "main.rs"]);
+        git(&repo, &["commit", "-q", "-m", "first"]);
+        fs::write(repo.join("main.rs"), "fn main() {\n}\n").unwrap();
+        git(&repo, &["commit", "-q", "-am", "second"]);
+        git(&repo, &["tag", "v1"]);
+        git(&repo, &["branch", "topic"]);
+        fs::write(repo.join("main.rs"), "fn main() {}\n").unwrap();
+        git(&repo, &["stash", "-q"]);
+        fs::write(repo.join("main.rs"), "fn main() { }\n").unwrap();
+        git(&repo, &["remote", "add", "origin", "../origin"]);
+        repo
+    }
+
+    fn draw_in_a_cell(app: &mut impl GitApp) {
+        let mut terminal = Terminal::new(TestBackend::new(1, 1)).unwrap();
+        for _ in 0..2 {
+            app.draw_frame(&mut terminal, 0).unwrap();
+        }
+        app.on_exit().unwrap();
+    }
+
+    #[test]
+    fn every_view_draws_in_a_single_cell() {
+        let _lock = PROCESS.lock().unwrap_or_else(|err| err.into_inner());
+        let original_dir = env::current_dir().unwrap();
+        for menu_bar in [true, false] {
+            let repo = test_repo("single-cell", &format!("set menu_bar {}\n", menu_bar));
+            env::set_current_dir(&repo).unwrap();
+
+            draw_in_a_cell(&mut StatusApp::new().unwrap());
+            draw_in_a_cell(&mut BlameApp::new("main.rs".to_string(), None, None).unwrap());
+            draw_in_a_cell(&mut ShowApp::new(None).unwrap());
+            for command in [
+                PagerCommand::Log(Vec::new()),
+                PagerCommand::Show(Vec::new()),
+                PagerCommand::Diff(Vec::new()),
+                PagerCommand::Reflog(Vec::new()),
+            ] {
+                draw_in_a_cell(&mut PagerApp::new(Some(command)).unwrap());
+            }
+            draw_in_a_cell(&mut StashApp::new().unwrap());
+            draw_in_a_cell(&mut BranchApp::new().unwrap());
+            draw_in_a_cell(&mut RemoteApp::new().unwrap());
+            draw_in_a_cell(&mut TagApp::new().unwrap());
+            draw_in_a_cell(&mut TreeApp::new().unwrap());
+
+            env::set_current_dir(&original_dir).unwrap();
+            fs::remove_dir_all(repo.parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn show_opened_from_log_restores_the_working_directory() {
+        let _lock = PROCESS.lock().unwrap_or_else(|err| err.into_inner());
+        let original_dir = env::current_dir().unwrap();
+        let repo = test_repo("nested", "").canonicalize().unwrap();
+        let subdir = repo.join("sub");
+        fs::create_dir(&subdir).unwrap();
+        env::set_current_dir(&subdir).unwrap();
+        let cwd = || env::current_dir().unwrap().canonicalize().unwrap();
+
+        let mut log = PagerApp::new(Some(PagerCommand::Log(Vec::new()))).unwrap();
+        assert_eq!(cwd(), repo);
+        let (_, rev, _) = log.get_file_rev_line().unwrap();
+
+        let mut show = ShowApp::new(rev).unwrap();
+        draw_in_a_cell(&mut show);
+        assert_eq!(cwd(), repo);
+        // a failing view leaves the directory of the one it was opened from
+        assert!(ShowApp::new(Some("unknown".to_string())).is_err());
+        assert_eq!(cwd(), repo);
+
+        log.on_exit().unwrap();
+        assert_eq!(cwd(), subdir);
+
+        env::set_current_dir(&original_dir).unwrap();
+        fs::remove_dir_all(repo.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn discarding_untracked_files_keeps_the_ignored_ones() {
+        let _lock = PROCESS.lock().unwrap_or_else(|err| err.into_inner());
+        let original_dir = env::current_dir().unwrap();
+        let repo = test_repo("clean", "").canonicalize().unwrap();
+        fs::write(repo.join(".gitignore"), "*.log\n").unwrap();
+        fs::create_dir(repo.join("build")).unwrap();
+        for file in ["build/out.txt", "build/run.log", "notes.txt"] {
+            fs::write(repo.join(file), "").unwrap();
+        }
+        env::set_current_dir(&repo).unwrap();
+
+        let config = Config::default();
+        git_clean("notes.txt", &config).unwrap();
+        git_clean("build/", &config).unwrap();
+        assert!(!repo.join("notes.txt").exists());
+        assert!(!repo.join("build/out.txt").exists());
+        assert!(repo.join("build/run.log").exists());
+
+        env::set_current_dir(&original_dir).unwrap();
+        fs::remove_dir_all(repo.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn config_is_looked_up_in_the_default_xdg_directory() {
+        let _lock = PROCESS.lock().unwrap_or_else(|err| err.into_inner());
+        let variables = ["GITRS_CONFIG", "XDG_CONFIG_HOME", "HOME"];
+        let saved = variables.map(env::var_os);
+        let home = env::temp_dir().join(format!("gitrs-home-{}", std::process::id()));
+        let xdg = home.join(".config").join("gitrs");
+        fs::create_dir_all(&xdg).unwrap();
+        env::remove_var("GITRS_CONFIG");
+        env::remove_var("XDG_CONFIG_HOME");
+        env::set_var("HOME", &home);
+
+        // created in the home directory when there is none
+        assert_eq!(config_path().unwrap(), home.join(".gitrsrc"));
+        fs::write(home.join(".gitrsrc"), "").unwrap();
+        fs::write(xdg.join("config"), "").unwrap();
+        assert_eq!(config_path().unwrap(), xdg.join("config"));
+        fs::remove_file(xdg.join("config")).unwrap();
+        assert_eq!(config_path().unwrap(), home.join(".gitrsrc"));
+
+        for (variable, value) in variables.iter().zip(saved) {
+            match value {
+                Some(value) => env::set_var(variable, value),
+                None => env::remove_var(variable),
+            }
+        }
+        fs::remove_dir_all(home).unwrap();
+    }
+
+    // lines drawn by `highlight_search` only
+    struct LinesApp {
+        state: AppState,
+        lines: Vec<String>,
+        wrap: bool,
+    }
+
+    impl GitApp for LinesApp {
+        fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+            self.highlight_search(frame, rect);
+        }
+        fn reload(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+        fn get_text_line(&self, idx: usize) -> Option<String> {
+            self.lines.get(idx).cloned()
+        }
+        fn state(&mut self) -> &mut AppState {
+            &mut self.state
+        }
+        fn get_state(&self) -> &AppState {
+            &self.state
+        }
+        fn get_mapping_fields(&self) -> Vec<MappingScope> {
+            Vec::new()
+        }
+        fn get_file_rev_line(&self) -> Result<FileRevLine, Error> {
+            Ok((None, None, None))
+        }
+        fn run_action(
+            &mut self,
+            _action: &Action,
+            _terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+        fn on_scroll(&mut self, _down: bool) {}
+        fn wraps_lines(&self) -> bool {
+            self.wrap
+        }
+    }
+
+    // highlighted cells of each row, with the chars they display
+    fn search_highlights(
+        line: &str,
+        search: &str,
+        wrap: bool,
+        width: u16,
+    ) -> Vec<(u16, u16, String)> {
+        let mut app = LinesApp {
+            state: AppState::with_config(Default::default(), Default::default()),
+            lines: vec![line.to_string()],
+            wrap,
+        };
+        app.state.search_string = search.to_string();
+        let mut terminal = Terminal::new(TestBackend::new(width, 3)).unwrap();
+        terminal
+            .draw(|frame| app.draw(frame, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut highlights = Vec::new();
+        for y in 0..3 {
+            for x in 0..width {
+                let cell = &buffer[(x, y)];
+                // the second cell of wide chars is left empty
+                if cell.bg == search_highlight_style().bg.unwrap() && !cell.symbol().is_empty() {
+                    highlights.push((x, y, cell.symbol().to_string()));
+                }
+            }
+        }
+        highlights
+    }
+
+    #[test]
+    fn search_highlight_after_wide_chars() {
+        let line = "日本語 text 漢字";
+        let highlights = search_highlights(line, "text", false, 40);
+        let expected: Vec<(u16, u16, String)> = "text"
+            .chars()
+            .enumerate()
+            .map(|(idx, c)| (7 + idx as u16, 0, c.to_string()))
+            .collect();
+        assert_eq!(highlights, expected);
+
+        let highlights = search_highlights(line, "漢字", false, 40);
+        assert_eq!(
+            highlights,
+            vec![(12, 0, "漢".to_string()), (14, 0, "字".to_string())]
+        );
+    }
+
+    #[test]
+    fn search_highlight_of_wrapped_wide_chars() {
+        // the second `本` does not fit at the end of the first row of 9 columns
+        let line = "日本語日本語 ab";
+        let highlights = search_highlights(line, "本語 a", true, 9);
+        assert_eq!(
+            highlights,
+            vec![
+                (0, 1, "本".to_string()),
+                (2, 1, "語".to_string()),
+                (4, 1, " ".to_string()),
+                (5, 1, "a".to_string()),
+            ]
+        );
+        let highlights = search_highlights(line, "日本", true, 9);
+        assert_eq!(
+            highlights,
+            vec![
+                (0, 0, "日".to_string()),
+                (2, 0, "本".to_string()),
+                (6, 0, "日".to_string()),
+                (0, 1, "本".to_string()),
+            ]
+        );
+    }
 }
@@ -16,13 +16,17 @@ use std::io::{self, stdout};
 
 use crate::{
     app::GitApp,
-    model::errors::Error,
+    model::{config::parse_gitrs_config, errors::Error, git::is_valid_git_rev},
     views::{
-        blame::BlameApp,
+        blame::{print_blame_json, BlameApp},
+        branch::BranchApp,
         pager::{PagerApp, PagerCommand},
+        remote::RemoteApp,
         show::ShowApp,
         stash::StashApp,
         status::StatusApp,
+        tag::TagApp,
+        tree::TreeApp,
     },
 };
 
@@ -43,9 +47,16 @@ enum Commands {
         /// File to blame
         file: String,
 
-        /// Line number to focus on
-        #[arg(default_value_t = 1)]
-        line: usize,
+        /// Line number to focus on, `default_position` when omitted
+        line: Option<usize>,
+
+        /// Revision to blame the file at
+        #[arg(long)]
+        rev: Option<String>,
+
+        /// Print the blame as JSON instead of opening the view
+        #[arg(long)]
+        print_json: bool,
     },
 
     /// Show view
@@ -59,6 +70,12 @@ enum Commands {
         /// Arguments passed to git log
         args: Vec<String>,
     },
+    /// Reflog view
+    #[command(allow_hyphen_values = true)]
+    Reflog {
+        /// Arguments passed to git reflog
+        args: Vec<String>,
+    },
     /// Diff view
     #[command(allow_hyphen_values = true)]
     Diff {
@@ -67,53 +84,139 @@ enum Commands {
     },
     /// Stash view
     Stash,
+    /// Branch view
+    Branch,
+    /// Remote view
+    Remote,
+    /// Tag view
+    Tag,
+    /// Tree view of the tracked files
+    Tree,
+    /// Print the mappings and buttons in use as config lines
+    DumpConfig,
+}
+
+impl Cli {
+    // commands printing their output, without the terminal
+    fn prints(&self) -> bool {
+        matches!(
+            self.command,
+            Commands::DumpConfig
+                | Commands::Blame {
+                    print_json: true,
+                    ..
+                }
+        )
+    }
+}
+
+// run the commands printing their output
+fn print(cli: Cli) -> Result<(), Error> {
+    match cli.command {
+        Commands::Blame { file, rev, .. } => {
+            if let Some(rev) = &rev {
+                if !is_valid_git_rev(rev) {
+                    return Err(Error::Global(format!("invalid revision '{}'", rev)));
+                }
+            }
+            print_blame_json(file, rev)
+        }
+        Commands::DumpConfig => {
+            print!("{}", parse_gitrs_config()?.dump());
+            Ok(())
+        }
+        _ => Ok(()),
+    }
 }
 
 fn app(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>, cli: Cli) -> Result<(), Error> {
     match cli.command {
         Commands::Status => StatusApp::new()?.run(terminal),
-        Commands::Blame { file, line } => BlameApp::new(file, None, line)?.run(terminal),
+        Commands::Blame {
+            file, line, rev, ..
+        } => {
+            if let Some(rev) = &rev {
+                if !is_valid_git_rev(rev) {
+                    return Err(Error::Global(format!("invalid revision '{}'", rev)));
+                }
+            }
+            BlameApp::new(file, rev, line)?.run(terminal)
+        }
         Commands::Show { revision } => ShowApp::new(revision)?.run(terminal),
         Commands::Log { args } => PagerApp::new(Some(PagerCommand::Log(args)))?.run(terminal),
+        Commands::Reflog { args } => PagerApp::new(Some(PagerCommand::Reflog(args)))?.run(terminal),
         Commands::Diff { args } => PagerApp::new(Some(PagerCommand::Diff(args)))?.run(terminal),
         Commands::Stash => StashApp::new()?.run(terminal),
+        Commands::Branch => BranchApp::new()?.run(terminal),
+        Commands::Remote => RemoteApp::new()?.run(terminal),
+        Commands::Tag => TagApp::new()?.run(terminal),
+        Commands::Tree => TreeApp::new()?.run(terminal),
+        Commands::DumpConfig => Ok(()),
     }
 }
 
-fn prepare_terminal() -> Result<Terminal<CrosstermBackend<std::io::Stdout>>, io::Error> {
+fn prepare_terminal(
+    alt_screen: bool,
+) -> Result<Terminal<CrosstermBackend<std::io::Stdout>>, io::Error> {
     let backend = CrosstermBackend::new(stdout());
-    let terminal = Terminal::new(backend)?;
+    let mut terminal = Terminal::new(backend)?;
     enable_raw_mode()?;
-    execute!(stdout(), EnterAlternateScreen)?;
+    if alt_screen {
+        execute!(stdout(), EnterAlternateScreen)?;
+    } else {
+        terminal.clear()?;
+    }
     execute!(stdout(), EnableMouseCapture)?;
     Ok(terminal)
 }
 
 fn restore_terminal(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    alt_screen: bool,
 ) -> Result<(), io::Error> {
     disable_raw_mode()?;
     terminal.show_cursor()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    if alt_screen {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    } else {
+        // below the last screen, which stays in the scrollback
+        let height = terminal.size()?.height;
+        terminal.set_cursor_position((0, height.saturating_sub(1)))?;
+        println!();
+    }
     execute!(stdout(), DisableMouseCapture)?;
     Ok(())
 }
 
 fn main() -> io::Result<()> {
-    let ret = if atty::is(Stream::Stdin) {
-        let cli = Cli::parse();
-        let mut terminal = prepare_terminal()?;
+    // the errors of the config are reported by the views
+    let alt_screen = parse_gitrs_config().map_or(true, |config| config.alt_screen);
+    let cli = match atty::is(Stream::Stdin) {
+        true => Some(Cli::parse()),
+        // the input is paged, unless the command only prints its output, as from an editor
+        false => Cli::try_parse().ok().filter(Cli::prints),
+    };
+    let ret = if let Some(cli) = cli {
+        if cli.prints() {
+            // does not need the terminal
+            if let Err(err) = print(cli) {
+                eprintln!("{} {}", "error:".red().bold(), err.to_string().white());
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        let mut terminal = prepare_terminal(alt_screen)?;
         let ret = app(&mut terminal, cli);
-        restore_terminal(&mut terminal)?;
+        restore_terminal(&mut terminal, alt_screen)?;
         ret
     } else {
         // use the application as a pager
-        let mut terminal = prepare_terminal()?;
+        let mut terminal = prepare_terminal(alt_screen)?;
         let ret = match PagerApp::new(None) {
             Ok(mut pager_app) => pager_app.run(&mut terminal),
             Err(e) => Err(e),
         };
-        restore_terminal(&mut terminal)?;
+        restore_terminal(&mut terminal, alt_screen)?;
         ret
     };
 
@@ -1,6 +1,6 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
-use crate::model::errors::Error;
+use crate::model::{errors::Error, git::FileStatus};
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum CommandType {
@@ -24,33 +24,350 @@ pub enum Action {
     ShiftLineBottom,
     Search,
     SearchReverse,
+    ToggleSearchCase,
     NextSearchResult,
     PreviousSearchResult,
     TypeCommand,
     Command(CommandType, String),
     GoTo(usize),
+    // lines below the selected one, above when negative
+    GoToRelative(isize),
     StageUnstageFile,
     StageUnstageFiles,
+    StageByPattern(Option<String>),
     StatusSwitchView,
     FocusUnstagedView,
     FocusStagedView,
+    FocusMetadata,
+    FocusFiles,
+    CopyMessage,
+    CopySubject,
+    Commit(String),
+    Amend(String),
+    FilterUnmerged,
+    FilterStatus(FileStatus),
+    ClearFilter,
     OpenGitShow,
     OpenLogApp,
+    OpenDiff,
+    FileHistory,
     OpenShowApp,
     NextCommitBlame,
     PreviousCommitBlame,
+    BlameOpenLog,
+    IgnoreWhitespaceBlame,
+    BlameAtRev(Option<String>),
     PagerNextCommit,
     PreviousCommit,
+    NextHunk,
+    PreviousHunk,
+    StageHunk,
+    DiscardFile,
+    MarkResolved,
+    OpenConflict,
+    ToggleWrap,
+    Follow,
+    ToggleSideBySide,
+    FilterLines(Option<String>),
+    ToggleStat,
+    ToggleDiffstat,
     StashPop,
     StashApply,
     StashDrop,
+    StashPush(Option<String>),
+    StashPushIncludeUntracked(Option<String>),
+    TagCreate(Option<String>),
+    GoToCommit(Option<String>),
+    TagCheckout,
+    TreeOpen,
+    BranchCheckout,
+    BranchDelete,
+    BranchMark,
+    DiffBranch,
+    Push(String),
+    Pull(String),
+    Fetch(String),
+    RemoteFetch,
+    RemotePull,
+    RemotePush,
+    CherryPick,
+    Revert,
+    OpenConfigFile,
+    ReloadConfig,
+    Help,
+    CommandPalette,
+    EditFile,
+    CopyCommit,
+    CopyLine,
+    ToggleVisual,
+    CopyPath,
+    CopyLineRef,
     Echo(String),
+    // message given by the output of a shell command
+    EchoCommand(String),
     Set(String),
     Map(String),
     Button(String),
     None,
 }
 
+// flag given alone or among other short flags, `-fd` containing `-f`
+fn has_short_flag(args: &[&str], flag: char) -> bool {
+    args.iter().any(|arg| {
+        arg.strip_prefix('-')
+            .is_some_and(|flags| !flags.starts_with('-') && flags.contains(flag))
+    })
+}
+
+fn has_flag(args: &[&str], short: char, long: &str) -> bool {
+    has_short_flag(args, short) || args.contains(&long)
+}
+
+// git subcommands losing work or history, depending on their flags
+fn is_destructive_git(subcommand: &str, args: &[&str]) -> bool {
+    match subcommand {
+        "push" => has_short_flag(args, 'f') || args.iter().any(|arg| arg.starts_with("--force")),
+        "clean" => !has_flag(args, 'n', "--dry-run"),
+        "reset" => args.contains(&"--hard"),
+        "stash" => matches!(args.first(), Some(&"drop" | &"clear")),
+        "branch" => {
+            has_short_flag(args, 'D')
+                || (has_flag(args, 'd', "--delete") && has_flag(args, 'f', "--force"))
+        }
+        // only the index is changed with `--staged` alone
+        "restore" => !has_flag(args, 'S', "--staged") || has_flag(args, 'W', "--worktree"),
+        "checkout" => args.contains(&"--") || has_flag(args, 'f', "--force"),
+        "rm" => !args.contains(&"--cached"),
+        _ => false,
+    }
+}
+
+// shell commands asking for a confirmation, each command of a pipeline or a list being checked
+fn is_destructive_command(command: &str) -> bool {
+    command.split([';', '&', '|', '\n']).any(|segment| {
+        let mut tokens = segment
+            .split_whitespace()
+            // `VAR=value` environment assignments
+            .skip_while(|token| token.contains('=') && !token.starts_with('-'));
+        let program = tokens.next().unwrap_or_default();
+        let program = program.rsplit(['/', '\\']).next().unwrap_or_default();
+        match program.trim_end_matches(".exe") {
+            "rm" => true,
+            // `%(git)` being expanded to the configured git executable and its arguments
+            "git" | "%(git)" => {
+                let mut subcommand = "";
+                let mut args = Vec::new();
+                while let Some(token) = tokens.next() {
+                    match (subcommand, token) {
+                        // global options, `-C <path>` and `-c <name>=<value>` taking a value
+                        ("", "-C" | "-c") => {
+                            tokens.next();
+                        }
+                        ("", option) if option.starts_with('-') => (),
+                        ("", token) => subcommand = token,
+                        (_, arg) => args.push(arg),
+                    }
+                }
+                is_destructive_git(subcommand, &args)
+            }
+            _ => false,
+        }
+    })
+}
+
+impl Action {
+    pub fn is_destructive(&self) -> bool {
+        match self {
+            Action::StashDrop | Action::BranchDelete | Action::DiscardFile => true,
+            Action::Push(parameters) => parameters
+                .split_whitespace()
+                .any(|arg| arg == "-f" || arg.starts_with("--force")),
+            Action::Command(_, command) | Action::EchoCommand(command) => {
+                is_destructive_command(command)
+            }
+            _ => false,
+        }
+    }
+
+    // destructive actions, the network commands of the remote view and the ones creating
+    // commits
+    pub fn needs_confirmation(&self) -> bool {
+        self.is_destructive()
+            || matches!(
+                self,
+                Action::RemoteFetch
+                    | Action::RemotePull
+                    | Action::RemotePush
+                    | Action::CherryPick
+                    | Action::Revert
+            )
+    }
+
+    pub fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            Action::Up
+                | Action::Down
+                | Action::First
+                | Action::Last
+                | Action::HalfPageUp
+                | Action::HalfPageDown
+                | Action::ShiftLineMiddle
+                | Action::ShiftLineTop
+                | Action::ShiftLineBottom
+                | Action::NextSearchResult
+                | Action::PreviousSearchResult
+                | Action::GoTo(_)
+                | Action::GoToRelative(_)
+                | Action::PagerNextCommit
+                | Action::PreviousCommit
+                | Action::NextHunk
+                | Action::PreviousHunk
+        )
+    }
+}
+
+impl fmt::Display for Action {
+    /// Writes the action the way it is parsed from the config.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (name, parameters) = match self {
+            Action::Reload => ("reload", ""),
+            Action::Up => ("up", ""),
+            Action::Down => ("down", ""),
+            Action::First => ("first", ""),
+            Action::Last => ("last", ""),
+            Action::Quit => ("quit", ""),
+            Action::HalfPageUp => ("half_page_up", ""),
+            Action::HalfPageDown => ("half_page_down", ""),
+            Action::ShiftLineMiddle => ("shift_line_middle", ""),
+            Action::ShiftLineTop => ("shift_line_top", ""),
+            Action::ShiftLineBottom => ("shift_line_bottom", ""),
+            Action::Search => ("search", ""),
+            Action::SearchReverse => ("search_reverse", ""),
+            Action::ToggleSearchCase => ("toggle_search_case", ""),
+            Action::NextSearchResult => ("next_search_result", ""),
+            Action::PreviousSearchResult => ("previous_search_result", ""),
+            Action::TypeCommand => ("type_command", ""),
+            Action::Command(command_type, command) => {
+                let prefix = match command_type {
+                    CommandType::Sync => '!',
+                    CommandType::SyncQuit => '>',
+                    CommandType::Async => '@',
+                };
+                return write!(f, "{}{}", prefix, command);
+            }
+            Action::GoTo(line) => return write!(f, "goto {}", line + 1),
+            Action::GoToRelative(offset) => return write!(f, "goto {:+}", offset),
+            Action::StageUnstageFile => ("stage_unstage_file", ""),
+            Action::StageUnstageFiles => ("stage_unstage_files", ""),
+            Action::StageByPattern(pattern) => {
+                ("stage_by_pattern", pattern.as_deref().unwrap_or(""))
+            }
+            Action::StatusSwitchView => ("status_switch_view", ""),
+            Action::FocusUnstagedView => ("focus_unstaged_view", ""),
+            Action::FocusStagedView => ("focus_staged_view", ""),
+            Action::FocusMetadata => ("focus_metadata", ""),
+            Action::FocusFiles => ("focus_files", ""),
+            Action::CopyMessage => ("copy_message", ""),
+            Action::CopySubject => ("copy_subject", ""),
+            Action::Commit(message) => ("commit", message.as_str()),
+            Action::Amend(message) => ("amend", message.as_str()),
+            Action::FilterUnmerged => ("filter_unmerged", ""),
+            Action::FilterStatus(file_status) => ("filter_status", file_status.name()),
+            Action::ClearFilter => ("clear_filter", ""),
+            Action::OpenGitShow => ("open_git_show", ""),
+            Action::OpenLogApp => ("open_log_app", ""),
+            Action::OpenDiff => ("open_diff", ""),
+            Action::FileHistory => ("file_history", ""),
+            Action::OpenShowApp => ("open_show_app", ""),
+            Action::NextCommitBlame => ("next_commit_blame", ""),
+            Action::PreviousCommitBlame => ("previous_commit_blame", ""),
+            Action::BlameOpenLog => ("blame_open_log", ""),
+            Action::IgnoreWhitespaceBlame => ("ignore_whitespace_blame", ""),
+            Action::BlameAtRev(rev) => ("blame_at_rev", rev.as_deref().unwrap_or("")),
+            Action::PagerNextCommit => ("pager_next_commit", ""),
+            Action::PreviousCommit => ("pager_previous_commit", ""),
+            Action::NextHunk => ("next_hunk", ""),
+            Action::PreviousHunk => ("previous_hunk", ""),
+            Action::StageHunk => ("stage_hunk", ""),
+            Action::DiscardFile => ("discard_file", ""),
+            Action::MarkResolved => ("mark_resolved", ""),
+            Action::OpenConflict => ("open_conflict", ""),
+            Action::ToggleWrap => ("toggle_wrap", ""),
+            Action::Follow => ("follow", ""),
+            Action::ToggleSideBySide => ("toggle_side_by_side", ""),
+            Action::FilterLines(pattern) => ("filter_lines", pattern.as_deref().unwrap_or("")),
+            Action::ToggleStat => ("toggle_stat", ""),
+            Action::ToggleDiffstat => ("toggle_diffstat", ""),
+            Action::StashPop => ("stash_pop", ""),
+            Action::StashApply => ("stash_apply", ""),
+            Action::StashDrop => ("stash_drop", ""),
+            Action::StashPush(message) => ("stash_push", message.as_deref().unwrap_or("")),
+            Action::TagCreate(name) => ("tag_create", name.as_deref().unwrap_or("")),
+            Action::GoToCommit(rev) => ("go_to_commit", rev.as_deref().unwrap_or("")),
+            Action::TagCheckout => ("tag_checkout", ""),
+            Action::TreeOpen => ("tree_open", ""),
+            Action::StashPushIncludeUntracked(message) => (
+                "stash_push_include_untracked",
+                message.as_deref().unwrap_or(""),
+            ),
+            Action::BranchCheckout => ("branch_checkout", ""),
+            Action::BranchDelete => ("branch_delete", ""),
+            Action::BranchMark => ("branch_mark", ""),
+            Action::DiffBranch => ("diff_branch", ""),
+            Action::Push(parameters) => ("push", parameters.as_str()),
+            Action::Pull(parameters) => ("pull", parameters.as_str()),
+            Action::Fetch(parameters) => ("fetch", parameters.as_str()),
+            Action::RemoteFetch => ("remote_fetch", ""),
+            Action::RemotePull => ("remote_pull", ""),
+            Action::RemotePush => ("remote_push", ""),
+            Action::CherryPick => ("cherry_pick", ""),
+            Action::Revert => ("revert", ""),
+            Action::OpenConfigFile => ("open_config_file", ""),
+            Action::ReloadConfig => ("reload_config", ""),
+            Action::Help => ("help", ""),
+            Action::CommandPalette => ("command_palette", ""),
+            Action::EditFile => ("edit_file", ""),
+            Action::CopyCommit => ("copy_commit", ""),
+            Action::CopyLine => ("copy_line", ""),
+            Action::ToggleVisual => ("toggle_visual", ""),
+            Action::CopyPath => ("copy_path", ""),
+            Action::CopyLineRef => ("copy_line_ref", ""),
+            Action::Echo(message) => ("echo", message.as_str()),
+            Action::EchoCommand(command) => ("echo_command", command.as_str()),
+            Action::Set(parameters) => ("set", parameters.as_str()),
+            Action::Map(parameters) => ("map", parameters.as_str()),
+            Action::Button(parameters) => ("button", parameters.as_str()),
+            Action::None => ("nop", ""),
+        };
+        match parameters.is_empty() {
+            true => write!(f, "{}", name),
+            false => write!(f, "{} {}", name, parameters),
+        }
+    }
+}
+
+// `12`, `+10`, `-5` or `$`, too big numbers being clamped and `0` going to the first line
+fn parse_goto(s: &str) -> Option<Action> {
+    if s == "$" {
+        return Some(Action::Last);
+    }
+    let (sign, digits) = match s.strip_prefix(['+', '-']) {
+        Some(digits) => (s.chars().next(), digits),
+        None => (None, s),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let number: usize = digits.parse().unwrap_or(usize::MAX);
+    let offset = isize::try_from(number).unwrap_or(isize::MAX);
+    Some(match sign {
+        Some('+') => Action::GoToRelative(offset),
+        Some(_) => Action::GoToRelative(-offset),
+        None => Action::GoTo(number.saturating_sub(1)),
+    })
+}
+
 impl FromStr for Action {
     type Err = Error;
 
@@ -75,39 +392,101 @@ impl FromStr for Action {
             "type_command" => Ok(Action::TypeCommand),
             "search" => Ok(Action::Search),
             "search_reverse" => Ok(Action::SearchReverse),
+            "toggle_search_case" => Ok(Action::ToggleSearchCase),
             "stage_unstage_file" => Ok(Action::StageUnstageFile),
             "stage_unstage_files" => Ok(Action::StageUnstageFiles),
+            "stage_by_pattern" => Ok(Action::StageByPattern(
+                (!parameters.is_empty()).then(|| parameters.to_string()),
+            )),
             "status_switch_view" => Ok(Action::StatusSwitchView),
             "focus_unstaged_view" => Ok(Action::FocusUnstagedView),
             "focus_staged_view" => Ok(Action::FocusStagedView),
+            "focus_metadata" => Ok(Action::FocusMetadata),
+            "focus_files" => Ok(Action::FocusFiles),
+            "copy_message" => Ok(Action::CopyMessage),
+            "copy_subject" => Ok(Action::CopySubject),
+            "commit" => Ok(Action::Commit(parameters.to_string())),
+            "amend" => Ok(Action::Amend(parameters.to_string())),
+            "filter_unmerged" => Ok(Action::FilterUnmerged),
+            "filter_status" => Ok(Action::FilterStatus(parameters.parse()?)),
+            "clear_filter" => Ok(Action::ClearFilter),
             "open_git_show" => Ok(Action::OpenGitShow),
             "open_log_app" => Ok(Action::OpenLogApp),
+            "open_diff" => Ok(Action::OpenDiff),
+            "file_history" => Ok(Action::FileHistory),
             "open_show_app" => Ok(Action::OpenShowApp),
             "next_commit_blame" => Ok(Action::NextCommitBlame),
             "previous_commit_blame" => Ok(Action::PreviousCommitBlame),
+            "blame_open_log" => Ok(Action::BlameOpenLog),
+            "ignore_whitespace_blame" => Ok(Action::IgnoreWhitespaceBlame),
+            "blame_at_rev" => Ok(Action::BlameAtRev(
+                (!parameters.is_empty()).then(|| parameters.to_string()),
+            )),
             "pager_next_commit" => Ok(Action::PagerNextCommit),
             "pager_previous_commit" => Ok(Action::PreviousCommit),
+            "next_hunk" => Ok(Action::NextHunk),
+            "previous_hunk" => Ok(Action::PreviousHunk),
+            "stage_hunk" => Ok(Action::StageHunk),
+            "discard_file" => Ok(Action::DiscardFile),
+            "mark_resolved" => Ok(Action::MarkResolved),
+            "open_conflict" => Ok(Action::OpenConflict),
+            "toggle_wrap" => Ok(Action::ToggleWrap),
+            "follow" => Ok(Action::Follow),
+            "toggle_side_by_side" => Ok(Action::ToggleSideBySide),
+            "filter_lines" => Ok(Action::FilterLines(
+                (!parameters.is_empty()).then(|| parameters.to_string()),
+            )),
+            "toggle_stat" => Ok(Action::ToggleStat),
+            "toggle_diffstat" => Ok(Action::ToggleDiffstat),
             "stash_pop" => Ok(Action::StashPop),
             "stash_apply" => Ok(Action::StashApply),
             "stash_drop" => Ok(Action::StashDrop),
+            "stash_push" => Ok(Action::StashPush(
+                (!parameters.is_empty()).then(|| parameters.to_string()),
+            )),
+            "stash_push_include_untracked" => Ok(Action::StashPushIncludeUntracked(
+                (!parameters.is_empty()).then(|| parameters.to_string()),
+            )),
+            "tag_create" => Ok(Action::TagCreate(
+                (!parameters.is_empty()).then(|| parameters.to_string()),
+            )),
+            "go_to_commit" => Ok(Action::GoToCommit(
+                (!parameters.is_empty()).then(|| parameters.to_string()),
+            )),
+            "tag_checkout" => Ok(Action::TagCheckout),
+            "tree_open" => Ok(Action::TreeOpen),
+            "branch_checkout" => Ok(Action::BranchCheckout),
+            "branch_delete" => Ok(Action::BranchDelete),
+            "branch_mark" => Ok(Action::BranchMark),
+            "diff_branch" => Ok(Action::DiffBranch),
+            "push" => Ok(Action::Push(parameters.to_string())),
+            "pull" => Ok(Action::Pull(parameters.to_string())),
+            "fetch" => Ok(Action::Fetch(parameters.to_string())),
+            "cherry_pick" => Ok(Action::CherryPick),
+            "revert" => Ok(Action::Revert),
+            "remote_fetch" => Ok(Action::RemoteFetch),
+            "remote_pull" => Ok(Action::RemotePull),
+            "remote_push" => Ok(Action::RemotePush),
+            "open_config_file" => Ok(Action::OpenConfigFile),
+            "reload_config" => Ok(Action::ReloadConfig),
+            "help" => Ok(Action::Help),
+            "command_palette" => Ok(Action::CommandPalette),
+            "edit_file" => Ok(Action::EditFile),
+            "copy_commit" => Ok(Action::CopyCommit),
+            "copy_line" => Ok(Action::CopyLine),
+            "toggle_visual" => Ok(Action::ToggleVisual),
+            "copy_path" => Ok(Action::CopyPath),
+            "copy_line_ref" => Ok(Action::CopyLineRef),
             "echo" => Ok(Action::Echo(parameters.to_string())),
+            "echo_command" => Ok(Action::EchoCommand(parameters.to_string())),
             "set" => Ok(Action::Set(parameters.to_string())),
             "map" => Ok(Action::Map(parameters.to_string())),
             "button" => Ok(Action::Button(parameters.to_string())),
             "nop" => Ok(Action::None),
-            "goto" => {
-                if let Ok(number) = parameters.parse::<usize>() {
-                    if number > 0 {
-                        return Ok(Action::GoTo(number - 1));
-                    }
-                };
-                Ok(Action::GoTo(0))
-            }
+            "goto" => Ok(parse_goto(parameters).unwrap_or(Action::GoTo(0))),
             _ => {
-                if let Ok(number) = s.parse::<usize>() {
-                    if number > 0 {
-                        return Ok(Action::GoTo(number - 1));
-                    }
+                if let Some(action) = parse_goto(s) {
+                    return Ok(action);
                 }
                 let command_type = match s.chars().next() {
                     Some('!') => CommandType::Sync,
@@ -121,3 +500,106 @@ impl FromStr for Action {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destructive_commands() {
+        for command in [
+            "git push --force",
+            "git push -f origin main",
+            "git push --force-with-lease",
+            "git clean -fd",
+            "git reset --hard HEAD~1",
+            "git stash drop",
+            "git stash clear",
+            "git branch -D topic",
+            "git branch --delete --force topic",
+            "git restore %(file)",
+            "git restore --staged --worktree %(file)",
+            "git checkout -- %(file)",
+            "git rm %(file)",
+            "rm -rf build",
+            "git -C repo clean -f",
+            "git fetch && git reset --hard origin/main",
+            "echo done; /usr/bin/git clean -fdx",
+            "%(git) reset --hard %(rev)",
+            "%(git) -c core.pager=cat clean -fd",
+        ] {
+            assert!(is_destructive_command(command), "{}", command);
+        }
+    }
+
+    #[test]
+    fn harmless_commands() {
+        for command in [
+            "git push",
+            "git push -u origin main",
+            "git restore --staged %(file)",
+            "git rm --cached %(file)",
+            "git clean -n",
+            "git reset --soft HEAD~1",
+            "git stash list",
+            "git branch -d topic",
+            "git checkout main",
+            "git cleanup",
+            "git log --format=%H | grep rm",
+            "cargo clean-cache",
+            "make cleanup",
+            "git commit -m 'restore the old behavior'",
+            "%(git) status",
+            "%(git) checkout %(rev)",
+        ] {
+            assert!(!is_destructive_command(command), "{}", command);
+        }
+    }
+
+    #[test]
+    fn echo_commands_are_checked_like_commands() {
+        assert!(Action::EchoCommand("git clean -fd".to_string()).needs_confirmation());
+        assert!(!Action::EchoCommand("git rev-parse HEAD".to_string()).needs_confirmation());
+    }
+
+    #[test]
+    fn goto_lines() {
+        for (input, action) in [
+            ("12", Some(Action::GoTo(11))),
+            ("1", Some(Action::GoTo(0))),
+            ("0", Some(Action::GoTo(0))),
+            ("+10", Some(Action::GoToRelative(10))),
+            ("-5", Some(Action::GoToRelative(-5))),
+            ("+0", Some(Action::GoToRelative(0))),
+            ("$", Some(Action::Last)),
+            ("", None),
+            ("+", None),
+            ("-", None),
+            ("+-3", None),
+            ("12a", None),
+            ("1 2", None),
+            ("$1", None),
+            ("¹", None),
+        ] {
+            assert_eq!(parse_goto(input), action, "{}", input);
+        }
+    }
+
+    #[test]
+    fn goto_overflow_is_clamped() {
+        let huge = "9".repeat(40);
+        assert_eq!(parse_goto(&huge), Some(Action::GoTo(usize::MAX - 1)));
+        assert_eq!(
+            parse_goto(&format!("+{}", huge)),
+            Some(Action::GoToRelative(isize::MAX))
+        );
+        assert_eq!(
+            parse_goto(&format!("-{}", huge)),
+            Some(Action::GoToRelative(-isize::MAX))
+        );
+        assert_eq!(
+            parse_goto(&format!("+{}", usize::MAX)),
+            Some(Action::GoToRelative(isize::MAX))
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 use ratatui::{
     layout::{Position, Rect},
@@ -7,24 +7,43 @@ use ratatui::{
 
 use crate::model::{
     action::Action,
-    config::{parse_gitrs_config, Config},
+    config::{parse_gitrs_config, Config, DefaultPosition},
     errors::Error,
+    history::History,
 };
+use crate::ui::{help::Help, palette::Palette};
 
 #[derive(Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub enum NotifChannel {
     Search,
+    Matches,
     Echo,
     Line,
     Keys,
     Error,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum MultiLinePrompt {
+    Commit,
+    Amend,
+}
+
 #[derive(Clone, PartialEq)]
 pub enum InputState {
     App,
     Search,
     Command,
+    // text spanning several lines, submitted with ctrl-s
+    MultiLine(MultiLinePrompt),
+    StashMessage { include_untracked: bool },
+    TagName,
+    CommitRev,
+    BlameRev,
+    StagePattern,
+    FilterLines,
+    Palette,
+    Confirm,
 }
 
 pub struct AppState {
@@ -32,38 +51,116 @@ pub struct AppState {
     pub config: Config,
     pub notif: HashMap<NotifChannel, String>,
     pub key_combination: String,
+    // numeric prefix typed before a motion, `5j` or `120G`
+    pub count: Option<usize>,
+    // action of the typed keys, fired once no longer mapping starting with them is typed in time
+    pub pending_chord: Option<(Action, Instant)>,
+    // action to run before handling the next event
+    pub queued_action: Option<Action>,
     pub search_string: String,
     pub search_reverse: bool,
     pub current_search_idx: Option<usize>,
+    pub search_matches: Vec<usize>,
+    pub search_scanned: usize,
     pub command_string: String,
     pub edit_cursor: usize,
+    pub history: History,
+    // position in the history while browsing it, with the line being typed before that
+    pub history_idx: Option<usize>,
+    pub history_draft: String,
     pub input_state: InputState,
+    // action waiting for a confirmation, with the question asked
+    pub pending_action: Option<(Action, String)>,
+    pub confirmed: bool,
     pub list_state: ListState,
+    pub wrap: bool,
     pub region_to_action: Vec<(Rect, Action)>,
     pub edit_bar_rect: Rect,
     pub mouse_position: Position,
     pub mouse_down: bool,
+    // anchor and current positions of a mouse drag, see `mouse_copy`
+    pub mouse_selection: Option<(Position, Position)>,
+    // line where the visual mode started, the range going from it to the selected line
+    pub visual_anchor: Option<usize>,
+    // keybindings overlay, capturing the keys while displayed
+    pub help: Option<Help>,
+    // actions filtered by the line typed in `InputState::Palette`
+    pub palette: Option<Palette>,
+    // the view is only drawn again after a change, see `GitApp::run`
+    pub dirty: bool,
 }
 
 impl AppState {
+    pub fn edit_line_prefix(&self) -> &str {
+        match self.input_state {
+            InputState::Search => match self.search_reverse {
+                false => "/",
+                true => "?",
+            },
+            InputState::Command => ":",
+            InputState::MultiLine(MultiLinePrompt::Commit) => "commit message",
+            InputState::MultiLine(MultiLinePrompt::Amend) => "amend message",
+            InputState::StashMessage { .. } => "stash message: ",
+            InputState::TagName => "tag name: ",
+            InputState::CommitRev => "go to commit: ",
+            InputState::BlameRev => "blame at: ",
+            InputState::StagePattern => "stage pattern: ",
+            InputState::FilterLines => "filter: ",
+            InputState::Palette => "> ",
+            InputState::Confirm => match &self.pending_action {
+                Some((_, prompt)) => prompt,
+                None => "",
+            },
+            InputState::App => "",
+        }
+    }
+
+    // first or last line, as set by `default_position`
+    pub fn select_default(&mut self) {
+        match self.config.default_position {
+            DefaultPosition::Top => self.list_state.select_first(),
+            DefaultPosition::Bottom => self.list_state.select_last(),
+        }
+    }
+
     pub fn new() -> Result<Self, Error> {
-        let r = Self {
+        Ok(Self::with_config(parse_gitrs_config()?, History::load()))
+    }
+
+    // without reading the config and history files
+    pub fn with_config(config: Config, history: History) -> Self {
+        Self {
             quit: false,
-            config: parse_gitrs_config()?,
+            wrap: config.wrap,
+            config,
             notif: HashMap::new(),
             key_combination: "".to_string(),
+            count: None,
+            pending_chord: None,
+            queued_action: None,
             search_string: "".to_string(),
             search_reverse: false,
             current_search_idx: None,
+            search_matches: Vec::new(),
+            search_scanned: 0,
             command_string: "".to_string(),
             edit_cursor: 0,
+            history,
+            history_idx: None,
+            history_draft: "".to_string(),
             input_state: InputState::App,
+            pending_action: None,
+            confirmed: false,
             list_state: ListState::default(),
             region_to_action: Vec::new(),
             edit_bar_rect: Rect::default(),
             mouse_position: Position::default(),
             mouse_down: false,
-        };
-        Ok(r)
+            mouse_selection: None,
+            visual_anchor: None,
+            help: None,
+            palette: None,
+            dirty: true,
+        }
     }
 }
@@ -0,0 +1,55 @@
+use std::{
+    env,
+    io::{stdout, ErrorKind, Write},
+    process::{Command, Stdio},
+};
+
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use crossterm::{execute, style::Print};
+
+use crate::model::{config::Config, errors::Error};
+
+// `set clipboard osc52` has the terminal copy the text, also over ssh or from a container
+const OSC52: &str = "osc52";
+
+fn copy_osc52(text: &str) -> Result<(), Error> {
+    let sequence = format!("\x1b]52;c;{}\x07", BASE64_STANDARD.encode(text));
+    let sequence = match env::var_os("TMUX") {
+        // passed through to the outer terminal, with `set -g allow-passthrough on`
+        Some(_) => format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b")),
+        None => sequence,
+    };
+    execute!(stdout(), Print(sequence))?;
+    Ok(())
+}
+
+pub fn copy_to_clipboard(text: &str, config: &Config) -> Result<(), Error> {
+    if config.clipboard_tool == OSC52 {
+        return copy_osc52(text);
+    }
+    // the clipboard tool may come with arguments, e.g. `xsel -b`
+    let mut words = config.clipboard_tool.split_whitespace();
+    let tool = words
+        .next()
+        .ok_or_else(|| Error::Global("no clipboard tool configured".to_string()))?;
+    let mut child = Command::new(tool)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            ErrorKind::NotFound => Error::Global(format!(
+                "clipboard tool `{}` not found, see `set clipboard`",
+                tool
+            )),
+            _ => Error::from(err),
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        return Err(Error::Global(format!("clipboard tool `{}` failed", tool)));
+    }
+    Ok(())
+}
@@ -1,10 +1,13 @@
 use std::{
     collections::HashMap,
-    fs,
+    fmt, fs,
     io::{BufRead, BufReader},
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
+use chrono::format::{Item, StrftimeItems};
+use ratatui::style::Color;
 use regex::Regex;
 
 use crate::model::{
@@ -12,8 +15,9 @@ use crate::model::{
     errors::Error,
     git::{FileStatus, StagedStatus},
 };
+use crate::ui::syntax::DEFAULT_THEME;
 
-const DEFAULT_CONFIG: &str = include_str!("../../config/.gitrsrc");
+pub const DEFAULT_CONFIG: &str = include_str!("../../config/.gitrsrc");
 
 #[derive(Hash, Eq, PartialEq, Debug, Clone)]
 pub enum MappingScope {
@@ -22,10 +26,14 @@ pub enum MappingScope {
     Status(Option<StagedStatus>, Option<FileStatus>),
     Pager,
     Log,
+    Reflog,
     Diff,
     Branch,
+    Remote,
+    Tag,
     Stash,
     Blame,
+    Tree,
 }
 
 impl FromStr for MappingScope {
@@ -39,9 +47,13 @@ impl FromStr for MappingScope {
             "global" => Ok(MappingScope::Global),
             "pager" => Ok(MappingScope::Pager),
             "log" => Ok(MappingScope::Log),
+            "reflog" => Ok(MappingScope::Reflog),
             "branch" => Ok(MappingScope::Branch),
+            "remote" => Ok(MappingScope::Remote),
+            "tag" => Ok(MappingScope::Tag),
             "stash" => Ok(MappingScope::Stash),
             "blame" => Ok(MappingScope::Blame),
+            "tree" => Ok(MappingScope::Tree),
             "diff" => Ok(MappingScope::Diff),
             "show" => {
                 let file_status = match split.next() {
@@ -66,17 +78,166 @@ impl FromStr for MappingScope {
     }
 }
 
+impl fmt::Display for MappingScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MappingScope::Global => write!(f, "global"),
+            MappingScope::Pager => write!(f, "pager"),
+            MappingScope::Log => write!(f, "log"),
+            MappingScope::Reflog => write!(f, "reflog"),
+            MappingScope::Branch => write!(f, "branch"),
+            MappingScope::Remote => write!(f, "remote"),
+            MappingScope::Tag => write!(f, "tag"),
+            MappingScope::Stash => write!(f, "stash"),
+            MappingScope::Blame => write!(f, "blame"),
+            MappingScope::Tree => write!(f, "tree"),
+            MappingScope::Diff => write!(f, "diff"),
+            MappingScope::Show(file_status) => {
+                write!(f, "show")?;
+                if let Some(file_status) = file_status {
+                    write!(f, ":{}", file_status.name())?;
+                }
+                Ok(())
+            }
+            MappingScope::Status(staged_status, file_status) => {
+                write!(f, "status")?;
+                if let Some(staged_status) = staged_status {
+                    write!(f, ":{}", staged_status.name())?;
+                }
+                if let Some(file_status) = file_status {
+                    write!(f, ":{}", file_status.name())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShowCr {
+    Show,
+    Hide,
+    Highlight,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MenuBarPosition {
+    Top,
+    Bottom,
+}
+
+// line selected when a view opens
+#[derive(Clone, Copy, PartialEq)]
+pub enum DefaultPosition {
+    Top,
+    Bottom,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SearchCase {
+    // sensitive only if the search contains an uppercase character
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl fmt::Display for SearchCase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchCase::Smart => write!(f, "smart"),
+            SearchCase::Sensitive => write!(f, "sensitive"),
+            SearchCase::Insensitive => write!(f, "insensitive"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum DateFormat {
+    // as `--date=relative`, "3 days ago"
+    Relative,
+    // strftime format
+    Absolute(String),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LineNumbers {
+    Hide,
+    Absolute,
+    // distance from the selected line
+    Relative,
+}
+
+// colors of the file rows, the status tables use the color of their stage except for conflicts
+#[derive(Clone, Copy)]
+pub struct StatusColors {
+    pub new: Color,
+    pub modified: Color,
+    pub deleted: Color,
+    pub unmerged: Color,
+    pub renamed: Color,
+    pub unstaged: Color,
+    pub staged: Color,
+}
+
+impl StatusColors {
+    pub fn file(&self, file_status: &FileStatus) -> Color {
+        match file_status {
+            FileStatus::New => self.new,
+            FileStatus::Modified => self.modified,
+            FileStatus::Deleted => self.deleted,
+            FileStatus::Unmerged => self.unmerged,
+            FileStatus::Renamed => self.renamed,
+            FileStatus::None => Color::default(),
+        }
+    }
+}
+
 pub type KeyBindings = HashMap<MappingScope, HashMap<String, Action>>;
 pub type Button = (String, Action);
 pub type Buttons = HashMap<MappingScope, Vec<Button>>;
 
+#[derive(Clone)]
 pub struct Config {
     pub scrolloff: usize,
     pub git_exe: String,
-    pub smart_case: bool,
+    // passed before the subcommand to every git invocation
+    pub git_args: Vec<String>,
+    pub search_case: SearchCase,
     pub scroll_step: usize,
+    pub default_position: DefaultPosition,
+    // lines per mouse wheel step, `scroll_step` when unset
+    pub mouse_scroll_step: Option<usize>,
+    // the content follows the fingers on a trackpad, scrolling down on `ScrollUp`
+    pub natural_scroll: bool,
     pub menu_bar: bool,
+    pub menu_bar_position: MenuBarPosition,
+    // the screen is left as is on quit, the output staying in the scrollback
+    pub alt_screen: bool,
     pub clipboard_tool: String,
+    pub editor_cmd: String,
+    pub show_cr: ShowCr,
+    pub theme: String,
+    // syntax of the blame view, detected from the file when unset
+    pub syntax: Option<String>,
+    pub blame_search_metadata: bool,
+    pub blame_ignore_whitespace: bool,
+    pub tab_width: usize,
+    pub date_format: DateFormat,
+    pub copy_strip_ansi: bool,
+    pub wrap: bool,
+    pub word_diff: bool,
+    // lines displayed around the matches of `filter_lines`
+    pub filter_context: usize,
+    pub pager_line_numbers: LineNumbers,
+    pub unknown_key_hint: bool,
+    pub status_preview: bool,
+    pub detect_renames: bool,
+    // lines changed in each file of the show view
+    pub show_diffstat: bool,
+    pub confirm_destructive: bool,
+    pub chord_timeout_ms: u64,
+    pub mouse_copy: bool,
+    pub colors: StatusColors,
     pub use_default_mappings: bool,
     pub use_default_buttons: bool,
     pub user_bindings: KeyBindings,
@@ -86,7 +247,15 @@ pub struct Config {
 }
 
 impl Config {
+    pub fn mouse_scroll_step(&self) -> usize {
+        self.mouse_scroll_step.unwrap_or(self.scroll_step)
+    }
+
     fn parse_line(&mut self, line: &str, default: bool) -> Result<(), Error> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
         let mut split = line.splitn(2, ' ');
         let keyword = split.next().unwrap_or("");
         let params = split.next().unwrap_or("");
@@ -95,7 +264,7 @@ impl Config {
             "map" => self.parse_map_line(params, default)?,
             "set" => self.parse_set_line(params)?,
             "button" => self.parse_button_line(params, default)?,
-            _ => (),
+            _ => return Err(Error::ParseKeyword(keyword.to_string())),
         };
         Ok(())
     }
@@ -103,11 +272,11 @@ impl Config {
     pub fn parse_map_line(&mut self, params: &str, default: bool) -> Result<(), Error> {
         let parts: Vec<&str> = params.splitn(3, ' ').collect();
         if parts.len() < 3 {
-            return Ok(());
+            return Err(Error::ParseMapping(params.to_string()));
         }
         let mode = parts[0].to_string().parse()?;
         let key = parts[1].to_string();
-        let action_str = parts[2].to_string();
+        let action_str = strip_action_comment(parts[2]);
 
         let action = action_str.parse::<Action>()?;
         let bindings = match default {
@@ -120,6 +289,7 @@ impl Config {
     }
 
     pub fn parse_set_line(&mut self, params: &str) -> Result<(), Error> {
+        let params = strip_inline_comment(params);
         let parts: Vec<&str> = params.splitn(2, ' ').collect();
         if parts.len() < 2 {
             return Err(Error::ParseVariable(params.to_string()));
@@ -133,16 +303,134 @@ impl Config {
                     self.scrolloff = so;
                 }
             }
-            "git" => self.git_exe = value,
-            "smart_case" => self.smart_case = value == "true",
+            "git" => {
+                let value = value.trim_matches('"');
+                // a path with spaces, e.g. `C:\Program Files\Git\bin\git.exe`, is not split
+                let mut words: Vec<String> = match Path::new(value).is_file() {
+                    true => vec![value.to_string()],
+                    false => value.split_whitespace().map(String::from).collect(),
+                };
+                if words.is_empty() {
+                    return Err(Error::ParseVariable(params.to_string()));
+                }
+                self.git_exe = words.remove(0);
+                self.git_args = words;
+            }
+            "smart_case" => {
+                self.search_case = match value == "true" {
+                    true => SearchCase::Smart,
+                    false => SearchCase::Sensitive,
+                }
+            }
+            "search_case" => {
+                self.search_case = match value.as_str() {
+                    "smart" => SearchCase::Smart,
+                    "sensitive" => SearchCase::Sensitive,
+                    "insensitive" => SearchCase::Insensitive,
+                    _ => return Err(Error::ParseVariable(params.to_string())),
+                }
+            }
             "scroll_step" => {
                 let number: Result<usize, _> = value.parse();
                 if let Ok(ss) = number {
                     self.scroll_step = ss;
                 }
             }
+            "mouse_scroll_step" => match value.parse() {
+                Ok(step) => self.mouse_scroll_step = Some(step),
+                Err(_) => return Err(Error::ParseVariable(params.to_string())),
+            },
+            "default_position" => {
+                self.default_position = match value.as_str() {
+                    "top" => DefaultPosition::Top,
+                    "bottom" => DefaultPosition::Bottom,
+                    _ => return Err(Error::ParseVariable(params.to_string())),
+                }
+            }
+            "natural_scroll" => self.natural_scroll = value == "true",
+            "alt_screen" => self.alt_screen = value == "true",
+            "tab_width" => {
+                let number: Result<usize, _> = value.parse();
+                if let Ok(tw) = number {
+                    self.tab_width = tw;
+                }
+            }
+            "date_format" => {
+                self.date_format = match value.as_str() {
+                    "relative" => DateFormat::Relative,
+                    format => {
+                        // formatting with an invalid specifier would panic
+                        if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                            return Err(Error::ParseVariable(params.to_string()));
+                        }
+                        DateFormat::Absolute(format.to_string())
+                    }
+                }
+            }
+            "copy_strip_ansi" => self.copy_strip_ansi = value == "true",
             "menu_bar" => self.menu_bar = value == "true",
+            "menu_bar_position" => {
+                self.menu_bar_position = match value.as_str() {
+                    "top" => MenuBarPosition::Top,
+                    "bottom" => MenuBarPosition::Bottom,
+                    _ => return Err(Error::ParseVariable(params.to_string())),
+                }
+            }
             "clipboard" => self.clipboard_tool = value,
+            "editor_cmd" => self.editor_cmd = value,
+            "show_cr" => {
+                self.show_cr = match value.as_str() {
+                    "true" => ShowCr::Show,
+                    "false" => ShowCr::Hide,
+                    "highlight" => ShowCr::Highlight,
+                    _ => return Err(Error::ParseVariable(params.to_string())),
+                }
+            }
+            "theme" => self.theme = value,
+            "syntax" => self.syntax = (value != "auto").then_some(value),
+            "blame_search_metadata" => self.blame_search_metadata = value == "true",
+            "blame_ignore_whitespace" => self.blame_ignore_whitespace = value == "true",
+            "wrap" => self.wrap = value == "true",
+            "word_diff" => self.word_diff = value == "true",
+            "filter_context" => match value.parse() {
+                Ok(context) => self.filter_context = context,
+                Err(_) => return Err(Error::ParseVariable(params.to_string())),
+            },
+            "pager_line_numbers" => {
+                self.pager_line_numbers = match value.as_str() {
+                    "true" => LineNumbers::Absolute,
+                    "false" => LineNumbers::Hide,
+                    "relative" => LineNumbers::Relative,
+                    _ => return Err(Error::ParseVariable(params.to_string())),
+                }
+            }
+            "unknown_key_hint" => self.unknown_key_hint = value == "true",
+            "status_preview" => self.status_preview = value == "true",
+            "detect_renames" => self.detect_renames = value == "true",
+            "show_diffstat" => self.show_diffstat = value == "true",
+            "confirm_destructive" => self.confirm_destructive = value == "true",
+            "chord_timeout_ms" => {
+                self.chord_timeout_ms = value
+                    .parse()
+                    .map_err(|_| Error::ParseVariable(params.to_string()))?
+            }
+            "color_new" | "color_modified" | "color_deleted" | "color_unmerged"
+            | "color_renamed" | "color_unstaged" | "color_staged" => {
+                let color: Color = value
+                    .parse()
+                    .map_err(|_| Error::ParseVariable(params.to_string()))?;
+                let field = match key.as_str() {
+                    "color_new" => &mut self.colors.new,
+                    "color_modified" => &mut self.colors.modified,
+                    "color_deleted" => &mut self.colors.deleted,
+                    "color_unmerged" => &mut self.colors.unmerged,
+                    "color_renamed" => &mut self.colors.renamed,
+                    "color_unstaged" => &mut self.colors.unstaged,
+                    _ => &mut self.colors.staged,
+                };
+                *field = color;
+            }
+            "mouse_copy" => self.mouse_copy = value == "true",
             "default_mappings" => self.use_default_mappings = value == "true",
             "default_buttons" => self.use_default_buttons = value == "true",
             _ => return Err(Error::ParseVariable(params.to_string())),
@@ -158,8 +446,7 @@ impl Config {
             if name.starts_with('"') && name.ends_with('"') {
                 name = name[1..name.len() - 1].to_string(); // Remove quotes
             }
-            let action_str = caps[3].to_string();
-            let action = action_str.parse::<Action>()?;
+            let action = strip_action_comment(&caps[3]).parse::<Action>()?;
 
             let buttons = match default {
                 true => &mut self.default_buttons,
@@ -176,7 +463,10 @@ impl Config {
 
     pub fn get_bindings(&self, mapping_scope: MappingScope) -> Vec<(String, Action)> {
         let user_bindings = self.user_bindings.get(&mapping_scope);
-        let default_bindings = self.default_bindings.get(&mapping_scope);
+        let default_bindings = self
+            .use_default_mappings
+            .then(|| self.default_bindings.get(&mapping_scope))
+            .flatten();
         let mut merged: HashMap<String, Action> = HashMap::new();
 
         if let Some(default_bindings) = default_bindings {
@@ -207,15 +497,89 @@ impl Config {
     }
 }
 
+impl Config {
+    /// Writes the bindings and buttons in use as config lines, grouped by scope.
+    pub fn dump(&self) -> String {
+        let mut scopes: Vec<&MappingScope> = self
+            .default_bindings
+            .keys()
+            .chain(self.user_bindings.keys())
+            .chain(self.default_buttons.keys())
+            .chain(self.user_buttons.keys())
+            .collect();
+        scopes.sort_by_key(|scope| scope.to_string());
+        scopes.dedup();
+
+        let mut lines = Vec::new();
+        for scope in scopes {
+            let mut bindings = self.get_bindings(scope.clone());
+            let buttons = self.get_buttons(scope.clone());
+            if bindings.is_empty() && buttons.is_empty() {
+                continue;
+            }
+            bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            lines.push(format!("# {}", scope));
+            for (keys, action) in bindings {
+                lines.push(format!("map {} {} {}", scope, keys, action));
+            }
+            for (text, action) in buttons {
+                let text = match text.contains(' ') {
+                    true => format!(r#""{}""#, text),
+                    false => text,
+                };
+                lines.push(format!("button {} {} {}", scope, text, action));
+            }
+            lines.push("".to_string());
+        }
+        lines.join("\n")
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         let mut config = Config {
             scrolloff: 5,
             git_exe: "git".to_string(),
-            smart_case: true,
+            git_args: Vec::new(),
+            search_case: SearchCase::Smart,
             scroll_step: 2,
+            mouse_scroll_step: None,
+            default_position: DefaultPosition::Top,
+            natural_scroll: false,
             menu_bar: true,
+            menu_bar_position: MenuBarPosition::Top,
+            alt_screen: true,
             clipboard_tool: if cfg!(windows) { "clip.exe" } else { "xsel" }.to_string(),
+            editor_cmd: String::new(),
+            show_cr: ShowCr::Show,
+            theme: DEFAULT_THEME.to_string(),
+            syntax: None,
+            blame_search_metadata: false,
+            blame_ignore_whitespace: false,
+            tab_width: 4,
+            date_format: DateFormat::Absolute("%Y-%m-%d".to_string()),
+            copy_strip_ansi: true,
+            wrap: false,
+            word_diff: false,
+            filter_context: 0,
+            pager_line_numbers: LineNumbers::Hide,
+            unknown_key_hint: false,
+            status_preview: true,
+            detect_renames: false,
+            show_diffstat: false,
+            confirm_destructive: true,
+            chord_timeout_ms: 1000,
+            mouse_copy: false,
+            colors: StatusColors {
+                new: Color::Green,
+                modified: Color::LightBlue,
+                deleted: Color::Red,
+                unmerged: Color::Magenta,
+                renamed: Color::Yellow,
+                unstaged: Color::Red,
+                staged: Color::Green,
+            },
             use_default_mappings: true,
             use_default_buttons: true,
             default_bindings: HashMap::new(),
@@ -230,17 +594,86 @@ impl Default for Config {
     }
 }
 
-pub fn parse_gitrs_config() -> Result<Config, Error> {
-    let home = std::env::var("HOME")
-        .map_err(|_| Error::Global("could not read `HOME` environment variable".to_string()))?;
-    let result = fs::File::open(home + "/.gitrsrc");
+// inline comments start with a `#` surrounded by whitespaces, outside of quotes, so that
+// values such as `#ff8800` are kept
+fn strip_inline_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = None;
+    for (idx, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#')
+                if previous.is_some_and(char::is_whitespace)
+                    && !text[idx + 1..].starts_with(|c: char| !c.is_whitespace()) =>
+            {
+                return text[..idx].trim_end();
+            }
+            _ => (),
+        }
+        previous = Some(c);
+    }
+    text
+}
 
+// shell commands are kept as is, `#` may be meaningful to the shell
+fn strip_action_comment(action: &str) -> &str {
+    match action.starts_with(['!', '>', '@']) {
+        true => action,
+        false => strip_inline_comment(action),
+    }
+}
+
+fn env_path(variable: &str) -> Option<PathBuf> {
+    std::env::var_os(variable)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+// `$GITRS_CONFIG` if set, else the first existing file among `$XDG_CONFIG_HOME/gitrs/config`,
+// `$HOME/.gitrsrc` and `%USERPROFILE%/.gitrsrc`, else where the config file should be created
+pub fn config_path() -> Result<PathBuf, Error> {
+    if let Some(path) = env_path("GITRS_CONFIG") {
+        return Ok(path);
+    }
+    // `~/.config` when `XDG_CONFIG_HOME` is not set, as in the XDG specification
+    let xdg = env_path("XDG_CONFIG_HOME")
+        .or_else(|| env_path("HOME").map(|dir| dir.join(".config")))
+        .map(|dir| dir.join("gitrs").join("config"));
+    let home = env_path("HOME").map(|dir| dir.join(".gitrsrc"));
+    let user_profile = match cfg!(windows) {
+        true => env_path("USERPROFILE").map(|dir| dir.join(".gitrsrc")),
+        false => None,
+    };
+    if let Some(path) = [&xdg, &home, &user_profile]
+        .into_iter()
+        .flatten()
+        .find(|path| path.is_file())
+    {
+        return Ok(path.clone());
+    }
+    // new config files are created in the home directory
+    [home, user_profile, xdg]
+        .into_iter()
+        .flatten()
+        .next()
+        .ok_or_else(|| {
+            Error::Global(
+                "could not locate the config file, set `GITRS_CONFIG` or `HOME`".to_string(),
+            )
+        })
+}
+
+pub fn parse_gitrs_config() -> Result<Config, Error> {
     let mut config: Config = Config::default();
 
-    if let Ok(file) = result {
+    // missing files and locations fall back to the default config
+    if let Ok(file) = config_path().and_then(|path| Ok(fs::File::open(path)?)) {
         let reader = BufReader::new(file);
-        for line in reader.lines() {
-            config.parse_line(&line?, false)?;
+        for (idx, line) in reader.lines().enumerate() {
+            config
+                .parse_line(&line?, false)
+                .map_err(|err| Error::ConfigLine(idx + 1, Box::new(err)))?;
         }
     }
 
@@ -0,0 +1,63 @@
+use std::{env, path::Path};
+
+use crate::model::config::Config;
+
+// single argument of the shell running the commands, `bash -c` or `cmd /C`
+pub fn shell_quote(arg: &str) -> String {
+    match cfg!(windows) {
+        // windows file names cannot contain double quotes
+        true => format!("\"{}\"", arg),
+        false => format!("'{}'", arg.replace('\'', r"'\''")),
+    }
+}
+
+// command opening `file` at `%(line)`, from `set editor_cmd` or guessed from the editor name
+pub fn editor_command(config: &Config, file: &str) -> String {
+    if !config.editor_cmd.is_empty() {
+        return config.editor_cmd.clone();
+    }
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|variable| env::var(variable).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    // the editor may come with arguments, e.g. `code --wait`
+    let program = editor.split_whitespace().next().unwrap_or_default();
+    let name = Path::new(program)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file = shell_quote(file);
+    match name.as_str() {
+        "code" | "codium" | "code-insiders" => format!("{} --goto {}:%(line)", editor, file),
+        "subl" | "hx" | "helix" | "zed" => format!("{} {}:%(line)", editor, file),
+        "notepad" => format!("{} {}", editor, file),
+        // vi, vim, nvim, nano, emacs, kak, micro...
+        _ => format!("{} +%(line) {}", editor, file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[cfg(unix)]
+    #[test]
+    fn quoted_file_names_reach_the_command_unchanged() {
+        for file in [
+            "main.rs",
+            "my file.rs",
+            "it's.rs",
+            "'quoted'.rs",
+            "$HOME `ls` \"a\".rs",
+        ] {
+            let command = format!("printf %s {}", shell_quote(file));
+            let output = Command::new("bash")
+                .args(["-c", &command])
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8_lossy(&output.stdout), file);
+        }
+    }
+}
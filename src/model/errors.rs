@@ -14,6 +14,12 @@ pub enum Error {
     ParseVariable(String),
     #[error("unable to parse button `{0}`")]
     ParseButton(String),
+    #[error("unable to parse mapping `{0}`")]
+    ParseMapping(String),
+    #[error("unknown keyword `{0}`")]
+    ParseKeyword(String),
+    #[error("line {0} of the config file: {1}")]
+    ConfigLine(usize, Box<Error>),
     #[error("invalid state index")]
     StateIndex,
     #[error("reached last match")]
@@ -1,13 +1,24 @@
 use std::{
     collections::HashMap,
     env,
-    io::{BufRead, BufReader},
-    process::{ChildStdout, Command, Stdio},
+    io::{self, BufReader, Read, Write},
+    path::PathBuf,
+    process::{Child, ChildStdout, Command, Stdio},
     str::FromStr,
+    thread,
 };
 
+use chrono::{DateTime, FixedOffset};
+
 use crate::model::{config::Config, errors::Error};
 
+// git executable followed by the arguments given with `set git`, e.g. `-c core.quotepath=false`
+fn git_command(config: &Config) -> Command {
+    let mut command = Command::new(&config.git_exe);
+    command.args(&config.git_args);
+    command
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Ord, PartialOrd, Hash)]
 #[repr(u8)]
 pub enum FileStatus {
@@ -16,6 +27,7 @@ pub enum FileStatus {
     New = 2,
     Modified = 3,
     Deleted = 4,
+    Renamed = 5,
 }
 impl Eq for FileStatus {}
 
@@ -26,9 +38,21 @@ impl FileStatus {
             FileStatus::Deleted => '-',
             FileStatus::New => '+',
             FileStatus::Unmerged => '@',
+            FileStatus::Renamed => 'R',
             FileStatus::None => panic!("None file status should not be displayed"),
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileStatus::Modified => "modified",
+            FileStatus::Deleted => "deleted",
+            FileStatus::New => "new",
+            FileStatus::Unmerged => "conflicted",
+            FileStatus::Renamed => "renamed",
+            FileStatus::None => "none",
+        }
+    }
 }
 
 impl FromStr for FileStatus {
@@ -40,6 +64,7 @@ impl FromStr for FileStatus {
             "new" => Ok(FileStatus::New),
             "deleted" => Ok(FileStatus::Deleted),
             "conflicted" => Ok(FileStatus::Unmerged),
+            "renamed" => Ok(FileStatus::Renamed),
             _ => Err(Error::ParseMappingScope(s.to_string())),
         }
     }
@@ -52,6 +77,15 @@ pub enum StagedStatus {
     Staged = 1,
 }
 
+impl StagedStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StagedStatus::Unstaged => "unstaged",
+            StagedStatus::Staged => "staged",
+        }
+    }
+}
+
 impl FromStr for StagedStatus {
     type Err = Error;
 
@@ -64,17 +98,43 @@ impl FromStr for StagedStatus {
     }
 }
 
+#[derive(Clone)]
 pub struct CommitInBlame {
     pub hash: String,
     pub author: String,
+    // formatted with `date_format`
     pub date: String,
+    pub time: DateTime<FixedOffset>,
+    // number of the line in the commit
+    pub orig_line: usize,
 }
 
 pub struct Stash {
     pub date: String,
+    pub time: DateTime<FixedOffset>,
     pub title: String,
 }
 
+pub struct Branch {
+    pub name: String,
+    pub remote: bool,
+    pub current: bool,
+}
+
+pub struct Tag {
+    pub date: String,
+    pub time: DateTime<FixedOffset>,
+    pub name: String,
+    // subject of the message of annotated tags
+    pub message: Option<String>,
+}
+
+pub struct Remote {
+    pub name: String,
+    pub fetch_url: String,
+    pub push_url: String,
+}
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum GitOp {
     Add,
@@ -88,12 +148,16 @@ pub struct GitFile {
     pub staged_status: FileStatus,
     init_unstaged_status: FileStatus,
     init_staged_status: FileStatus,
+    // path before a rename, staged along with the new one
+    pub orig_path: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct Commit {
     pub metadata: String,
     pub files: Vec<(FileStatus, String)>,
+    // previous path of the renamed files
+    pub renames: HashMap<String, String>,
     pub hash: String,
 }
 
@@ -104,6 +168,7 @@ impl GitFile {
             staged_status,
             init_unstaged_status: unstaged_status,
             init_staged_status: staged_status,
+            orig_path: None,
         }
     }
 
@@ -130,60 +195,87 @@ impl GitFile {
         None
     }
 
+    pub fn is_pending(&self) -> bool {
+        self.git_op().is_some()
+    }
+
     fn reinit(&mut self) {
         self.init_staged_status = self.staged_status;
         self.init_unstaged_status = self.unstaged_status;
     }
 }
 
-pub fn git_status_output(config: &Config) -> Result<String, Error> {
-    let mut child = Command::new(config.git_exe.clone())
-        .args(["status", "--short", "--no-renames"])
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Failed to execute git command");
-
-    let stdout = child.stdout.take().expect("Failed to capture stdout");
-    let reader = BufReader::new(stdout);
-
-    let lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
-    let output_text = lines.join("\n");
-
-    let status = child.wait().expect("Failed to wait on child");
+// entries separated by NUL characters, paths being neither quoted nor escaped, and relative to
+// the root of the repository
+// bytes, file names not being necessarily valid UTF-8
+pub fn git_status_output(config: &Config) -> Result<Vec<u8>, Error> {
+    let output = git_command(config)
+        .args(["status", "--porcelain", "-z"])
+        .args((!config.detect_renames).then_some("--no-renames"))
+        .output()
+        .map_err(|_| Error::GitCommand)?;
 
-    if !status.success() {
+    if !output.status.success() {
         return Err(Error::GitCommand);
     }
-    Ok(output_text)
+    Ok(output.stdout)
 }
 
 pub fn git_blame_output(
     file: String,
     revision: Option<String>,
+    ignore_whitespace: bool,
     config: &Config,
-) -> Result<String, Error> {
-    let mut args: Vec<String> = vec!["blame".to_string()];
+) -> Result<(BufReader<ChildStdout>, GitChild), Error> {
+    let mut args: Vec<String> = vec!["blame".to_string(), "--line-porcelain".to_string()];
+    if ignore_whitespace {
+        args.push("-w".to_string());
+    }
     if let Some(rev) = revision {
         args.push(rev);
     }
     args.push(file);
 
-    let output = Command::new(config.git_exe.clone())
+    let child = git_command(config)
         .args(args)
-        .output()
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
         .map_err(|_| Error::GitCommand)?;
 
-    if !output.status.success() {
-        return Err(Error::GitCommand);
-    }
+    git_child(child)
+}
+
+// command whose standard output is read as it comes, its errors being reported once it is done
+pub struct GitChild {
+    child: Child,
+    stderr: thread::JoinHandle<String>,
+}
 
-    Ok(String::from_utf8_lossy(&output.stdout)
-        .to_string()
-        .replace('\t', "    "))
+// stderr is read in the background, git blocking once the pipe is full otherwise
+fn git_child(mut child: Child) -> Result<(BufReader<ChildStdout>, GitChild), Error> {
+    let stdout = child.stdout.take().ok_or_else(|| Error::GitCommand)?;
+    let stderr = child.stderr.take().ok_or_else(|| Error::GitCommand)?;
+    let stderr = thread::spawn(move || tee(stderr, io::sink()));
+    Ok((BufReader::new(stdout), GitChild { child, stderr }))
 }
 
-pub fn git_parse_commit(output: &str) -> Result<Commit, Error> {
-    let mut lines = output.lines().map(String::from);
+// message of a command whose standard output was read, if it failed
+pub fn git_child_error(mut child: GitChild) -> Option<String> {
+    let status = child.child.wait().ok()?;
+    let stderr = child.stderr.join().unwrap_or_default();
+    if status.success() {
+        return None;
+    }
+    let message = stderr.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+    Some(match message.trim().is_empty() {
+        true => format!("git exited with {}", status),
+        false => message.trim().to_string(),
+    })
+}
+
+// parsed as the lines come, the files of big commits not being buffered as a whole
+pub fn git_parse_commit(mut lines: impl Iterator<Item = String>) -> Result<Commit, Error> {
     let mut metadata: Vec<String> = Vec::new();
 
     // Parse commit hash
@@ -206,6 +298,7 @@ pub fn git_parse_commit(output: &str) -> Result<Commit, Error> {
     // Read commit message and files
     let mut parsing_files = false;
     let mut files: Vec<(FileStatus, String)> = Vec::new();
+    let mut renames = HashMap::new();
 
     for line in lines {
         if !parsing_files {
@@ -220,13 +313,17 @@ pub fn git_parse_commit(output: &str) -> Result<Commit, Error> {
                 Some('M') => FileStatus::Modified,
                 Some('A') => FileStatus::New,
                 Some('D') => FileStatus::Deleted,
+                // `R<score>\t<old>\t<new>`
+                Some('R') => FileStatus::Renamed,
                 _ => break,
             };
-            let filename = line
-                .split('\t')
-                .nth(1)
-                .ok_or_else(|| Error::GitParsing)?
-                .to_string();
+            let mut paths = line.split('\t').skip(1);
+            let mut filename = paths.next().ok_or_else(|| Error::GitParsing)?.to_string();
+            if status == FileStatus::Renamed {
+                let new_filename = paths.next().ok_or_else(|| Error::GitParsing)?.to_string();
+                renames.insert(new_filename.clone(), filename);
+                filename = new_filename;
+            }
             files.push((status, filename));
         }
     }
@@ -234,6 +331,7 @@ pub fn git_parse_commit(output: &str) -> Result<Commit, Error> {
     let commit = Commit {
         metadata: metadata.join("\n"),
         files,
+        renames,
         hash: commit_hash.to_string(),
     };
     Ok(commit)
@@ -244,60 +342,509 @@ pub fn git_stash_output(config: &Config) -> Result<String, Error> {
         "stash".to_string(),
         "list".to_string(),
         "--format=%cd\t%s".to_string(),
-        "--date=iso-local".to_string(),
+        "--date=iso-strict-local".to_string(),
     ];
-    let output = Command::new(config.git_exe.clone())
+    let output = git_command(config)
+        .args(args)
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn git_stash_push(
+    message: &str,
+    include_untracked: bool,
+    config: &Config,
+) -> Result<(), Error> {
+    let mut args = vec!["stash", "push"];
+    if include_untracked {
+        args.push("--include-untracked");
+    }
+    if !message.is_empty() {
+        args.extend(["-m", message]);
+    }
+    let output = git_command(config)
+        .args(args)
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().next().unwrap_or("git stash push failed");
+        return Err(Error::Global(message.to_string()));
+    }
+    Ok(())
+}
+
+pub fn git_branch_output(config: &Config) -> Result<String, Error> {
+    let output = git_command(config)
+        .args(["branch", "--all", "--no-color"])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Returns the paths of the tracked files, relative to the repository root.
+pub fn git_ls_files(config: &Config) -> Result<Vec<String>, Error> {
+    let output = git_command(config)
+        .args(["ls-files", "-z", "--full-name"])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut paths: Vec<String> = stdout
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| path.to_string())
+        .collect();
+    // conflicted files are listed once per stage
+    paths.dedup();
+    Ok(paths)
+}
+
+// names without the `tags/` prefix `refname:short` adds when a branch has the same name
+pub fn git_tag_output(config: &Config) -> Result<String, Error> {
+    let output = git_command(config)
+        .args([
+            "tag",
+            "--sort=-creatordate",
+            "--format=%(creatordate:iso-strict)%09%(objecttype)%09%(refname:lstrip=2)%09%(contents:subject)",
+        ])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn git_tag_create(name: &str, config: &Config) -> Result<(), Error> {
+    let output = git_command(config)
+        .args(["tag", name])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().next().unwrap_or("git tag failed");
+        return Err(Error::Global(message.to_string()));
+    }
+    Ok(())
+}
+
+/// Discards the unstaged changes of a tracked file, `git checkout` is used
+/// by versions of git without `restore`.
+pub fn git_discard(filename: &str, config: &Config) -> Result<(), Error> {
+    let mut output = git_command(config)
+        .args(["--literal-pathspecs", "restore", "--", filename])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() && stderr.contains("is not a git command") {
+        output = git_command(config)
+            .args(["--literal-pathspecs", "checkout", "--", filename])
+            .output()
+            .map_err(|_| Error::GitCommand)?;
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().next().unwrap_or("git restore failed");
+        return Err(Error::Global(message.to_string()));
+    }
+    Ok(())
+}
+
+// untracked files, ignored ones being kept as git would
+pub fn git_clean(filename: &str, config: &Config) -> Result<(), Error> {
+    // untracked directories are listed with a trailing slash
+    let flags = match filename.ends_with('/') {
+        true => "-fd",
+        false => "-f",
+    };
+    let output = git_command(config)
+        .args(["--literal-pathspecs", "clean", flags, "--", filename])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().next().unwrap_or("git clean failed");
+        return Err(Error::Global(message.to_string()));
+    }
+    Ok(())
+}
+
+// `git add` of a conflicted file, staging its deletion when it is no longer there
+pub fn git_mark_resolved(filename: &str, config: &Config) -> Result<(), Error> {
+    let output = git_command(config)
+        .args(["--literal-pathspecs", "add", "-A", "--", filename])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().next().unwrap_or("git add failed");
+        return Err(Error::Global(message.to_string()));
+    }
+    Ok(())
+}
+
+pub fn git_remote_output(config: &Config) -> Result<String, Error> {
+    let output = git_command(config)
+        .args(["remote", "-v"])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn git_diff_is_empty(range: &str, config: &Config) -> Result<bool, Error> {
+    let status = git_command(config)
+        .args(["diff", "--quiet", range])
+        .status()
+        .map_err(|_| Error::GitCommand)?;
+
+    match status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        _ => Err(Error::GitCommand),
+    }
+}
+
+/// Returns the number of commits ahead and behind the upstream, if any.
+pub fn git_ahead_behind(config: &Config) -> Option<(usize, usize)> {
+    let output = git_command(config)
+        .args(["rev-list", "--left-right", "--count", "@{u}...HEAD"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    // `<behind>\t<ahead>`, the upstream being on the left
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (behind, ahead) = stdout.trim().split_once('\t')?;
+    Some((ahead.parse().ok()?, behind.parse().ok()?))
+}
+
+pub fn git_current_branch(config: &Config) -> Result<String, Error> {
+    let output = git_command(config)
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub fn git_commit(message: &str, amend: bool, config: &Config) -> Result<String, Error> {
+    let mut args = vec!["commit", "-m", message];
+    if amend {
+        args.push("--amend");
+    }
+    let output = git_command(config)
         .args(args)
         .output()
         .map_err(|_| Error::GitCommand)?;
 
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().next().unwrap_or("git commit failed");
+        return Err(Error::Global(message.to_string()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or("").to_string())
+}
+
+pub fn git_commit_subject(hash: &str, config: &Config) -> Result<String, Error> {
+    let output = git_command(config)
+        .args(["log", "-1", "--format=%s", hash])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
     if !output.status.success() {
         return Err(Error::GitCommand);
     }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.trim_end().to_string())
+}
+
+/// Returns the full hash of the commit `rev` points to.
+pub fn git_commit_hash(rev: &str, config: &Config) -> Result<String, Error> {
+    let output = git_command(config)
+        .args(["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.trim_end().to_string())
+}
+
+/// Returns the subject and the body of the last commit message.
+pub fn git_head_message(config: &Config) -> Result<(String, String), Error> {
+    let output = git_command(config)
+        .args(["log", "-1", "--format=%s%x00%b"])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::Global("no commit to amend yet".to_string()));
+    }
+
+    let message = String::from_utf8_lossy(&output.stdout);
+    let (subject, body) = message.split_once('\0').unwrap_or((&message, ""));
+    Ok((subject.to_string(), body.trim_end().to_string()))
+}
+
+// forward everything to `writer` while keeping a copy of it
+fn tee(mut reader: impl Read, mut writer: impl Write) -> String {
+    let mut captured = Vec::new();
+    let mut buffer = [0; 1024];
+    while let Ok(n) = reader.read(&mut buffer) {
+        if n == 0 {
+            break;
+        }
+        let _ = writer.write_all(&buffer[..n]);
+        let _ = writer.flush();
+        captured.extend_from_slice(&buffer[..n]);
+    }
+    String::from_utf8_lossy(&captured).to_string()
+}
+
+// progress is reported on the same line using carriage returns
+fn last_line(output: &str) -> Option<&str> {
+    output
+        .split(['\r', '\n'])
+        .map(|line| line.trim())
+        .rfind(|line| !line.is_empty())
+}
+
+// run a git command talking to a remote (push, pull, fetch) while showing its progress,
+// returning a one line summary of its output
+pub fn git_remote_command(args: &[String], config: &Config) -> Result<String, Error> {
+    let mut child = git_command(config)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| Error::GitCommand)?;
+
+    let stderr = child.stderr.take().ok_or_else(|| Error::GitCommand)?;
+    let stderr_thread = thread::spawn(move || tee(stderr, io::stderr()));
+    let stdout = child.stdout.take().ok_or_else(|| Error::GitCommand)?;
+    let stdout = tee(stdout, io::stdout());
+    let stderr = stderr_thread.join().unwrap_or_default();
+    let status = child.wait()?;
+
+    if !status.success() {
+        let message = stderr
+            .lines()
+            .find(|line| line.starts_with("fatal:") || line.starts_with("error:"))
+            .or_else(|| last_line(&stderr))
+            .unwrap_or("unknown error");
+        return Err(Error::Global(format!(
+            "git {} failed: {}",
+            args[0], message
+        )));
+    }
+
+    let summary = last_line(&stdout)
+        .or_else(|| last_line(&stderr))
+        .unwrap_or("done");
+    Ok(format!("git {}: {}", args[0], summary))
+}
+
+pub fn git_diff_output(args: &[String], config: &Config) -> Result<String, Error> {
+    let output = git_command(config)
+        .args(args)
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    // `diff --no-index` exits with 1 when the files differ
+    if !matches!(output.status.code(), Some(0) | Some(1)) {
+        return Err(Error::GitCommand);
+    }
 
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
-pub fn git_show_output(revision: &Option<String>, config: &Config) -> Result<String, Error> {
+/// Builds a patch with the header of the unstaged diff of `file` and only its `hunk_idx`th hunk.
+pub fn git_hunk_patch(file: &str, hunk_idx: usize, config: &Config) -> Result<Vec<u8>, Error> {
+    let output = git_command(config)
+        .args(["diff", "--no-color", "--no-ext-diff", "--", file])
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+
+    // work on bytes, the patch has to match the file content exactly
+    let mut header: Vec<&[u8]> = Vec::new();
+    let mut hunks: Vec<Vec<&[u8]>> = Vec::new();
+    for line in output.stdout.split_inclusive(|c| *c == b'\n') {
+        if line.starts_with(b"@@ ") {
+            hunks.push(vec![line]);
+        } else if let Some(hunk) = hunks.last_mut() {
+            hunk.push(line);
+        } else {
+            header.push(line);
+        }
+    }
+    if header
+        .iter()
+        .any(|line| line.starts_with(b"Binary files") || line.starts_with(b"GIT binary patch"))
+    {
+        return Err(Error::Global(format!("{} is a binary file", file)));
+    }
+    let hunk = hunks
+        .get(hunk_idx)
+        .ok_or_else(|| Error::Global("hunk not found, reload the diff".to_string()))?;
+
+    let mut patch = header.concat();
+    patch.extend(hunk.concat());
+    if !patch.ends_with(b"\n") {
+        patch.push(b'\n');
+    }
+    Ok(patch)
+}
+
+pub fn git_apply_cached(patch: &[u8], config: &Config) -> Result<(), Error> {
+    let mut child = git_command(config)
+        .args(["apply", "--cached", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| Error::GitCommand)?;
+
+    // dropping stdin closes it so that git starts applying
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| Error::GitCommand)?
+        .write_all(patch)?;
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().next().unwrap_or("git apply failed");
+        return Err(Error::Global(message.to_string()));
+    }
+    Ok(())
+}
+
+// metadata and changed files only, `open_git_show` paging the whole patch
+pub fn git_show_output(
+    revision: &Option<String>,
+    config: &Config,
+) -> Result<(BufReader<ChildStdout>, GitChild), Error> {
     let mut args = vec![
         "show".to_string(),
         "--decorate".to_string(),
         "--name-status".to_string(),
         "--stat".to_string(),
-        "--no-renames".to_string(),
     ];
+    if !config.detect_renames {
+        args.push("--no-renames".to_string());
+    }
     if let Some(rev) = revision {
         args.push(rev.clone());
     }
 
-    let output = Command::new(config.git_exe.clone())
+    let child = git_command(config)
+        .args(args)
+        .stdout(Stdio::piped())
+        // reported with `git_child_error` once the output is read
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| Error::GitCommand)?;
+
+    git_child(child)
+}
+
+// lines added and deleted in each file of a commit, none for binary files
+pub fn git_numstat(
+    hash: &str,
+    config: &Config,
+) -> Result<HashMap<String, Option<(usize, usize)>>, Error> {
+    let mut args = vec!["show", "--format=", "--numstat", "-z"];
+    if !config.detect_renames {
+        args.push("--no-renames");
+    }
+    args.push(hash);
+    let output = git_command(config)
         .args(args)
+        .stderr(Stdio::null())
         .output()
         .map_err(|_| Error::GitCommand)?;
 
     if !output.status.success() {
         return Err(Error::GitCommand);
     }
-
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.split('\0');
+    let mut stats = HashMap::new();
+    while let Some(field) = fields.next() {
+        let mut parts = field.trim_start_matches('\n').splitn(3, '\t');
+        let (Some(added), Some(deleted), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        // `<added>\t<deleted>\t\0<old>\0<new>` for renames
+        let path = match path.is_empty() {
+            true => fields.nth(1).ok_or_else(|| Error::GitParsing)?,
+            false => path,
+        };
+        // `-\t-` for binary files
+        let stat = added.parse().ok().zip(deleted.parse().ok());
+        stats.insert(path.to_string(), stat);
+    }
+    Ok(stats)
 }
 
 pub fn git_pager_output(
     command: &str,
-    git_exe: String,
     user_args: Vec<String>,
-) -> Result<BufReader<ChildStdout>, Error> {
+    config: &Config,
+) -> Result<(BufReader<ChildStdout>, GitChild), Error> {
     let mut args: Vec<String> = vec![command.to_string(), "--color=always".to_string()];
     args.extend(user_args);
 
-    let command = Command::new(git_exe)
+    // the child is kept to report its errors once its output is read
+    let child = git_command(config)
         .args(args)
         .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()?;
 
-    let stdout = command.stdout.ok_or_else(|| Error::GitParsing)?;
-
-    Ok(BufReader::new(stdout))
+    git_child(child)
 }
 
 #[cfg(target_os = "linux")]
@@ -314,20 +861,23 @@ pub fn adapt_repo_root(root: String) -> String {
     root
 }
 
-pub fn set_git_dir(config: &Config) -> Result<(), Error> {
+// move to the root of the repository, returning the directory to go back to on exit
+pub fn set_git_dir(config: &Config) -> Result<PathBuf, Error> {
+    let original_dir = env::current_dir()?;
     // get git repo root dir
-    let output = Command::new(config.git_exe.clone())
+    let output = git_command(config)
         .args(["rev-parse", "--show-toplevel"])
         .output()
-        .expect("Failed to execute git command");
+        .map_err(|_| Error::GitCommand)?;
 
     if !output.status.success() {
         return Err(Error::NotInGitRepo);
     }
     let mut repo_root = String::from_utf8_lossy(&output.stdout);
     repo_root = adapt_repo_root(repo_root.to_string().clone()).into();
-    env::set_current_dir(repo_root.trim()).expect("Failed to change directory");
-    Ok(())
+    env::set_current_dir(repo_root.trim())
+        .map_err(|_| Error::Global("could not move to the root of the repository".to_string()))?;
+    Ok(original_dir)
 }
 
 pub fn git_add_restore(files: &mut HashMap<String, GitFile>, config: &Config) {
@@ -336,6 +886,7 @@ pub fn git_add_restore(files: &mut HashMap<String, GitFile>, config: &Config) {
         for (filename, git_file) in files.iter() {
             if Some(*op) == git_file.git_op() {
                 files_to_op.push(filename.clone());
+                files_to_op.extend(git_file.orig_path.clone());
             }
         }
         if files_to_op.is_empty() {
@@ -346,8 +897,11 @@ pub fn git_add_restore(files: &mut HashMap<String, GitFile>, config: &Config) {
             GitOp::Restore => vec!["restore", "--staged"],
             GitOp::RmCached => vec!["rm", "--cached"],
         };
-        let mut git_add_output = Command::new(config.git_exe.clone())
+        // paths are not pathspecs, `*` or `:` are part of the file name
+        let mut git_add_output = git_command(config)
+            .arg("--literal-pathspecs")
             .args(&args)
+            .arg("--")
             .args(files_to_op.iter().map(|s| s.as_str()))
             .stdout(Stdio::piped())
             .spawn()
@@ -385,6 +939,56 @@ pub fn get_previous_filename(rev: &str, current_filename: &str) -> Result<String
     Ok(current_filename.to_string())
 }
 
+/// Returns the line of the parent of `rev`, or of `HEAD` for the working tree, matching `line`
+/// of `rev`. Lines changed by `rev` are mapped to the start of their hunk.
+pub fn git_line_in_parent(
+    rev: Option<&str>,
+    paths: &[String],
+    line: usize,
+    config: &Config,
+) -> Result<usize, Error> {
+    let mut args: Vec<String> = vec!["diff".to_string(), "-U0".to_string(), "-M".to_string()];
+    match rev {
+        Some(rev) => args.extend([format!("{rev}^"), rev.to_string()]),
+        None => args.push("HEAD".to_string()),
+    }
+    args.push("--".to_string());
+    args.extend(paths.iter().cloned());
+    let output = git_command(config)
+        .args(args)
+        .output()
+        .map_err(|_| Error::GitCommand)?;
+
+    if !output.status.success() {
+        return Err(Error::GitCommand);
+    }
+
+    // `@@ -start[,len] +start[,len] @@`, the start being the line before when the length is 0
+    let range = |range: &str| -> Option<(usize, usize)> {
+        let (start, len) = range.split_once(',').unwrap_or((range, "1"));
+        let (start, len) = (start.parse::<usize>().ok()?, len.parse::<usize>().ok()?);
+        Some((if len == 0 { start + 1 } else { start }, len))
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut parent_line = line;
+    for hunk in stdout.lines().filter(|l| l.starts_with("@@ ")) {
+        let mut parts = hunk.split_whitespace().skip(1);
+        let (Some(old), Some(new)) = (parts.next(), parts.next()) else {
+            return Err(Error::GitParsing);
+        };
+        let old = range(&old[1..]).ok_or_else(|| Error::GitParsing)?;
+        let new = range(&new[1..]).ok_or_else(|| Error::GitParsing)?;
+        if line < new.0 {
+            break;
+        }
+        if line < new.0 + new.1 {
+            return Ok(old.0);
+        }
+        parent_line = line + (old.0 + old.1) - (new.0 + new.1);
+    }
+    Ok(parent_line)
+}
+
 pub fn is_valid_git_rev(rev: &str) -> bool {
     let output = Command::new("git")
         .args(["rev-parse", "--verify", rev])
@@ -392,3 +996,26 @@ pub fn is_valid_git_rev(rev: &str) -> bool {
 
     matches!(output, Ok(output) if output.status.success())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead as _;
+
+    // a failing command writing much more than a pipe buffer to stderr before its output ends
+    #[cfg(unix)]
+    #[test]
+    fn large_stderr_does_not_block_the_output() {
+        let script = "head -c 1000000 /dev/zero | tr '\\0' x >&2; echo line; exit 1";
+        let config = Config {
+            git_exe: "sh".to_string(),
+            git_args: vec!["-c".to_string(), script.to_string(), "sh".to_string()],
+            ..Config::default()
+        };
+        let (stdout, child) = git_pager_output("log", Vec::new(), &config).unwrap();
+        let lines: Vec<String> = stdout.lines().map_while(Result::ok).collect();
+        assert_eq!(lines, ["line"]);
+        let message = git_child_error(child).unwrap();
+        assert_eq!(message.len(), 1_000_000);
+    }
+}
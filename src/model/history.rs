@@ -0,0 +1,72 @@
+use std::{fs, path::PathBuf};
+
+use crate::model::app_state::InputState;
+
+// number of entries kept for each kind of input
+const HISTORY_SIZE: usize = 100;
+
+/// Previously submitted searches and commands, shared between sessions.
+#[derive(Default)]
+pub struct History {
+    pub search: Vec<String>,
+    pub command: Vec<String>,
+}
+
+fn history_path() -> Option<PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    Some(PathBuf::from(home).join(".gitrs_history"))
+}
+
+impl History {
+    pub fn load() -> Self {
+        let mut history = Self::default();
+        let content = history_path().and_then(|path| fs::read_to_string(path).ok());
+        for line in content.unwrap_or_default().lines() {
+            if let Some(entry) = line.strip_prefix('/') {
+                history.search.push(entry.to_string());
+            } else if let Some(entry) = line.strip_prefix(':') {
+                history.command.push(entry.to_string());
+            }
+        }
+        history
+    }
+
+    pub fn entries(&self, input_state: &InputState) -> &[String] {
+        match input_state {
+            InputState::Search => &self.search,
+            InputState::Command => &self.command,
+            _ => &[],
+        }
+    }
+
+    pub fn push(&mut self, input_state: &InputState, entry: &str) {
+        let entries = match input_state {
+            InputState::Search => &mut self.search,
+            InputState::Command => &mut self.command,
+            _ => return,
+        };
+        if entry.is_empty() {
+            return;
+        }
+        entries.retain(|e| e != entry);
+        entries.push(entry.to_string());
+        if entries.len() > HISTORY_SIZE {
+            entries.remove(0);
+        }
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = history_path() else {
+            return;
+        };
+        let lines: Vec<String> = self
+            .search
+            .iter()
+            .map(|entry| format!("/{}", entry))
+            .chain(self.command.iter().map(|entry| format!(":{}", entry)))
+            .collect();
+        // the history is a convenience, failing to save it should not bother the user
+        let _ = fs::write(path, lines.join("\n") + "\n");
+    }
+}
@@ -1,5 +1,8 @@
 pub mod action;
 pub mod app_state;
+pub mod clipboard;
 pub mod config;
+pub mod editor;
 pub mod errors;
 pub mod git;
+pub mod history;
@@ -0,0 +1,186 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+#[derive(Clone, Copy)]
+enum RefKind {
+    Head,
+    Branch,
+    Tag,
+}
+
+impl RefKind {
+    fn style(self) -> Style {
+        match self {
+            RefKind::Head => Style::from(Color::Cyan).add_modifier(Modifier::BOLD),
+            RefKind::Branch => Style::from(Color::Green).add_modifier(Modifier::BOLD),
+            RefKind::Tag => Style::from(Color::Yellow).add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+// char range of the refs between the parentheses following the hash of a log line, as in
+// `commit 1a2b3c4 (HEAD -> main, tag: v1)` or `* 1a2b3c4 (origin/main) message`
+fn decorations_range(text: &str) -> Option<(usize, usize)> {
+    let graph_len = text.len() - text.trim_start_matches(|c| "*|/\\_-. ".contains(c)).len();
+    let rest = &text[graph_len..];
+    let rest = rest.strip_prefix("commit ").unwrap_or(rest);
+    let (hash, rest) = rest.split_once(' ')?;
+    if !(4..=64).contains(&hash.len()) || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let refs = rest.strip_prefix('(')?;
+    let len = refs.find(')')?;
+    let start = text[..text.len() - refs.len()].chars().count();
+    Some((start, start + refs[..len].chars().count()))
+}
+
+// kind of each char of the refs, separators excluded
+fn ref_kinds(refs: &str) -> Vec<Option<RefKind>> {
+    let mut kinds = Vec::new();
+    for (idx, name) in refs.split(", ").enumerate() {
+        if idx > 0 {
+            kinds.extend([None, None]);
+        }
+        if let Some(branch) = name.strip_prefix("HEAD -> ") {
+            kinds.extend(std::iter::repeat(Some(RefKind::Head)).take(4));
+            kinds.extend([None; 4]);
+            kinds.extend(std::iter::repeat(Some(RefKind::Branch)).take(branch.chars().count()));
+            continue;
+        }
+        let kind = match name {
+            "HEAD" => RefKind::Head,
+            _ if name.starts_with("tag: ") => RefKind::Tag,
+            _ => RefKind::Branch,
+        };
+        kinds.extend(std::iter::repeat(Some(kind)).take(name.chars().count()));
+    }
+    kinds
+}
+
+// color the refs of a log line, keeping the colors already given by git
+pub fn decorate_line(line: Line<'static>) -> Line<'static> {
+    let text: String = line
+        .spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect();
+    let Some((start, end)) = decorations_range(&text) else {
+        return line;
+    };
+    let refs: String = text.chars().skip(start).take(end - start).collect();
+    let kinds = ref_kinds(&refs);
+
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut pos: usize = 0;
+    for span in line.spans {
+        for c in span.content.chars() {
+            let kind = match pos.checked_sub(start) {
+                Some(offset) if pos < end => kinds.get(offset).copied().flatten(),
+                _ => None,
+            };
+            let style = match kind {
+                Some(kind) if span.style.fg.is_none() => span.style.patch(kind.style()),
+                _ => span.style,
+            };
+            match spans.last_mut() {
+                Some(last) if last.style == style => last.content.to_mut().push(c),
+                _ => spans.push(Span::styled(c.to_string(), style)),
+            }
+            pos += 1;
+        }
+    }
+    Line::from(spans).style(line.style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // refs and their kind, as styled by `decorate_line`
+    fn decorated(line: Line<'static>) -> Vec<(String, Option<Color>)> {
+        decorate_line(line)
+            .spans
+            .into_iter()
+            .map(|span| (span.content.to_string(), span.style.fg))
+            .collect()
+    }
+
+    fn refs_of(text: &str) -> Option<&str> {
+        let (start, end) = decorations_range(text)?;
+        let start = text.char_indices().nth(start)?.0;
+        let end = text
+            .char_indices()
+            .nth(end)
+            .map_or(text.len(), |(idx, _)| idx);
+        Some(&text[start..end])
+    }
+
+    #[test]
+    fn decorations_of_each_log_format() {
+        for (line, refs) in [
+            (
+                "commit 0123456789abcdef0123456789abcdef01234567 (HEAD -> main, tag: v1)",
+                Some("HEAD -> main, tag: v1"),
+            ),
+            ("c182c05 (origin/main) message", Some("origin/main")),
+            (
+                "* | 7f5fb46 (tag: v1.0) Merge branch 'hotfix'",
+                Some("tag: v1.0"),
+            ),
+            ("| * commit 41d34fc (side)", Some("side")),
+            ("*   c182c05 (HEAD) détaché", Some("HEAD")),
+            ("c182c05 message", None),
+            ("c182c05", None),
+            ("| |/", None),
+            ("Author: someone (HEAD)", None),
+            ("    (HEAD -> main) in a message", None),
+        ] {
+            assert_eq!(refs_of(line), refs, "{}", line);
+        }
+    }
+
+    #[test]
+    fn each_ref_kind_is_styled() {
+        let line = Line::raw("* c182c05 (HEAD -> main, origin/main, tag: v1.0, HEAD) message");
+        let cyan = Some(Color::Cyan);
+        let green = Some(Color::Green);
+        let yellow = Some(Color::Yellow);
+        assert_eq!(
+            decorated(line),
+            vec![
+                ("* c182c05 (".to_string(), None),
+                ("HEAD".to_string(), cyan),
+                (" -> ".to_string(), None),
+                ("main".to_string(), green),
+                (", ".to_string(), None),
+                ("origin/main".to_string(), green),
+                (", ".to_string(), None),
+                ("tag: v1.0".to_string(), yellow),
+                (", ".to_string(), None),
+                ("HEAD".to_string(), cyan),
+                (") message".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn colors_given_by_git_are_kept() {
+        let line = Line::from(vec![
+            Span::raw("c182c05 ("),
+            Span::styled("main", Style::from(Color::Red)),
+            Span::raw(", tag: v1)"),
+        ]);
+        assert_eq!(
+            decorated(line),
+            vec![
+                ("c182c05 (".to_string(), None),
+                ("main".to_string(), Some(Color::Red)),
+                (", ".to_string(), None),
+                ("tag: v1".to_string(), Some(Color::Yellow)),
+                (")".to_string(), None),
+            ]
+        );
+    }
+}
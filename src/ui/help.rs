@@ -0,0 +1,93 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::model::config::{Config, MappingScope};
+
+pub struct Help {
+    lines: Vec<Line<'static>>,
+    scroll: usize,
+    height: usize,
+}
+
+impl Help {
+    // bindings of each scope, the most specific scopes first as for key handling
+    pub fn new(config: &Config, scopes: &[MappingScope]) -> Self {
+        let mut lines = Vec::new();
+        for scope in scopes {
+            let mut bindings = config.get_bindings(scope.clone());
+            if bindings.is_empty() {
+                continue;
+            }
+            bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let key_width = bindings
+                .iter()
+                .map(|(key, _)| key.chars().count())
+                .max()
+                .unwrap_or(0);
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::styled(
+                scope.to_string(),
+                Style::from(Color::Yellow).add_modifier(Modifier::BOLD),
+            ));
+            for (key, action) in bindings {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {:width$}  ", key, width = key_width),
+                        Style::from(Color::Blue),
+                    ),
+                    Span::raw(action.to_string()),
+                ]));
+            }
+        }
+        Self {
+            lines,
+            scroll: 0,
+            height: 0,
+        }
+    }
+
+    pub fn scroll(&mut self, delta: isize) {
+        let max_scroll = self.lines.len().saturating_sub(self.height);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max_scroll);
+    }
+
+    pub fn page(&self) -> isize {
+        (self.height / 2).max(1) as isize
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        let width = rect.width.saturating_sub(4).min(100);
+        let height = rect.height.saturating_sub(2);
+        let area = Rect {
+            x: rect.x + (rect.width - width) / 2,
+            y: rect.y + (rect.height - height) / 2,
+            width,
+            height,
+        };
+        // borders take two rows
+        self.height = height.saturating_sub(2) as usize;
+        self.scroll(0);
+
+        let title = match self.lines.len() > self.height {
+            true => format!(
+                " Help {}-{}/{} ",
+                self.scroll + 1,
+                (self.scroll + self.height).min(self.lines.len()),
+                self.lines.len()
+            ),
+            false => " Help ".to_string(),
+        };
+        let paragraph = Paragraph::new(self.lines.clone())
+            .scroll((self.scroll as u16, 0))
+            .block(Block::default().title(title).borders(Borders::ALL));
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,88 @@
+// lines of the pager matching a regex, with some lines of context around them
+
+use regex::Regex;
+
+pub struct LineFilter {
+    regex: Regex,
+    context: usize,
+    // indices of the displayed lines, in increasing order
+    lines: Vec<usize>,
+    rendered: Vec<String>,
+    // number of lines already scanned
+    scanned: usize,
+    // lines still to display after the last match
+    trailing: usize,
+}
+
+fn strip(line: &str) -> String {
+    String::from_utf8(strip_ansi_escapes::strip(line.as_bytes())).unwrap_or_default()
+}
+
+impl LineFilter {
+    pub fn new(regex: Regex, context: usize) -> Self {
+        LineFilter {
+            regex,
+            context,
+            lines: Vec::new(),
+            rendered: Vec::new(),
+            scanned: 0,
+            trailing: 0,
+        }
+    }
+
+    pub fn pattern(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    pub fn context(&self) -> usize {
+        self.context
+    }
+
+    fn push(&mut self, lines: &[String], idx: usize) {
+        if self.lines.last().is_some_and(|&last| last >= idx) {
+            return;
+        }
+        self.lines.push(idx);
+        self.rendered.push(lines[idx].clone());
+    }
+
+    // scan the lines loaded since the last update
+    pub fn update(&mut self, lines: &[String]) {
+        for (idx, line) in lines.iter().enumerate().skip(self.scanned) {
+            if self.regex.is_match(&strip(line)) {
+                for before in idx.saturating_sub(self.context)..idx {
+                    self.push(lines, before);
+                }
+                self.push(lines, idx);
+                self.trailing = self.context;
+            } else if self.trailing > 0 {
+                self.push(lines, idx);
+                self.trailing -= 1;
+            }
+        }
+        self.scanned = lines.len();
+    }
+
+    pub fn rendered(&self) -> &[String] {
+        &self.rendered
+    }
+
+    pub fn line_idx(&self, row: usize) -> Option<usize> {
+        self.lines.get(row).copied()
+    }
+
+    pub fn row_of_line(&self, idx: usize) -> Option<usize> {
+        self.lines.binary_search(&idx).ok()
+    }
+
+    // row of `idx`, or of the first displayed line after it
+    pub fn nearest_row(&self, idx: usize) -> usize {
+        self.lines
+            .partition_point(|&line| line < idx)
+            .min(self.lines.len().saturating_sub(1))
+    }
+}
@@ -1,2 +1,9 @@
+pub mod decorations;
+pub mod help;
+pub mod line_filter;
 pub mod pager_widget;
+pub mod palette;
+pub mod side_by_side;
+pub mod syntax;
 pub mod utils;
+pub mod word_diff;
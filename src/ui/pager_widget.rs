@@ -1,21 +1,41 @@
-use std::cmp::min;
+use std::{cell::RefCell, cmp::min, collections::HashMap};
 
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    widgets::{Block, Borders, List, ListItem, ListState, StatefulWidget},
+    style::Style,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
 };
 
-use crate::{model::app_state::AppState, ui::utils::highlight_style};
+use crate::{
+    model::app_state::AppState,
+    ui::{
+        decorations::decorate_line,
+        utils::{highlight_style, line_number_style},
+        word_diff::word_diff_line,
+    },
+};
 use ansi_to_tui::IntoText as _;
+use unicode_width::UnicodeWidthChar as _;
+
+// rows taken by the wrapped lines, by line index and width, kept by the view between frames
+pub type RowCounts = HashMap<(usize, usize), usize>;
 
 #[derive(Clone, Default)]
 pub struct PagerWidget {
     inner: List<'static>,
     state: ListState,
+    // index of the line displayed on each row
+    rows: Vec<usize>,
+}
+
+// at most half of the view, so that some row can always be selected
+pub fn clamp_scrolloff(scrolloff: usize, height: usize) -> usize {
+    min(scrolloff, height.saturating_sub(1) / 2)
 }
 
-// duplicates some logic
+// keep the selected index in the view after scrolling
 pub fn adapt_index_in_frame(
     offset: usize,
     scrolloff: usize,
@@ -23,16 +43,87 @@ pub fn adapt_index_in_frame(
     height: usize,
     len: usize,
 ) -> usize {
+    let scrolloff = clamp_scrolloff(scrolloff, height);
     if offset + scrolloff >= index {
         index = offset + scrolloff;
     }
-    if index >= len {
-        index = len - 1;
-    }
     if offset + height > scrolloff && index >= offset + height - scrolloff {
         index = offset + height - scrolloff - 1;
     }
-    index
+    min(index, len.saturating_sub(1))
+}
+
+// row and column of each char once wrapped at `width`, in terminal cells, wide chars (CJK,
+// emoji) not fitting at the end of a row going to the next one
+pub fn wrap_positions(chars: &[char], width: Option<usize>) -> Vec<(usize, usize)> {
+    let mut positions = Vec::with_capacity(chars.len());
+    let (mut row, mut x) = (0, 0);
+    for c in chars {
+        let char_width = c.width().unwrap_or(0);
+        if let Some(width) = width {
+            if x > 0 && x + char_width > width {
+                row += 1;
+                x = 0;
+            }
+        }
+        positions.push((row, x));
+        x += char_width;
+    }
+    positions
+}
+
+pub fn wrap_line(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    if width == 0 {
+        return vec![line];
+    }
+    let chars: Vec<(char, Style)> = line
+        .spans
+        .iter()
+        .flat_map(|span| span.content.chars().map(|c| (c, span.style)))
+        .collect();
+    let just_chars: Vec<char> = chars.iter().map(|(c, _)| *c).collect();
+    let positions = wrap_positions(&just_chars, Some(width));
+
+    let mut rows: Vec<Vec<Span>> = vec![Vec::new()];
+    for ((c, style), (row, _)) in chars.into_iter().zip(positions) {
+        if row == rows.len() {
+            rows.push(Vec::new());
+        }
+        let current = rows.last_mut().unwrap();
+        match current.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push(c),
+            _ => current.push(Span::styled(c.to_string(), style)),
+        }
+    }
+    rows.into_iter()
+        .map(|spans| Line::from(spans).style(line.style))
+        .collect()
+}
+
+fn item_text(
+    items: &[String],
+    idx: usize,
+    (word_diff, decorations): (bool, bool),
+) -> Text<'static> {
+    let mut text = items[idx].as_bytes().into_text().unwrap_or_default();
+    if word_diff && text.lines.len() == 1 {
+        let line = text.lines.remove(0);
+        text.lines.push(word_diff_line(items, idx, line));
+    }
+    if decorations && text.lines.len() == 1 {
+        let line = text.lines.remove(0);
+        text.lines.push(decorate_line(line));
+    }
+    text
+}
+
+fn wrapped_text(text: Text<'static>, width: usize) -> Text<'static> {
+    let lines: Vec<Line> = text
+        .lines
+        .into_iter()
+        .flat_map(|line| wrap_line(line, width))
+        .collect();
+    Text::from(lines)
 }
 
 impl PagerWidget {
@@ -42,17 +133,31 @@ impl PagerWidget {
         app_state: &mut AppState,
         scroll: Option<bool>,
         scroll_step: usize,
+        // width the lines are wrapped at
+        wrap: Option<(usize, &mut RowCounts)>,
+        // diff words and log decorations to highlight
+        styling: (bool, bool),
     ) -> Self {
-        let scrolloff = app_state.config.scrolloff;
+        if let Some((width, row_counts)) = wrap {
+            return Self::new_wrapped(
+                items,
+                height,
+                (width, row_counts),
+                app_state,
+                (scroll, scroll_step),
+                styling,
+            );
+        }
+        let scrolloff = clamp_scrolloff(app_state.config.scrolloff, height);
 
+        if items.is_empty() {
+            return Self::default();
+        }
         // ensure the real index is properly defined
         let mut index = app_state.list_state.selected().unwrap_or(0);
         if index >= items.len() {
             index = items.len() - 1;
         }
-        if items.is_empty() {
-            return Self::default();
-        }
         let mut offset = app_state.list_state.offset();
 
         match scroll {
@@ -103,20 +208,249 @@ impl PagerWidget {
             state.select(Some(index - first));
         }
 
-        let list_items: Vec<ListItem> = items[first..last]
-            .iter()
-            .map(|s| {
-                let text = s.as_bytes().into_text().unwrap_or_default();
-                ListItem::new(text)
-            })
+        let list_items: Vec<ListItem> = (first..last)
+            .map(|idx| ListItem::new(item_text(items, idx, styling)))
             .collect();
         let inner = List::new(list_items)
             .block(Block::default().borders(Borders::NONE))
             .highlight_style(highlight_style());
-        Self { inner, state }
+        Self {
+            inner,
+            state,
+            rows: (first..last).collect(),
+        }
+    }
+
+    fn new_wrapped(
+        items: &[String],
+        height: usize,
+        (width, row_counts): (usize, &mut RowCounts),
+        app_state: &mut AppState,
+        (scroll, scroll_step): (Option<bool>, usize),
+        styling: (bool, bool),
+    ) -> Self {
+        if items.is_empty() {
+            return Self::default();
+        }
+        let len = items.len();
+        let scrolloff = clamp_scrolloff(app_state.config.scrolloff, height);
+        // word diff and decorations only change styles, not the number of rows
+        let row_counts = RefCell::new(row_counts);
+        let rows_of = |idx: usize| {
+            *row_counts
+                .borrow_mut()
+                .entry((idx, width))
+                .or_insert_with(|| {
+                    wrapped_text(item_text(items, idx, (false, false)), width)
+                        .lines
+                        .len()
+                })
+        };
+        // number of rows taken by the lines from `first` to `last` included
+        let rows_between = |first: usize, last: usize| -> usize {
+            (first..=min(last, len - 1)).map(rows_of).sum()
+        };
+        // last line entirely displayed when starting at `offset`
+        let last_visible = |offset: usize| -> usize {
+            let mut rows = 0;
+            let mut idx = offset;
+            while idx < len {
+                rows += rows_of(idx);
+                if rows > height {
+                    break;
+                }
+                idx += 1;
+            }
+            idx.saturating_sub(1).max(offset)
+        };
+
+        let mut index = min(app_state.list_state.selected().unwrap_or(0), len - 1);
+        let mut offset = min(app_state.list_state.offset(), len - 1);
+
+        match scroll {
+            None => {
+                if index < offset + scrolloff {
+                    offset = index.saturating_sub(scrolloff);
+                }
+                // each line takes at least one row
+                let below = min(index + scrolloff, len - 1);
+                offset = offset.max((below + 1).saturating_sub(height)).min(index);
+                let mut rows = rows_between(offset, below);
+                while offset < index && rows > height {
+                    rows -= rows_of(offset);
+                    offset += 1;
+                }
+            }
+            Some(down) => {
+                offset = match down {
+                    true => min(offset + scroll_step, len - 1),
+                    false => offset.saturating_sub(scroll_step),
+                };
+                if index < offset + scrolloff {
+                    index = min(offset + scrolloff, len - 1);
+                }
+                let last = last_visible(offset);
+                if last + 1 < len && index + scrolloff > last {
+                    index = last.saturating_sub(scrolloff).max(offset);
+                }
+            }
+        }
+        *app_state.list_state.offset_mut() = offset;
+        app_state.list_state.select(Some(index));
+
+        let mut rows = Vec::new();
+        let mut list_items = Vec::new();
+        let mut idx = offset;
+        while idx < len && rows.len() < height {
+            let text = wrapped_text(item_text(items, idx, styling), width);
+            rows.extend(std::iter::repeat(idx).take(text.lines.len()));
+            list_items.push(ListItem::new(text));
+            idx += 1;
+        }
+
+        let mut state = ListState::default();
+        state.select(Some(index - offset));
+
+        let inner = List::new(list_items)
+            .block(Block::default().borders(Borders::NONE))
+            .highlight_style(highlight_style());
+        Self { inner, state, rows }
+    }
+
+    pub fn line_at_row(&self, row: usize) -> Option<usize> {
+        self.rows.get(row).copied()
+    }
+
+    // right-aligned numbers of the displayed lines, blank on the continuation rows of wrapped lines
+    pub fn render_line_numbers(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        selected: usize,
+        relative: bool,
+    ) {
+        let width = area.width.saturating_sub(1) as usize;
+        let lines: Vec<Line> = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(row, &idx)| {
+                if row > 0 && self.rows[row - 1] == idx {
+                    return Line::raw("");
+                }
+                let number = match relative && idx != selected {
+                    true => idx.abs_diff(selected),
+                    false => idx + 1,
+                };
+                Line::styled(format!("{:>width$} ", number), line_number_style())
+            })
+            .collect();
+        Paragraph::new(lines).render(area, buf);
     }
 
     pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
         StatefulWidget::render(&self.inner, area, buf, &mut self.state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{config::Config, history::History};
+
+    #[test]
+    fn scrolloff_leaves_a_selectable_row() {
+        for height in 0..=3 {
+            for scrolloff in [0, 1, 2, 1000, usize::MAX] {
+                let clamped = clamp_scrolloff(scrolloff, height);
+                assert!(clamped <= scrolloff);
+                assert!(2 * clamped < height.max(1), "{} {}", height, scrolloff);
+            }
+        }
+    }
+
+    #[test]
+    fn index_stays_in_range_on_small_views() {
+        for height in 1..=3 {
+            for len in [1, 2, 3, 10] {
+                for scrolloff in [0, 1, 1000, usize::MAX] {
+                    for offset in 0..len {
+                        for index in [0, offset, len - 1, len + 5] {
+                            let adapted =
+                                adapt_index_in_frame(offset, scrolloff, index, height, len);
+                            assert!(adapted < len);
+                            // in the view, unless it is beyond the last line
+                            assert!(adapted + 1 == len || adapted < offset + height);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn index_follows_the_view() {
+        // `j` scrolling past the selected line drags it with the view
+        assert_eq!(adapt_index_in_frame(10, 2, 0, 10, 100), 12);
+        assert_eq!(adapt_index_in_frame(10, 2, 50, 10, 100), 17);
+        assert_eq!(adapt_index_in_frame(10, 2, 15, 10, 100), 15);
+        // the scrolloff of a 3 rows view is clamped to 1, the middle row
+        assert_eq!(adapt_index_in_frame(10, 1000, 0, 3, 100), 11);
+        assert_eq!(adapt_index_in_frame(10, 1000, 50, 3, 100), 11);
+    }
+
+    #[test]
+    fn row_counts_are_kept_between_frames() {
+        let items: Vec<String> = (0..1000).map(|idx| format!("line {:>5}", idx)).collect();
+        let mut state = AppState::with_config(Config::default(), History::default());
+        let mut row_counts = RowCounts::new();
+        let offset_of = |state: &mut AppState, row_counts: &mut RowCounts| {
+            state.list_state = ListState::default().with_selected(Some(500));
+            let wrap = Some((4, row_counts));
+            PagerWidget::new(&items, 10, state, None, 2, wrap, (false, false));
+            state.list_state.offset()
+        };
+        let offset = offset_of(&mut state, &mut row_counts);
+        assert!(row_counts
+            .iter()
+            .all(|(&(_, width), &rows)| width == 4 && rows == 3));
+        assert!(row_counts.contains_key(&(offset, 4)) && row_counts.len() < 20);
+
+        // the counts of the previous frame are used instead of measuring the lines again
+        row_counts.values_mut().for_each(|rows| *rows = 1);
+        assert!(offset_of(&mut state, &mut row_counts) < offset);
+    }
+
+    #[test]
+    fn pager_on_small_views_with_a_large_scrolloff() {
+        let items: Vec<String> = (0..20).map(|idx| format!("line {}", idx)).collect();
+        let config = Config {
+            scrolloff: 1000,
+            ..Config::default()
+        };
+        let mut state = AppState::with_config(config, History::default());
+        for height in 1..=3 {
+            for wrap_width in [None, Some(3)] {
+                for scroll in [None, Some(true), Some(false)] {
+                    for index in [0, 7, 19, 50] {
+                        state.list_state.select(Some(index));
+                        let mut row_counts = RowCounts::new();
+                        PagerWidget::new(
+                            &items,
+                            height,
+                            &mut state,
+                            scroll,
+                            2,
+                            wrap_width.map(|width| (width, &mut row_counts)),
+                            (false, false),
+                        );
+                        let selected = state.list_state.selected().unwrap();
+                        let offset = state.list_state.offset();
+                        assert!(selected < items.len());
+                        assert!(offset <= selected && selected < offset + height);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, StatefulWidget},
+    Frame,
+};
+
+use crate::model::{
+    action::Action,
+    config::{Button, Config, MappingScope},
+};
+
+// rows of the palette, the filtered entries being scrolled
+const MAX_HEIGHT: usize = 12;
+
+struct Entry {
+    label: String,
+    keys: String,
+    action: Action,
+}
+
+pub struct Palette {
+    entries: Vec<Entry>,
+    // indices of the entries matching the query, best matches first
+    filtered: Vec<usize>,
+    list_state: ListState,
+}
+
+// characters of `query` found in order in `label`, the lower the more compact the match
+fn fuzzy_score(label: &str, query: &str) -> Option<usize> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut first = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = label[position..].iter().position(|&l| l == c)?;
+        if first.is_some() {
+            score += found;
+        }
+        first = first.or(Some(position + found));
+        position += found + 1;
+    }
+    Some(score * 2 + first.unwrap_or(0))
+}
+
+impl Palette {
+    // actions bound in the current scopes and actions of the buttons
+    pub fn new(config: &Config, scopes: &[MappingScope], buttons: &[Button]) -> Self {
+        let mut entries: Vec<Entry> = Vec::new();
+        for scope in scopes {
+            let mut bindings = config.get_bindings(scope.clone());
+            bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (key, action) in bindings {
+                if key == "<any>" || action == Action::None {
+                    continue;
+                }
+                let label = action.to_string();
+                match entries.iter_mut().find(|entry| entry.label == label) {
+                    Some(entry) => {
+                        entry.keys.push(' ');
+                        entry.keys.push_str(&key);
+                    }
+                    None => entries.push(Entry {
+                        label,
+                        keys: key,
+                        action,
+                    }),
+                }
+            }
+        }
+        for (text, action) in buttons {
+            let label = action.to_string();
+            if !entries.iter().any(|entry| entry.label == label) {
+                entries.push(Entry {
+                    label,
+                    keys: format!("[{}]", text.trim()),
+                    action: action.clone(),
+                });
+            }
+        }
+        let mut palette = Self {
+            entries,
+            filtered: Vec::new(),
+            list_state: ListState::default(),
+        };
+        palette.filter("");
+        palette
+    }
+
+    pub fn filter(&mut self, query: &str) {
+        let mut scored: Vec<(usize, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| fuzzy_score(&entry.label, query).map(|score| (score, idx)))
+            .collect();
+        scored.sort();
+        self.filtered = scored.into_iter().map(|(_, idx)| idx).collect();
+        self.list_state
+            .select((!self.filtered.is_empty()).then_some(0));
+    }
+
+    pub fn select(&mut self, delta: isize) {
+        if self.filtered.is_empty() {
+            return;
+        }
+        let idx = self.list_state.selected().unwrap_or(0);
+        let idx = idx
+            .saturating_add_signed(delta)
+            .min(self.filtered.len() - 1);
+        self.list_state.select(Some(idx));
+    }
+
+    pub fn selected_action(&self) -> Option<Action> {
+        let idx = self.filtered.get(self.list_state.selected()?)?;
+        Some(self.entries[*idx].action.clone())
+    }
+
+    // drawn at the bottom of `rect`, right above the edit bar
+    pub fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        let label_width = self
+            .entries
+            .iter()
+            .map(|entry| entry.label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let items: Vec<Line> = self
+            .filtered
+            .iter()
+            .map(|&idx| {
+                let entry = &self.entries[idx];
+                Line::from(vec![
+                    Span::raw(format!("{:width$}  ", entry.label, width = label_width)),
+                    Span::styled(entry.keys.clone(), Style::from(Color::Blue)),
+                ])
+            })
+            .collect();
+        // borders take two rows
+        let height = (items.len().clamp(1, MAX_HEIGHT) + 2).min(rect.height as usize) as u16;
+        let area = Rect {
+            y: rect.y + rect.height - height,
+            height,
+            ..rect
+        };
+        let title = format!(" {}/{} ", self.filtered.len(), self.entries.len());
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::TOP | Borders::BOTTOM),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_widget(Clear, area);
+        StatefulWidget::render(list, area, frame.buffer_mut(), &mut self.list_state);
+    }
+}
@@ -0,0 +1,210 @@
+// two-column rendering of unified diffs, lines outside of hunks span the whole width
+
+use unicode_width::UnicodeWidthChar as _;
+
+#[derive(Clone, Copy)]
+enum Row {
+    Full(usize),
+    // old and new sides of the row, a context line being on both sides
+    Split(Option<usize>, Option<usize>),
+}
+
+#[derive(Default)]
+pub struct SideBySide {
+    rows: Vec<Row>,
+    rendered: Vec<String>,
+    width: usize,
+    // number of lines already dispatched into rows
+    parsed: usize,
+    in_hunk: bool,
+    removed: Vec<usize>,
+    added: Vec<usize>,
+}
+
+fn strip(line: &str) -> String {
+    String::from_utf8(strip_ansi_escapes::strip(line.as_bytes())).unwrap_or_default()
+}
+
+// truncate or pad `line` to `width` terminal cells, keeping its escape sequences, a wide char
+// (CJK, emoji) not fitting at the end being replaced by padding
+fn fit(line: &str, width: usize) -> String {
+    let mut fitted = String::new();
+    let mut visible = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            fitted.push(c);
+            if chars.peek() == Some(&'[') {
+                // CSI sequences end with a character in the `@`..=`~` range
+                for c in chars.by_ref() {
+                    fitted.push(c);
+                    if c != '[' && ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        let char_width = c.width().unwrap_or(0);
+        if visible + char_width > width {
+            break;
+        }
+        fitted.push(c);
+        visible += char_width;
+    }
+    fitted.push_str("\x1b[0m");
+    fitted.push_str(&" ".repeat(width - visible));
+    fitted
+}
+
+impl SideBySide {
+    // pair the removed lines with the added ones following them
+    fn flush(&mut self) {
+        let removed = std::mem::take(&mut self.removed);
+        let added = std::mem::take(&mut self.added);
+        for i in 0..removed.len().max(added.len()) {
+            self.rows
+                .push(Row::Split(removed.get(i).copied(), added.get(i).copied()));
+        }
+    }
+
+    fn parse(&mut self, idx: usize, line: &str) {
+        let line = strip(line);
+        if line.starts_with("@@") {
+            self.flush();
+            self.in_hunk = true;
+            self.rows.push(Row::Full(idx));
+            return;
+        }
+        match (self.in_hunk, line.chars().next()) {
+            (true, Some('-')) => {
+                if !self.added.is_empty() {
+                    self.flush();
+                }
+                self.removed.push(idx);
+            }
+            (true, Some('+')) => self.added.push(idx),
+            (true, Some(' ')) => {
+                self.flush();
+                self.rows.push(Row::Split(Some(idx), Some(idx)));
+            }
+            // `\ No newline at end of file`
+            (true, Some('\\')) => {
+                self.flush();
+                self.rows.push(Row::Full(idx));
+            }
+            _ => {
+                self.flush();
+                self.in_hunk = false;
+                self.rows.push(Row::Full(idx));
+            }
+        }
+    }
+
+    fn render(&self, lines: &[String], row: Row) -> String {
+        match row {
+            Row::Full(idx) => lines[idx].clone(),
+            Row::Split(old, new) => {
+                let left_width = self.width.saturating_sub(1) / 2;
+                let right_width = self.width.saturating_sub(left_width + 1);
+                let side = |idx: Option<usize>, width| match idx {
+                    Some(idx) => fit(&lines[idx], width),
+                    None => " ".repeat(width),
+                };
+                format!(
+                    "{}\x1b[90m│\x1b[0m{}",
+                    side(old, left_width),
+                    side(new, right_width)
+                )
+            }
+        }
+    }
+
+    // dispatch the lines loaded since the last update, rendering every row again on resize
+    pub fn update(&mut self, lines: &[String], loaded: bool, width: usize) {
+        for (idx, line) in lines.iter().enumerate().skip(self.parsed) {
+            self.parse(idx, line);
+        }
+        self.parsed = lines.len();
+        if loaded {
+            self.flush();
+        }
+        if width != self.width {
+            self.width = width;
+            self.rendered.clear();
+        }
+        let rendered: Vec<String> = self.rows[self.rendered.len()..]
+            .iter()
+            .map(|&row| self.render(lines, row))
+            .collect();
+        self.rendered.extend(rendered);
+    }
+
+    pub fn rendered(&self) -> &[String] {
+        &self.rendered
+    }
+
+    pub fn search_line(&self, row: usize) -> Option<String> {
+        self.rendered.get(row).map(|line| strip(line))
+    }
+
+    // line displayed on `row`, the new side being preferred
+    pub fn line_idx(&self, row: usize) -> Option<usize> {
+        match self.rows.get(row)? {
+            Row::Full(idx) => Some(*idx),
+            Row::Split(old, new) => new.or(*old),
+        }
+    }
+
+    pub fn row_of_line(&self, idx: usize) -> Option<usize> {
+        self.rows.iter().position(|row| match *row {
+            Row::Full(line) => line == idx,
+            Row::Split(old, new) => old == Some(idx) || new == Some(idx),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_width::UnicodeWidthStr as _;
+
+    #[test]
+    fn fit_by_display_width() {
+        for (line, width, fitted) in [
+            ("main", 6, "main  "),
+            ("main.rs", 4, "main"),
+            ("日本語", 6, "日本語"),
+            ("日本語", 5, "日本 "),
+            ("a日本", 2, "a "),
+            ("\x1b[31mred\x1b[0m", 5, "red  "),
+        ] {
+            assert_eq!(strip(&fit(line, width)), fitted, "{} {}", line, width);
+        }
+        // colors are kept
+        assert!(fit("\x1b[31mred\x1b[0m", 2).starts_with("\x1b[31mre"));
+    }
+
+    #[test]
+    fn columns_line_up_with_wide_chars() {
+        let lines: Vec<String> = [
+            "@@ -1,2 +1,2 @@",
+            "-let 名前 = 1;",
+            "+let name = 1;",
+            " // 日本語のコメントです",
+            "-\x1b[31m😀 emoji\x1b[0m",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let mut side_by_side = SideBySide::default();
+        side_by_side.update(&lines, true, 21);
+        let rendered = side_by_side.rendered();
+        assert_eq!(rendered.len(), 4);
+        for row in &rendered[1..] {
+            let row = strip(row);
+            let (left, right) = row.split_once('│').unwrap();
+            assert_eq!((left.width(), right.width()), (10, 10), "{}", row);
+        }
+    }
+}
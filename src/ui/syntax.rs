@@ -0,0 +1,79 @@
+use std::{path::Path, sync::OnceLock};
+
+use syntect::{
+    highlighting::{Theme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
+};
+use two_face::{re_exports::syntect, syntax};
+
+use crate::model::errors::Error;
+
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+// loading the sets takes a noticeable time, only do it once
+pub fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(syntax::extra_newlines)
+}
+
+// files known by their name, also matched followed by a suffix as in `Dockerfile.dev`
+const SPECIAL_FILES: &[(&str, &str)] = &[
+    ("Containerfile", "Dockerfile"),
+    ("Dockerfile", "Dockerfile"),
+    ("Makefile", "Makefile"),
+    ("Jenkinsfile", "Groovy"),
+];
+
+// syntax given by `set syntax`, either its name or one of its extensions, case insensitive
+pub fn syntax_by_token(token: &str) -> Option<&'static SyntaxReference> {
+    syntax_set().find_syntax_by_token(token)
+}
+
+// by extension, then by file name (`Makefile`, `.bashrc`, `CMakeLists.txt`), then by first line
+// (shebangs, modelines)
+pub fn detect_syntax(file: &str, first_line: &str) -> &'static SyntaxReference {
+    let syn_set = syntax_set();
+    let path = Path::new(file);
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(file);
+    let special = || {
+        SPECIAL_FILES
+            .iter()
+            .find(|(special, _)| name == *special || name.starts_with(&format!("{}.", special)))
+            .and_then(|(_, syntax)| syn_set.find_syntax_by_name(syntax))
+    };
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| syn_set.find_syntax_by_extension(ext))
+        // `.txt` would hide `CMakeLists.txt`
+        .filter(|syntax| syntax.name != syn_set.find_syntax_plain_text().name)
+        .or_else(|| syn_set.find_syntax_by_extension(name))
+        .or_else(special)
+        .or_else(|| syn_set.find_syntax_by_first_line(first_line))
+        .unwrap_or_else(|| syn_set.find_syntax_plain_text())
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+// `name` is either one of the themes bundled with syntect or a path to a `.tmTheme` file
+pub fn load_theme(name: &str) -> Result<Theme, Error> {
+    if name.ends_with(".tmTheme") || Path::new(name).is_file() {
+        return ThemeSet::get_theme(name)
+            .map_err(|err| Error::Global(format!("could not load theme '{}': {}", name, err)));
+    }
+    theme_set().themes.get(name).cloned().ok_or_else(|| {
+        let mut names: Vec<&String> = theme_set().themes.keys().collect();
+        names.sort();
+        let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+        Error::Global(format!(
+            "unknown theme '{}', available themes: {}",
+            name,
+            names.join(", ")
+        ))
+    })
+}
@@ -1,13 +1,18 @@
-use crate::model::{action::Action, app_state::NotifChannel, config::Button};
-use chrono::{NaiveDate, Utc};
+use crate::model::{
+    action::Action,
+    app_state::NotifChannel,
+    config::{Button, DateFormat, MenuBarPosition, ShowCr},
+};
+use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Position, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Clear, Paragraph, Widget},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
     Frame,
 };
 use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
 
 pub const SPINNER_FRAMES: &[char] = &['⣾', '⣽', '⣻', '⢿', '⡿', '⣟', '⣯', '⣷'];
 
@@ -21,6 +26,14 @@ pub fn search_highlight_style() -> Style {
         .add_modifier(Modifier::REVERSED)
 }
 
+pub fn mouse_selection_style() -> Style {
+    Style::new().add_modifier(Modifier::REVERSED)
+}
+
+pub fn line_number_style() -> Style {
+    Style::from(Color::DarkGray)
+}
+
 pub fn bar_style() -> Style {
     Style::default().bg(Color::Rgb(25, 25, 25))
 }
@@ -46,10 +59,8 @@ pub fn clicked_button_style() -> Style {
         .add_modifier(Modifier::REVERSED | Modifier::BOLD)
 }
 
-pub fn date_to_color(date: &str) -> Color {
-    let today = Utc::now().date_naive();
-    let past_date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap_or(today);
-    let age_factor = (today - past_date).num_days() as f32 / (365.0 * 2.0);
+pub fn date_to_color(date: &DateTime<FixedOffset>) -> Color {
+    let age_factor = (Utc::now() - date.to_utc()).num_days() as f32 / (365.0 * 2.0);
 
     let clamped = age_factor.clamp(0.0, 1.0);
     let r = (255.0 * (1.0 - clamped) + 80.0 * clamped) as u8;
@@ -58,8 +69,59 @@ pub fn date_to_color(date: &str) -> Color {
     Color::Rgb(r, g, b)
 }
 
-pub fn clean_buggy_characters(line: &str) -> String {
-    line.replace("\t", "    ").replace("\r", "^M")
+pub fn format_date(date: &DateTime<FixedOffset>, date_format: &DateFormat) -> String {
+    match date_format {
+        DateFormat::Relative => relative_date(Utc::now() - date.to_utc()),
+        DateFormat::Absolute(format) => date.format(format).to_string(),
+    }
+}
+
+// with the thresholds of `git log --date=relative`
+fn relative_date(age: TimeDelta) -> String {
+    let plural = |n: i64, unit: &str| match n {
+        1 => format!("1 {} ago", unit),
+        n => format!("{} {}s ago", n, unit),
+    };
+    let seconds = age.num_seconds();
+    if seconds < 0 {
+        return "in the future".to_string();
+    }
+    if seconds < 90 {
+        return plural(seconds, "second");
+    }
+    let minutes = (seconds + 30) / 60;
+    if minutes < 90 {
+        return plural(minutes, "minute");
+    }
+    let hours = (minutes + 30) / 60;
+    if hours < 36 {
+        return plural(hours, "hour");
+    }
+    let days = (hours + 12) / 24;
+    if days < 14 {
+        return plural(days, "day");
+    }
+    if days < 70 {
+        return plural((days + 3) / 7, "week");
+    }
+    if days < 365 {
+        return plural((days + 15) / 30, "month");
+    }
+    plural((days * 12 + 182) / 365 / 12, "year")
+}
+
+pub fn cr_style() -> Style {
+    Style::default().bg(Color::Red)
+}
+
+pub fn clean_buggy_characters(line: &str, show_cr: ShowCr, tab_width: usize) -> String {
+    let cr = match show_cr {
+        ShowCr::Show => "^M",
+        ShowCr::Hide => "",
+        // red background, then back to the default one to keep git's colors
+        ShowCr::Highlight => "\x1b[41m^M\x1b[49m",
+    };
+    line.replace("\t", &" ".repeat(tab_width)).replace("\r", cr)
 }
 
 pub fn display_edit_bar(
@@ -96,6 +158,59 @@ pub fn display_edit_bar(
     chunks[1]
 }
 
+// box growing with the edited text, `title` being displayed on its border
+pub fn display_multi_line_edit(
+    edit_string: &str,
+    title: &str,
+    cursor: usize,
+    chunk: &mut Rect,
+    frame: &mut Frame,
+) -> Rect {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut cursor_row = 0;
+    let mut position = 0;
+    for (row, text) in edit_string.split('\n').enumerate() {
+        let chars: Vec<char> = text.chars().collect();
+        let line = match (position..=position + chars.len()).contains(&cursor) {
+            true => {
+                cursor_row = row;
+                let column = cursor - position;
+                let middle = chars.get(column).map_or(" ".to_string(), |c| c.to_string());
+                Line::from(vec![
+                    Span::raw(chars[..column].iter().collect::<String>()),
+                    Span::styled(middle, Style::default().add_modifier(Modifier::REVERSED)),
+                    Span::raw(
+                        chars[(column + 1).min(chars.len())..]
+                            .iter()
+                            .collect::<String>(),
+                    ),
+                ])
+            }
+            false => Line::raw(text.to_string()),
+        };
+        lines.push(line);
+        position += chars.len() + 1;
+    }
+
+    // borders take two rows, the box taking at most half of the view
+    let max_height = (chunk.height / 2).max(3);
+    let height = (lines.len() as u16 + 2).min(max_height);
+    let scroll = (cursor_row as u16 + 3).saturating_sub(height);
+    let title = format!(" {} (ctrl-s to submit, esc to cancel) ", title);
+    let paragraph = Paragraph::new(lines)
+        .scroll((scroll, 0))
+        .block(Block::default().title(title).borders(Borders::ALL));
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(height)])
+        .split(*chunk);
+    frame.render_widget(Clear, chunks[1]);
+    Widget::render(&paragraph, chunks[1], frame.buffer_mut());
+
+    *chunk = chunks[0];
+    chunks[1]
+}
+
 pub fn display_notifications(
     notifications: &HashMap<NotifChannel, String>,
     loading_char: char,
@@ -143,36 +258,60 @@ pub fn display_notifications(
     *chunk = chunks[0];
 }
 
+// rects of the buttons, laid out from left to right and wrapping to a new row when a button
+// does not fit in the remaining width
+fn menu_bar_layout(buttons: &[Button], width: u16) -> Vec<(u16, u16, u16)> {
+    let mut layout = Vec::new();
+    let (mut row, mut x) = (0, 1);
+    for button in buttons {
+        let len = (button.0.width() as u16).min(width.saturating_sub(1));
+        if x > 1 && x + len > width {
+            (row, x) = (row + 1, 1);
+        }
+        layout.push((row, x, len));
+        x += len + 1;
+    }
+    layout
+}
+
 pub fn display_menu_bar(
-    buttons: &Vec<Button>,
+    buttons: &[Button],
     mouse_position: Position,
     mouse_down: bool,
+    position: MenuBarPosition,
     chunk: &mut Rect,
     frame: &mut Frame,
 ) -> Vec<(Rect, Action)> {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(1), Constraint::Min(0)])
-        .split(*chunk);
-
-    let mut constraints = vec![Constraint::Length(1)];
-    for button in buttons {
-        constraints.push(Constraint::Length(button.0.chars().count() as u16));
-        constraints.push(Constraint::Length(1));
-    }
-
-    let horizontal_chunks = Layout::default()
-        .constraints(constraints)
-        .direction(Direction::Horizontal)
-        .split(chunks[0]);
+    let layout = menu_bar_layout(buttons, chunk.width);
+    let height = layout.last().map_or(1, |(row, _, _)| row + 1);
+    // (bar, remaining area)
+    let (bar_rect, remaining) = match position {
+        MenuBarPosition::Top => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(height), Constraint::Min(0)])
+                .split(*chunk);
+            (chunks[0], chunks[1])
+        }
+        MenuBarPosition::Bottom => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(height)])
+                .split(*chunk);
+            (chunks[1], chunks[0])
+        }
+    };
 
     let paragraph = Paragraph::default().style(bar_style());
-    Widget::render(&paragraph, chunks[0], frame.buffer_mut());
+    Widget::render(&paragraph, bar_rect, frame.buffer_mut());
 
     let mut region_to_action = Vec::new();
 
-    for (idx, button) in buttons.iter().enumerate() {
-        let chunk = horizontal_chunks[2 * idx + 1];
+    for (button, (row, x, len)) in buttons.iter().zip(layout) {
+        let chunk = Rect::new(bar_rect.x + x, bar_rect.y + row, len, 1).intersection(bar_rect);
+        if chunk.is_empty() {
+            continue;
+        }
         let style = if chunk.contains(mouse_position) {
             if mouse_down {
                 clicked_button_style()
@@ -186,6 +325,48 @@ pub fn display_menu_bar(
         Widget::render(&paragraph, chunk, frame.buffer_mut());
         region_to_action.push((chunk, button.1.clone()))
     }
-    *chunk = chunks[1];
+    *chunk = remaining;
     region_to_action
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_date_boundaries() {
+        for (age, expected) in [
+            (TimeDelta::seconds(-1), "in the future"),
+            (TimeDelta::seconds(1), "1 second ago"),
+            (TimeDelta::seconds(89), "89 seconds ago"),
+            (TimeDelta::seconds(90), "2 minutes ago"),
+            (TimeDelta::minutes(89), "89 minutes ago"),
+            (TimeDelta::minutes(90), "2 hours ago"),
+            // days
+            (TimeDelta::hours(24), "24 hours ago"),
+            (TimeDelta::hours(35), "35 hours ago"),
+            (TimeDelta::hours(36), "2 days ago"),
+            // weeks
+            (TimeDelta::days(13), "13 days ago"),
+            (TimeDelta::days(14), "2 weeks ago"),
+            (TimeDelta::days(69), "10 weeks ago"),
+            // months
+            (TimeDelta::days(70), "2 months ago"),
+            (TimeDelta::days(364), "12 months ago"),
+            (TimeDelta::days(365), "1 year ago"),
+            (TimeDelta::days(3 * 365), "3 years ago"),
+        ] {
+            assert_eq!(relative_date(age), expected, "{:?}", age);
+        }
+    }
+
+    #[test]
+    fn absolute_date_in_the_timezone_of_the_author() {
+        let offset = FixedOffset::east_opt(2 * 3600).unwrap();
+        let date = DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .with_timezone(&offset);
+        let date_format = DateFormat::Absolute("%Y-%m-%d %H:%M".to_string());
+        assert_eq!(format_date(&date, &date_format), "2023-11-15 00:13");
+    }
+}
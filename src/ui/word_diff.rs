@@ -0,0 +1,296 @@
+use std::cmp::min;
+
+use ratatui::{
+    style::Color,
+    text::{Line, Span},
+};
+
+// blocks of removed/added lines longer than this are not paired
+const MAX_BLOCK_LEN: usize = 100;
+// lines with more tokens than this are not compared
+const MAX_TOKENS: usize = 200;
+
+#[derive(Clone, Copy, PartialEq)]
+enum DiffLine {
+    Removed,
+    Added,
+}
+
+fn strip(line: &str) -> String {
+    String::from_utf8(strip_ansi_escapes::strip(line.as_bytes())).unwrap_or_default()
+}
+
+// `--- a/file` and `+++ b/file` lines following the `diff --git` and `index` ones, removed and
+// added lines starting with `--` or `++` being part of the hunks
+fn is_file_header(items: &[String], idx: usize, line: &str) -> bool {
+    let Some(previous) = idx.checked_sub(1).map(|previous| strip(&items[previous])) else {
+        return false;
+    };
+    if line.starts_with("---") {
+        previous.starts_with("index ") || previous.starts_with("diff ")
+    } else if line.starts_with("+++") {
+        previous.starts_with("---") && is_file_header(items, idx - 1, &previous)
+    } else {
+        false
+    }
+}
+
+fn diff_line(items: &[String], idx: usize) -> Option<DiffLine> {
+    let line = strip(items.get(idx)?);
+    if is_file_header(items, idx, &line) {
+        return None;
+    }
+    match line.chars().next() {
+        Some('-') => Some(DiffLine::Removed),
+        Some('+') => Some(DiffLine::Added),
+        _ => None,
+    }
+}
+
+// first index of the block of `kind` lines ending at `idx`, bounded by `MAX_BLOCK_LEN`
+fn block_start(items: &[String], idx: usize, kind: DiffLine) -> Option<usize> {
+    let mut start = idx;
+    while start > 0 && diff_line(items, start - 1) == Some(kind) {
+        start -= 1;
+        if idx - start >= MAX_BLOCK_LEN {
+            return None;
+        }
+    }
+    Some(start)
+}
+
+// index past the block of `kind` lines starting at `idx`, bounded by `MAX_BLOCK_LEN`
+fn block_end(items: &[String], idx: usize, kind: DiffLine) -> Option<usize> {
+    let mut end = idx;
+    while diff_line(items, end) == Some(kind) {
+        end += 1;
+        if end - idx > MAX_BLOCK_LEN {
+            return None;
+        }
+    }
+    Some(end)
+}
+
+// the removed and added lines are paired when their blocks have the same length
+fn paired_line(items: &[String], idx: usize) -> Option<usize> {
+    match diff_line(items, idx)? {
+        DiffLine::Removed => {
+            let start = block_start(items, idx, DiffLine::Removed)?;
+            let end = block_end(items, idx, DiffLine::Removed)?;
+            let added_end = block_end(items, end, DiffLine::Added)?;
+            (added_end - end == end - start).then_some(end + idx - start)
+        }
+        DiffLine::Added => {
+            let start = block_start(items, idx, DiffLine::Added)?;
+            let end = block_end(items, idx, DiffLine::Added)?;
+            if start == 0 {
+                return None;
+            }
+            let removed_start = block_start(items, start - 1, DiffLine::Removed)?;
+            let is_removed = diff_line(items, start - 1) == Some(DiffLine::Removed);
+            (is_removed && start - removed_start == end - start)
+                .then_some(removed_start + idx - start)
+        }
+    }
+}
+
+// words, runs of whitespaces and single punctuation characters, as char ranges
+fn tokens(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let c = chars[start];
+        let mut end = start + 1;
+        if c.is_alphanumeric() || c == '_' {
+            while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                end += 1;
+            }
+        } else if c.is_whitespace() {
+            while end < chars.len() && chars[end].is_whitespace() {
+                end += 1;
+            }
+        }
+        tokens.push((start, end));
+        start = end;
+    }
+    tokens
+}
+
+fn token(chars: &[char], (start, end): (usize, usize)) -> &[char] {
+    &chars[start..end]
+}
+
+// char ranges of `line` that are not part of the longest common token subsequence
+fn changed_ranges(line: &str, other: &str) -> Vec<(usize, usize)> {
+    // skip the `+` or `-` prefix
+    let chars: Vec<char> = line.chars().skip(1).collect();
+    let other_chars: Vec<char> = other.chars().skip(1).collect();
+    let a = tokens(&chars);
+    let b = tokens(&other_chars);
+    if a.len() > MAX_TOKENS || b.len() > MAX_TOKENS {
+        return Vec::new();
+    }
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = match token(&chars, a[i]) == token(&other_chars, b[j]) {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+    // nothing in common, highlighting every word would not help
+    if lcs[0][0] == 0 {
+        return Vec::new();
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() {
+        if j < b.len() && token(&chars, a[i]) == token(&other_chars, b[j]) {
+            i += 1;
+            j += 1;
+        } else if j < b.len() && lcs[i][j + 1] >= lcs[i + 1][j] {
+            j += 1;
+        } else {
+            let (start, end) = (a[i].0 + 1, a[i].1 + 1);
+            match ranges.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _ => ranges.push((start, end)),
+            }
+            i += 1;
+        }
+    }
+    ranges
+}
+
+fn emphasize(line: Line<'static>, ranges: &[(usize, usize)], bg: Color) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut position = 0;
+    for span in line.spans {
+        let chars: Vec<char> = span.content.chars().collect();
+        let span_end = position + chars.len();
+        let mut cursor = position;
+        // cut the span at each range boundary
+        let mut cuts: Vec<usize> = ranges
+            .iter()
+            .flat_map(|&(start, end)| [start, end])
+            .filter(|&cut| cut > position && cut < span_end)
+            .collect();
+        cuts.push(span_end);
+        for cut in cuts {
+            if cut <= cursor {
+                continue;
+            }
+            let text: String = chars[cursor - position..min(cut, span_end) - position]
+                .iter()
+                .collect();
+            let changed = ranges
+                .iter()
+                .any(|&(start, end)| start <= cursor && cursor < end);
+            let style = match changed {
+                true => span.style.bg(bg),
+                false => span.style,
+            };
+            spans.push(Span::styled(text, style));
+            cursor = cut;
+        }
+        position = span_end;
+    }
+    Line::from(spans).style(line.style)
+}
+
+// highlight the words changed between a removed line and the matching added line
+pub fn word_diff_line(items: &[String], idx: usize, line: Line<'static>) -> Line<'static> {
+    let Some(other_idx) = paired_line(items, idx) else {
+        return line;
+    };
+    let ranges = changed_ranges(&strip(&items[idx]), &strip(&items[other_idx]));
+    if ranges.is_empty() {
+        return line;
+    }
+    let bg = match diff_line(items, idx) {
+        Some(DiffLine::Removed) => Color::Rgb(110, 30, 30),
+        _ => Color::Rgb(30, 90, 30),
+    };
+    emphasize(line, &ranges, bg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // file and hunk headers followed by `lines`, the first of them at index 5
+    fn diff(lines: &[&str]) -> Vec<String> {
+        let header = [
+            "diff --git a/main.rs b/main.rs",
+            "index 1234567..89abcde 100644",
+            "--- a/main.rs",
+            "+++ b/main.rs",
+            "@@ -1,3 +1,3 @@",
+        ];
+        header
+            .iter()
+            .chain(lines)
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    // content of the spans given a background
+    fn emphasized(items: &[String], idx: usize) -> Vec<String> {
+        let line = word_diff_line(items, idx, Line::raw(items[idx].clone()));
+        line.spans
+            .iter()
+            .filter(|span| span.style.bg.is_some())
+            .map(|span| span.content.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn removed_and_added_lines_are_paired() {
+        let items = diff(&[
+            " fn main() {",
+            "-    let x = 1;",
+            "-    let y = 2;",
+            "+    let x = 3;",
+            "+    let y = 2;",
+            " }",
+        ]);
+        assert_eq!(paired_line(&items, 6), Some(8));
+        assert_eq!(paired_line(&items, 7), Some(9));
+        assert_eq!(paired_line(&items, 8), Some(6));
+        assert_eq!(paired_line(&items, 5), None);
+        assert_eq!(emphasized(&items, 6), ["1"]);
+        assert_eq!(emphasized(&items, 8), ["3"]);
+        // identical lines have nothing to highlight
+        assert!(emphasized(&items, 7).is_empty());
+    }
+
+    #[test]
+    fn blocks_of_different_lengths_are_not_paired() {
+        let items = diff(&[
+            "-    let x = 1;",
+            "-    let y = 2;",
+            "+    let x = 3;",
+            " }",
+            "-a",
+            "+b",
+            "+c",
+        ]);
+        for idx in 5..items.len() {
+            assert_eq!(paired_line(&items, idx), None, "{}", items[idx]);
+            assert!(emphasized(&items, idx).is_empty());
+        }
+    }
+
+    #[test]
+    fn file_headers_only_follow_the_diff_and_index_lines() {
+        let items = diff(&["--- a comment", "+++ a note"]);
+        assert!(diff_line(&items, 2).is_none() && diff_line(&items, 3).is_none());
+        // removed and added lines starting with `--` and `++`
+        assert!(diff_line(&items, 5) == Some(DiffLine::Removed));
+        assert!(diff_line(&items, 6) == Some(DiffLine::Added));
+        assert_eq!(emphasized(&items, 5), ["--", "comment"]);
+        assert_eq!(emphasized(&items, 6), ["++", "note"]);
+    }
+}
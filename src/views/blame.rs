@@ -1,35 +1,74 @@
 use crate::app::{FileRevLine, GitApp};
 use crate::model::{
     action::Action,
-    app_state::{AppState, NotifChannel},
-    config::{Config, MappingScope},
+    app_state::{AppState, InputState, NotifChannel},
+    config::{parse_gitrs_config, DateFormat, MappingScope, ShowCr},
     errors::Error,
-    git::{get_previous_filename, git_blame_output, CommitInBlame},
+    git::{
+        get_previous_filename, git_blame_output, git_child_error, git_commit_subject,
+        git_line_in_parent, is_valid_git_rev, CommitInBlame, GitChild,
+    },
 };
-use crate::ui::utils::{date_to_color, highlight_style};
+use crate::ui::{
+    syntax::{detect_syntax, load_theme, syntax_by_token, syntax_set, DEFAULT_THEME},
+    utils::{cr_style, date_to_color, format_date, highlight_style},
+};
+use crate::views::pager::{PagerApp, PagerCommand};
 
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use syntect::{easy::HighlightLines, highlighting::Theme, parsing::SyntaxReference};
 use two_face::re_exports::syntect;
-use two_face::syntax;
-use syntect::{
-    easy::HighlightLines,
-    highlighting::{Style as SyntectStyle, ThemeSet},
-};
 
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, StatefulWidget},
+    widgets::{Block, Borders, List, ListState, Paragraph, StatefulWidget},
     Frame, Terminal,
 };
-use syntect::util::LinesWithEndings;
 
-use std::path::Path;
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Lines},
+    path::Path,
+    process::ChildStdout,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+// line of `gitrs blame --print-json`, the commit fields being null for uncommitted lines
+#[derive(Serialize)]
+struct BlameJsonLine {
+    line: usize,
+    hash: Option<String>,
+    boundary: bool,
+    author: Option<String>,
+    // RFC 3339, in the time zone of the author
+    date: Option<String>,
+    orig_line: Option<usize>,
+    code: String,
+}
+
+struct BlameLine {
+    commit: Option<CommitInBlame>,
+    code: String,
+    highlighted: Line<'static>,
+}
+
+#[derive(Default)]
+struct LoadedBlame {
+    lines: Vec<BlameLine>,
+    max_author_len: usize,
+    // relative dates differ in length
+    max_date_len: usize,
+}
 
 struct BlameAppViewModel {
-    blame_list: List<'static>,
-    code_list: List<'static>,
     max_blame_len: usize,
     rect: Rect,
 }
@@ -37,15 +76,295 @@ struct BlameAppViewModel {
 pub struct BlameApp {
     state: AppState,
     file: String,
-    blames: Vec<Option<CommitInBlame>>,
-    code: Vec<String>,
+    blame: Arc<Mutex<LoadedBlame>>,
+    loaded: Arc<AtomicBool>,
+    // failure of git or of the parsing, reported once the lines are read
+    error: Arc<Mutex<Option<String>>>,
+    // theme, syntax and `show_cr` of the highlighted lines, reused while the code is unchanged
+    highlighted_with: Option<(String, Option<String>, ShowCr)>,
     revisions: Vec<Option<String>>,
     files: Vec<String>,
+    // selected line of each revision left for an older one, restored when coming back
+    selections: Vec<usize>,
+    // subjects of the commits already displayed, by hash
+    subjects: HashMap<String, String>,
+    // kept while navigating the revision stack
+    ignore_whitespace: bool,
     view_model: BlameAppViewModel,
 }
 
+// date of the commit in the timezone of its author, as displayed by `git blame`
+fn blame_date(time: i64, tz: &str) -> DateTime<FixedOffset> {
+    // `+hhmm` or `-hhmm`
+    let offset = tz
+        .parse::<i32>()
+        .map(|hhmm| hhmm / 100 * 3600 + hhmm % 100 * 60)
+        .unwrap_or(0);
+    let offset = FixedOffset::east_opt(offset).unwrap_or(FixedOffset::east_opt(0).unwrap());
+    DateTime::from_timestamp(time, 0)
+        .unwrap_or_default()
+        .with_timezone(&offset)
+}
+
+// read the next line of `git blame --line-porcelain`, whose headers are repeated for each line
+fn next_blame_entry(
+    lines: &mut Lines<impl BufRead>,
+    tab_width: usize,
+    date_format: &DateFormat,
+) -> Result<Option<(Option<CommitInBlame>, String)>, Error> {
+    let Some(header) = lines.next() else {
+        return Ok(None);
+    };
+    let header = header?;
+    // `<hash> <line in the commit> <line in the file> [<lines of the group>]`
+    let mut fields = header.split_whitespace();
+    let hash = fields.next().ok_or_else(|| Error::GitParsing)?.to_string();
+    let orig_line = fields
+        .next()
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| Error::GitParsing)?;
+    let mut author = String::new();
+    let mut time = 0;
+    let mut tz = String::new();
+    let mut boundary = false;
+    loop {
+        let line = lines.next().ok_or_else(|| Error::GitParsing)??;
+        // the code, prefixed by a tab, ends the entry
+        if let Some(code) = line.strip_prefix('\t') {
+            let code = code.replace('\t', &" ".repeat(tab_width));
+            // for lines not committed yet
+            if hash.starts_with("0000") {
+                return Ok(Some((None, code)));
+            }
+            let time = blame_date(time, &tz);
+            let commit = CommitInBlame {
+                // boundary commits are prefixed by `^` as in the default output
+                hash: match boundary {
+                    true => format!("^{}", hash),
+                    false => hash,
+                },
+                author,
+                date: format_date(&time, date_format),
+                time,
+                orig_line,
+            };
+            return Ok(Some((Some(commit), code)));
+        }
+        match line.split_once(' ') {
+            Some(("author", value)) => author = value.to_string(),
+            Some(("author-time", value)) => time = value.parse().map_err(|_| Error::GitParsing)?,
+            Some(("author-tz", value)) => tz = value.to_string(),
+            None if line == "boundary" => boundary = true,
+            _ => (),
+        }
+    }
+}
+
+// highlight line by line so that the code column always has as many lines as the blame column,
+// both being rendered with the same list state
+fn highlight_line(h: &mut HighlightLines, code_line: &str, show_cr: ShowCr) -> Line<'static> {
+    // syntect is given the line without its carriage returns, marked afterwards where they were
+    let line = format!("{}\n", code_line.replace('\r', ""));
+    let spans: Vec<Span> = match h.highlight_line(&line, syntax_set()) {
+        Ok(ranges) => ranges
+            .into_iter()
+            .map(|(style, text)| {
+                Span::styled(
+                    text.to_string(),
+                    Style::default().fg(Color::Rgb(
+                        style.foreground.r,
+                        style.foreground.g,
+                        style.foreground.b,
+                    )),
+                )
+            })
+            .collect(),
+        Err(_) => vec![Span::raw(line)],
+    };
+    let marker = match show_cr {
+        ShowCr::Show => Span::raw("^M"),
+        ShowCr::Hide => return Line::from(spans),
+        ShowCr::Highlight => Span::styled("^M", cr_style()),
+    };
+    // position of each carriage return in the line without them
+    let crs: Vec<usize> = code_line
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| *c == '\r')
+        .enumerate()
+        .map(|(count, (pos, _))| pos - count)
+        .collect();
+    if crs.is_empty() {
+        return Line::from(spans);
+    }
+    let mut marked: Vec<Span> = Vec::new();
+    let mut crs = crs.into_iter().peekable();
+    let mut pos = 0;
+    for span in spans {
+        let mut content = String::new();
+        for c in span.content.chars() {
+            while crs.next_if(|&cr| cr == pos).is_some() {
+                marked.push(Span::styled(std::mem::take(&mut content), span.style));
+                marked.push(marker.clone());
+            }
+            content.push(c);
+            pos += 1;
+        }
+        marked.push(Span::styled(content, span.style));
+    }
+    // the highlighted line ends with a newline, after all the carriage returns
+    marked.extend(crs.map(|_| marker.clone()));
+    Line::from(marked)
+}
+
+type LoadedBlameHandles = (
+    Arc<Mutex<LoadedBlame>>,
+    Arc<AtomicBool>,
+    Arc<Mutex<Option<String>>>,
+);
+
+// parse and highlight the remaining lines in the background, like the pager does
+fn load_blame(
+    (mut iterator, child): (Lines<BufReader<ChildStdout>>, GitChild),
+    first: (Option<CommitInBlame>, String),
+    // the syntax is detected from the file unless given
+    (file, syntax): (String, Option<&'static SyntaxReference>),
+    theme: Theme,
+    show_cr: ShowCr,
+    (tab_width, date_format): (usize, DateFormat),
+    // code and highlighting of the previous blame
+    previous: Vec<(String, Line<'static>)>,
+) -> LoadedBlameHandles {
+    let blame = Arc::new(Mutex::new(LoadedBlame::default()));
+    let blame_clone = Arc::clone(&blame);
+
+    let loaded = Arc::new(AtomicBool::new(false));
+    let loaded_clone = Arc::clone(&loaded);
+
+    let error = Arc::new(Mutex::new(None));
+    let error_clone = Arc::clone(&error);
+
+    thread::spawn(move || {
+        let syntax = syntax.unwrap_or_else(|| detect_syntax(&file, &first.1));
+        let mut h = HighlightLines::new(syntax, &theme);
+        // walking through the history of a file often leaves its content unchanged
+        let mut reusing = true;
+        let mut idx = 0;
+        let mut parse_error = None;
+
+        let mut next = Some(first);
+        let n = 100;
+        loop {
+            let mut chunk = Vec::with_capacity(n);
+            for _ in 0..n {
+                let Some((commit, code)) = next.take() else {
+                    break;
+                };
+                let highlighted = match previous.get(idx) {
+                    Some((previous_code, highlighted)) if reusing && *previous_code == code => {
+                        highlighted.clone()
+                    }
+                    _ => {
+                        if reusing {
+                            reusing = false;
+                            // the highlighting state follows the lines reused so far
+                            for (previous_code, _) in &previous[..idx] {
+                                highlight_line(&mut h, previous_code, show_cr);
+                            }
+                        }
+                        highlight_line(&mut h, &code, show_cr)
+                    }
+                };
+                chunk.push(BlameLine {
+                    commit,
+                    code,
+                    highlighted,
+                });
+                idx += 1;
+                next = match next_blame_entry(&mut iterator, tab_width, &date_format) {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        parse_error = Some(format!("line {}: {}", idx + 1, err));
+                        None
+                    }
+                };
+            }
+            let author_len = chunk
+                .iter()
+                .map(|line| match &line.commit {
+                    Some(commit) => commit.author.len(),
+                    _ => "Not Committed Yet".len(),
+                })
+                .max()
+                .unwrap_or(0);
+            let date_len = chunk
+                .iter()
+                .filter_map(|line| line.commit.as_ref())
+                .map(|commit| commit.date.chars().count())
+                .max()
+                .unwrap_or(0);
+            let mut blame = blame_clone.lock().unwrap();
+            blame.max_author_len = blame.max_author_len.max(author_len);
+            blame.max_date_len = blame.max_date_len.max(date_len);
+            blame.lines.extend(chunk);
+            if next.is_none() {
+                break;
+            }
+        }
+        // read until the end for git to exit
+        iterator.for_each(drop);
+        *error_clone.lock().unwrap() = git_child_error(child).or(parse_error);
+        loaded_clone.store(true, Ordering::SeqCst);
+    });
+
+    (blame, loaded, error)
+}
+
+// print the blame of `file` as a JSON array, without the terminal, for editor integrations
+pub fn print_blame_json(file: String, revision: Option<String>) -> Result<(), Error> {
+    if !Path::new(&file).exists() {
+        return Err(Error::Global(format!("file '{}' does not exist", file)));
+    }
+    let config = parse_gitrs_config()?;
+    let ignore_whitespace = config.blame_ignore_whitespace;
+    let (output, child) = git_blame_output(file, revision, ignore_whitespace, &config)?;
+    let mut lines = output.lines();
+    let mut json_lines = Vec::new();
+    while let Some((commit, code)) =
+        next_blame_entry(&mut lines, config.tab_width, &config.date_format)?
+    {
+        let line = json_lines.len() + 1;
+        json_lines.push(match commit {
+            Some(commit) => BlameJsonLine {
+                line,
+                boundary: commit.hash.starts_with('^'),
+                hash: Some(commit.hash.trim_start_matches('^').to_string()),
+                author: Some(commit.author),
+                date: Some(commit.time.to_rfc3339()),
+                orig_line: Some(commit.orig_line),
+                code,
+            },
+            None => BlameJsonLine {
+                line,
+                hash: None,
+                boundary: false,
+                author: None,
+                date: None,
+                orig_line: None,
+                code,
+            },
+        });
+    }
+    if let Some(message) = git_child_error(child) {
+        return Err(Error::Global(message));
+    }
+    let json = serde_json::to_string(&json_lines).map_err(|err| Error::Global(err.to_string()))?;
+    println!("{}", json);
+    Ok(())
+}
+
 impl<'a> BlameApp {
-    pub fn new(file: String, revision: Option<String>, line: usize) -> Result<Self, Error> {
+    pub fn new(file: String, revision: Option<String>, line: Option<usize>) -> Result<Self, Error> {
         if !Path::new(&file).exists() {
             return Err(Error::Global(
                 format!("file '{}' does not exist", file).to_string(),
@@ -55,17 +374,25 @@ impl<'a> BlameApp {
         let files = vec![file.clone()];
 
         let mut state = AppState::new()?;
-        state.list_state.select(Some(line - 1));
+        // an explicit line wins over `default_position`
+        match line {
+            Some(line) => state.list_state.select(Some(line.saturating_sub(1))),
+            None => state.select_default(),
+        }
+        let ignore_whitespace = state.config.blame_ignore_whitespace;
         let mut instance = Self {
             state,
             file,
-            blames: Vec::new(),
-            code: Vec::new(),
+            blame: Arc::new(Mutex::new(LoadedBlame::default())),
+            loaded: Arc::new(AtomicBool::new(true)),
+            error: Arc::new(Mutex::new(None)),
+            highlighted_with: None,
             revisions,
             files,
+            selections: Vec::new(),
+            subjects: HashMap::new(),
+            ignore_whitespace,
             view_model: BlameAppViewModel {
-                blame_list: List::default(),
-                code_list: List::default(),
                 max_blame_len: 0,
                 rect: Rect::default(),
             },
@@ -74,6 +401,17 @@ impl<'a> BlameApp {
         Ok(instance)
     }
 
+    // fetched once per commit, not committed lines have no subject
+    fn get_subject(&mut self, idx: usize) -> Option<String> {
+        let commit = self.get_commit(idx).ok()??;
+        let hash = commit.hash.trim_start_matches('^').to_string();
+        if !self.subjects.contains_key(&hash) {
+            let subject = git_commit_subject(&hash, &self.state.config).ok()?;
+            self.subjects.insert(hash.clone(), subject);
+        }
+        self.subjects.get(&hash).cloned()
+    }
+
     fn get_current_file(&self) -> Result<String, Error> {
         Ok(self
             .files
@@ -82,58 +420,26 @@ impl<'a> BlameApp {
             .to_string())
     }
 
-    fn highlighted_lines(&mut self) -> Result<Vec<Line<'a>>, Error> {
-        let syn_set = syntax::extra_newlines();
-        let ts = ThemeSet::load_defaults();
-        let theme = &ts.themes["base16-ocean.dark"];
-
-        let file_text = self.code.join("\n");
-        let path = Path::new(&self.file);
-        let syntax = path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .and_then(|ext| syn_set.find_syntax_by_extension(ext))
-            .unwrap_or_else(|| {
-                syn_set.find_syntax_by_first_line(&file_text)
-                    .unwrap_or_else(|| syn_set.find_syntax_plain_text())
-            });
-        let mut h = HighlightLines::new(syntax, theme);
-
-        let mut lines: Vec<Line> = Vec::new();
-
-        for line in LinesWithEndings::from(&file_text) {
-            let ranges: Vec<(SyntectStyle, String)> = h
-                .highlight_line(line, &syn_set)?
-                .into_iter()
-                .map(|(style, text)| (style, text.to_string())) // Convert &str to owned String
-                .collect();
-            let spans: Vec<Span> = ranges
-                .into_iter()
-                .map(|(style, text)| {
-                    Span::styled(
-                        text,
-                        Style::default().fg(Color::Rgb(
-                            style.foreground.r,
-                            style.foreground.g,
-                            style.foreground.b,
-                        )),
-                    )
-                })
-                .collect();
-            lines.push(Line::from(spans));
-        }
-        Ok(lines)
+    fn len(&self) -> usize {
+        self.blame.lock().unwrap().lines.len()
+    }
+
+    fn get_commit(&self, idx: usize) -> Result<Option<CommitInBlame>, Error> {
+        let blame = self.blame.lock().unwrap();
+        let line = blame.lines.get(idx).ok_or_else(|| Error::StateIndex)?;
+        Ok(line.commit.clone())
     }
 
     fn displayed_blame_line(
         opt_commit: &Option<CommitInBlame>,
         idx: usize,
         max_author_len: usize,
+        max_date_len: usize,
         max_line_len: usize,
     ) -> Line<'a> {
         match opt_commit {
             Some(commit) => {
-                let date_color = date_to_color(&commit.date);
+                let date_color = date_to_color(&commit.time);
                 let displayed_hash: String = commit.hash.chars().take(4).collect();
                 let spans = vec![
                     Span::styled(displayed_hash, Style::from(Color::Blue)),
@@ -143,7 +449,10 @@ impl<'a> BlameApp {
                         Style::from(Color::Gray),
                     ),
                     Span::raw(" "),
-                    Span::styled(commit.date.clone(), Style::from(date_color)),
+                    Span::styled(
+                        format!("{:<max_date_len$}", commit.date),
+                        Style::from(date_color),
+                    ),
                     Span::raw(" "),
                     Span::styled(
                         format!("{:>max_line_len$}", idx + 1),
@@ -156,42 +465,32 @@ impl<'a> BlameApp {
         }
     }
 
-    fn parse_git_blame(
-        file: String,
-        revision: Option<String>,
-        config: &Config,
-    ) -> Result<(Vec<Option<CommitInBlame>>, Vec<String>), Error> {
-        let output = git_blame_output(file, revision.clone(), config)?;
-
-        let mut blame_column = Vec::new();
-        let mut code_column = Vec::new();
-
-        for line in output.lines() {
-            let (blame, code) = line.split_once(')').ok_or_else(|| Error::GitParsing)?;
-            code_column.push(code.to_string());
-            let blame_text = blame.to_string() + ")";
-            let (hash, _) = blame_text
-                .split_once(" ")
-                .ok_or_else(|| Error::GitParsing)?;
-            // for initial commit
-            blame_column.push(if hash.starts_with("0000") {
-                None
-            } else {
-                let (_, blame_text) = blame_text
-                    .split_once(" (")
-                    .ok_or_else(|| Error::GitParsing)?;
-                let metadata: Vec<&str> = blame_text.split_whitespace().collect();
-                let author = metadata[..metadata.len() - 4].join(" ").to_string();
-                let date = metadata[metadata.len() - 4].to_string();
-                Some(CommitInBlame {
-                    hash: hash.to_string(),
-                    author,
-                    date,
-                })
-            });
+    // blame the initial file at `rev`, the working tree when empty, dropping the revision stack
+    fn blame_at_rev(&mut self, rev: &Option<String>) -> Result<(), Error> {
+        let Some(rev) = rev else {
+            self.state.command_string.clear();
+            self.state.edit_cursor = 0;
+            self.state.input_state = InputState::BlameRev;
+            return Ok(());
+        };
+        let rev = rev.trim();
+        let revision = match rev.is_empty() {
+            true => None,
+            false if is_valid_git_rev(rev) => Some(rev.to_string()),
+            false => return Err(Error::Global(format!("invalid revision '{}'", rev))),
+        };
+        let file = self.files.first().ok_or_else(|| Error::StateIndex)?.clone();
+        let previous = (
+            std::mem::replace(&mut self.revisions, vec![revision]),
+            std::mem::replace(&mut self.files, vec![file]),
+            std::mem::take(&mut self.selections),
+        );
+        if let Err(err) = self.reload() {
+            // e.g. the file did not exist yet, the previous blame is kept
+            (self.revisions, self.files, self.selections) = previous;
+            return Err(err);
         }
-
-        Ok((blame_column, code_column))
+        Ok(())
     }
 }
 
@@ -204,8 +503,33 @@ impl GitApp for BlameApp {
         &self.state
     }
 
+    fn loaded(&self) -> bool {
+        self.loaded.load(Ordering::SeqCst)
+    }
+
     fn get_text_line(&self, idx: usize) -> Option<String> {
-        self.code.get(idx).cloned()
+        let blame = self.blame.lock().unwrap();
+        blame.lines.get(idx).map(|line| line.code.replace('\r', ""))
+    }
+
+    fn get_search_line(&self, idx: usize) -> Option<String> {
+        let code = self.get_text_line(idx)?;
+        if !self.state.config.blame_search_metadata {
+            return Some(code);
+        }
+        let blame = self.blame.lock().unwrap();
+        let max_line_len = blame.lines.len().to_string().len();
+        let blame_text = BlameApp::displayed_blame_line(
+            &blame.lines.get(idx)?.commit,
+            idx,
+            blame.max_author_len,
+            blame.max_date_len,
+            max_line_len,
+        )
+        .to_string();
+        // mimic the two columns, the code column border being replaced by a space
+        let width = self.view_model.max_blame_len;
+        Some(format!("{:<width$} {}", blame_text, code))
     }
 
     fn reload(&mut self) -> Result<(), Error> {
@@ -215,68 +539,138 @@ impl GitApp for BlameApp {
             .ok_or_else(|| Error::Global("blame app revision stack empty".to_string()))?;
         let file = self.get_current_file()?;
 
-        let (new_blames, new_code) =
-            BlameApp::parse_git_blame(file.clone(), revision.clone(), &self.state.config)?;
-        if new_blames.is_empty() {
-            self.revisions.pop();
-            self.files.pop();
-            return Ok(());
-        }
-        self.blames = new_blames;
-        self.code = new_code;
-        let len = self.blames.len();
-        let max_author_len = self
-            .blames
-            .iter()
-            .map(|opt_commit| match opt_commit {
-                Some(commit) => commit.author.len(),
-                _ => "Not Committed Yet".len(),
-            })
-            .max()
-            .unwrap_or(0);
-        let max_line_len = format!("{}", self.blames.len()).len();
-
-        let mut max_blame_len = 0;
-        let blame_items: Vec<ListItem> = self
-            .blames
-            .iter()
-            .enumerate()
-            .map(|(idx, opt_commit)| {
-                let display =
-                    BlameApp::displayed_blame_line(opt_commit, idx, max_author_len, max_line_len);
-                max_blame_len = max_blame_len.max(display.width());
-                ListItem::new(display)
-            })
-            .collect();
-        self.view_model.max_blame_len = max_blame_len;
-
-        self.view_model.blame_list = List::new(blame_items)
-            .highlight_style(highlight_style())
-            .scroll_padding(self.state.config.scrolloff);
-
-        let code_items: Vec<ListItem> = self
-            .highlighted_lines()?
-            .iter()
-            .map(|line| ListItem::new(line.clone()))
-            .collect();
-        self.view_model.code_list = List::new(code_items)
-            .block(Block::default().borders(Borders::LEFT))
-            .highlight_style(highlight_style())
-            .scroll_padding(self.state.config.scrolloff);
+        let (output, child) = git_blame_output(
+            file,
+            revision.clone(),
+            self.ignore_whitespace,
+            &self.state.config,
+        )?;
+        let mut lines = output.lines();
+        let first = match next_blame_entry(
+            &mut lines,
+            self.state.config.tab_width,
+            &self.state.config.date_format,
+        ) {
+            Ok(Some(entry)) => entry,
+            entry => {
+                lines.for_each(drop);
+                if let Some(message) = git_child_error(child) {
+                    // e.g. the file did not exist in the older revision
+                    if self.revisions.len() > 1 {
+                        self.revisions.pop();
+                        self.files.pop();
+                        let selection = self.selections.pop();
+                        self.state.list_state.select(selection);
+                    }
+                    return Err(Error::Global(message));
+                }
+                entry?;
+                // an empty file has no line to blame
+                self.blame = Arc::default();
+                self.loaded = Arc::new(AtomicBool::new(true));
+                self.view_model.max_blame_len = 0;
+                return Ok(());
+            }
+        };
 
-        match self.state().list_state.selected() {
-            None => self.state().list_state.select(Some(len - 1)),
-            Some(idx) => {
-                if idx >= len {
-                    self.state().list_state.select(Some(len - 1));
+        let theme = match load_theme(&self.state.config.theme) {
+            Ok(theme) => theme,
+            Err(err) => {
+                self.notif(NotifChannel::Error, Some(err.to_string()));
+                load_theme(DEFAULT_THEME)?
+            }
+        };
+        let syntax = match &self.state.config.syntax {
+            Some(name) => {
+                let syntax = syntax_by_token(name);
+                if syntax.is_none() {
+                    let message = format!("unknown syntax '{}', detected from the file", name);
+                    self.notif(NotifChannel::Error, Some(message));
                 }
+                syntax
             }
+            None => None,
+        };
+        let config = &self.state.config;
+        let highlighted_with = (config.theme.clone(), config.syntax.clone(), config.show_cr);
+        let previous = match self.highlighted_with.as_ref() == Some(&highlighted_with) {
+            true => std::mem::take(&mut self.blame.lock().unwrap().lines)
+                .into_iter()
+                .map(|line| (line.code, line.highlighted))
+                .collect(),
+            false => Vec::new(),
+        };
+        self.highlighted_with = Some(highlighted_with);
+        (self.blame, self.loaded, self.error) = load_blame(
+            (lines, child),
+            first,
+            (self.file.clone(), syntax),
+            theme,
+            self.state.config.show_cr,
+            (
+                self.state.config.tab_width,
+                self.state.config.date_format.clone(),
+            ),
+            previous,
+        );
+        self.view_model.max_blame_len = 0;
+        if self.state.list_state.selected().is_none() {
+            self.state.select_default();
         }
         Ok(())
     }
 
     fn draw(&mut self, frame: &mut Frame, rect: Rect) {
         self.view_model.rect = rect;
+        let error = self.error.lock().unwrap().take();
+        if let Some(message) = error {
+            self.notif(NotifChannel::Error, Some(message));
+        }
+        let loaded = self.loaded();
+        let blame = self.blame.lock().unwrap();
+        let len = blame.lines.len();
+        if len == 0 {
+            if loaded {
+                frame.render_widget(Paragraph::new("Empty file"), rect);
+            }
+            return;
+        }
+
+        // only build the items around the displayed ones, the blamed file may be huge
+        let height = rect.height as usize;
+        // the line to select may not be loaded yet, `last` even selecting `usize::MAX`
+        let target = self.state.list_state.selected().unwrap_or(0);
+        let selected = target.min(len - 1);
+        let offset = self.state.list_state.offset().min(len - 1);
+        let start = offset.min(selected).min(len - 1).saturating_sub(height);
+        let end = (offset.max(selected) + 2 * height).min(len);
+        let max_line_len = len.to_string().len();
+
+        let blame_items: Vec<Line> = blame.lines[start..end]
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                BlameApp::displayed_blame_line(
+                    &line.commit,
+                    start + idx,
+                    blame.max_author_len,
+                    blame.max_date_len,
+                    max_line_len,
+                )
+            })
+            .collect();
+        let code_items: Vec<Line> = blame.lines[start..end]
+            .iter()
+            .map(|line| line.highlighted.clone())
+            .collect();
+        drop(blame);
+
+        // the blame column width only grows while lines are loaded, to avoid flickering
+        let max_blame_len = blame_items.iter().map(|line| line.width()).max();
+        self.view_model.max_blame_len = self
+            .view_model
+            .max_blame_len
+            .max(max_blame_len.unwrap_or(0));
 
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
@@ -286,40 +680,54 @@ impl GitApp for BlameApp {
             ])
             .split(rect);
 
+        let blame_list = List::new(blame_items)
+            .highlight_style(highlight_style())
+            .scroll_padding(self.state.config.scrolloff);
+        let code_list = List::new(code_items)
+            .block(Block::default().borders(Borders::LEFT))
+            .highlight_style(highlight_style())
+            .scroll_padding(self.state.config.scrolloff);
+
+        let mut window_state = ListState::default()
+            .with_offset(offset - start)
+            .with_selected(Some(selected - start));
         StatefulWidget::render(
-            &self.view_model.blame_list,
+            &blame_list,
             chunks[0],
             frame.buffer_mut(),
-            &mut self.state.list_state,
+            &mut window_state,
         );
+        StatefulWidget::render(&code_list, chunks[1], frame.buffer_mut(), &mut window_state);
 
-        StatefulWidget::render(
-            &self.view_model.code_list,
-            chunks[1],
-            frame.buffer_mut(),
-            &mut self.state.list_state,
-        );
+        *self.state.list_state.offset_mut() = start + window_state.offset();
+        // keep targeting a line that is not loaded yet
+        if loaded || target < len {
+            self.state
+                .list_state
+                .select(window_state.selected().map(|idx| start + idx));
+        }
 
-        self.highlight_search(
-            frame,
-            Rect {
-                x: rect.x + chunks[1].x + 1,
-                y: rect.y,
-                width: chunks[1].width,
-                height: chunks[1].height,
+        let search_rect = match self.state.config.blame_search_metadata {
+            true => rect,
+            // skip the left border of the code column
+            false => Rect {
+                x: chunks[1].x + 1,
+                width: chunks[1].width.saturating_sub(1),
+                ..chunks[1]
             },
-        );
+        };
+        self.highlight_search(frame, search_rect);
 
         if let Ok(file) = self.get_current_file() {
-            self.notif(
-                NotifChannel::Line,
-                Some(format!(
-                    "{} - line {} of {}",
-                    file,
-                    self.idx().unwrap_or(0) + 1,
-                    self.blames.len(),
-                )),
-            );
+            let idx = self.idx().unwrap_or(0);
+            let mut message = format!("{} - line {} of {}", file, idx + 1, self.len());
+            if self.ignore_whitespace {
+                message.push_str(" (-w)");
+            }
+            if let Some(subject) = self.get_subject(idx) {
+                message.push_str(&format!(" - {}", subject));
+            }
+            self.notif(NotifChannel::Line, Some(message));
         }
     }
 
@@ -329,7 +737,7 @@ impl GitApp for BlameApp {
 
     fn get_file_rev_line(&self) -> Result<FileRevLine, Error> {
         let idx = self.idx()?;
-        let commit_ref = self.blames.get(idx).ok_or_else(|| Error::StateIndex)?;
+        let commit_ref = self.get_commit(idx)?;
 
         let rev = match commit_ref {
             Some(commit) => {
@@ -357,26 +765,57 @@ impl GitApp for BlameApp {
                 }
                 self.revisions.pop();
                 self.files.pop();
+                let selection = self.selections.pop();
+                self.state.list_state.select(selection);
                 self.reload()?;
             }
             Action::PreviousCommitBlame => {
                 let idx = self.idx()?;
-                let commit_ref = self.blames.get(idx).ok_or_else(|| Error::StateIndex)?;
+                let commit_ref = self.get_commit(idx)?;
                 let file = self.get_current_file()?;
-                let (rev, prev_file) = if let Some(commit) = commit_ref {
+                // follow the selected line in the parent revision
+                let (rev, prev_file, line) = if let Some(commit) = commit_ref {
                     if let Some('^') = commit.hash.chars().next() {
                         return Ok(());
                     }
                     let rev = format!("{}^", commit.hash);
                     let prev_file = get_previous_filename(&commit.hash, &file)?;
-                    (rev, prev_file.to_string())
+                    let paths = [prev_file.clone(), file.clone()];
+                    let line = git_line_in_parent(
+                        Some(&commit.hash),
+                        &paths,
+                        commit.orig_line,
+                        &self.state.config,
+                    );
+                    (rev, prev_file, line)
                 } else {
-                    ("HEAD".to_string(), file.clone())
+                    let paths = [file.clone()];
+                    let line = git_line_in_parent(None, &paths, idx + 1, &self.state.config);
+                    ("HEAD".to_string(), file.clone(), line)
                 };
                 self.revisions.push(Some(rev.clone()));
                 self.files.push(prev_file.clone());
+                self.selections.push(idx);
+                if let Ok(line) = line {
+                    self.state.list_state.select(Some(line.saturating_sub(1)));
+                }
                 self.reload()?;
             }
+            Action::BlameAtRev(rev) => self.blame_at_rev(rev)?,
+            Action::IgnoreWhitespaceBlame => {
+                self.ignore_whitespace = !self.ignore_whitespace;
+                self.reload()?;
+            }
+            Action::BlameOpenLog => {
+                let (file, rev, _) = self.get_file_rev_line()?;
+                let file = file.ok_or_else(|| Error::StateIndex)?;
+                // uncommitted lines start the history at HEAD
+                let rev = rev.unwrap_or_else(|| "HEAD".to_string());
+                let args = vec![rev, "--follow".to_string(), "--".to_string(), file];
+                terminal.clear()?;
+                PagerApp::new(Some(PagerCommand::Log(args)))?.run(terminal)?;
+                terminal.clear()?;
+            }
             _ => {
                 self.run_action_generic(action, self.view_model.rect.height as usize, terminal)?;
                 return Ok(());
@@ -388,13 +827,319 @@ impl GitApp for BlameApp {
     fn on_click(&mut self) {
         if self.view_model.rect.contains(self.state.mouse_position) {
             let delta = (self.state.mouse_position.y - self.view_model.rect.y) as usize;
-            self.state
-                .list_state
-                .select(Some(self.state.list_state.offset() + delta));
+            // clicking below the last line selects the last line in both columns
+            let idx = (self.state.list_state.offset() + delta).min(self.len().saturating_sub(1));
+            self.state.list_state.select(Some(idx));
         }
     }
 
     fn on_scroll(&mut self, down: bool) {
-        self.on_scroll_generic(down, self.view_model.rect.height as usize, self.code.len());
+        self.on_scroll_generic(down, self.view_model.rect.height as usize, self.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{config::Config, history::History};
+    use ratatui::{backend::TestBackend, layout::Position};
+
+    fn test_app(len: usize, scrolloff: usize) -> BlameApp {
+        let config = Config {
+            scrolloff,
+            ..Config::default()
+        };
+        let time = blame_date(1_700_000_000, "+0000");
+        let lines = (0..len)
+            .map(|idx| BlameLine {
+                commit: Some(CommitInBlame {
+                    hash: "abcdef".to_string(),
+                    author: "author".to_string(),
+                    date: "date".to_string(),
+                    time,
+                    orig_line: idx + 1,
+                }),
+                code: format!("code {}", idx + 1),
+                highlighted: Line::raw(format!("code {}", idx + 1)),
+            })
+            .collect();
+        BlameApp {
+            state: AppState::with_config(config, History::default()),
+            file: "file".to_string(),
+            blame: Arc::new(Mutex::new(LoadedBlame {
+                lines,
+                max_author_len: 6,
+                max_date_len: 4,
+            })),
+            loaded: Arc::new(AtomicBool::new(true)),
+            error: Arc::new(Mutex::new(None)),
+            highlighted_with: None,
+            revisions: vec![None],
+            files: vec!["file".to_string()],
+            selections: Vec::new(),
+            // no `git` call for the subject in the notification
+            subjects: HashMap::from([("abcdef".to_string(), "subject".to_string())]),
+            ignore_whitespace: false,
+            view_model: BlameAppViewModel {
+                max_blame_len: 0,
+                rect: Rect::default(),
+            },
+        }
+    }
+
+    fn draw(app: &mut BlameApp, terminal: &mut Terminal<TestBackend>) {
+        terminal
+            .draw(|frame| app.draw(frame, frame.area()))
+            .unwrap();
+    }
+
+    // line number of the gutter and of the code of each row, and the highlighted row
+    fn rows(terminal: &Terminal<TestBackend>) -> (Vec<(usize, usize)>, Option<u16>) {
+        let buffer = terminal.backend().buffer();
+        let mut rows = Vec::new();
+        let mut highlighted = None;
+        for y in 0..buffer.area.height {
+            let text: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            let (gutter, code) = text.split_once('│').unwrap();
+            // scrolled past the last line
+            if gutter.trim().is_empty() {
+                assert!(code.trim().is_empty());
+                continue;
+            }
+            let gutter_line = gutter.split_whitespace().last().unwrap().parse().unwrap();
+            let code_line = code.trim().trim_start_matches("code ").parse().unwrap();
+            rows.push((gutter_line, code_line));
+
+            let code_x = gutter.chars().count() as u16 + 1;
+            let gutter_bg = buffer[(0, y)].bg;
+            assert_eq!(gutter_bg, buffer[(code_x, y)].bg, "row {}", y);
+            if gutter_bg == highlight_style().bg.unwrap() {
+                assert_eq!(highlighted, None);
+                highlighted = Some(y);
+            }
+        }
+        (rows, highlighted)
+    }
+
+    fn assert_aligned(app: &BlameApp, terminal: &Terminal<TestBackend>) {
+        let (rows, highlighted) = rows(terminal);
+        for (gutter_line, code_line) in &rows {
+            assert_eq!(gutter_line, code_line);
+        }
+        for window in rows.windows(2) {
+            assert_eq!(window[0].0 + 1, window[1].0);
+        }
+        let selected = app.state.list_state.selected().unwrap();
+        let highlighted = highlighted.expect("no highlighted row") as usize;
+        assert_eq!(rows[highlighted].0, selected + 1);
+        assert_eq!(rows[0].0, app.state.list_state.offset() + 1);
+    }
+
+    #[test]
+    fn porcelain_entries() {
+        let porcelain = "\
+0123456789abcdef0123456789abcdef01234567 1 1 2
+author Jane (JD) Doe
+author-mail <jane@mail>
+author-time 1700000000
+author-tz -0530
+committer Jane (JD) Doe
+committer-mail <jane@mail>
+committer-time 1700000000
+committer-tz -0530
+summary first commit
+boundary
+filename main.rs
+\tfn main() {
+0123456789abcdef0123456789abcdef01234567 2 2
+author Jane (JD) Doe
+author-mail <jane@mail>
+author-time 1700000000
+author-tz -0530
+committer Jane (JD) Doe
+committer-mail <jane@mail>
+committer-time 1700000000
+committer-tz -0530
+summary first commit
+boundary
+filename main.rs
+\t\tprintln!(\"(a) b\");
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+author-mail <not.committed.yet>
+author-time 1700003600
+author-tz +0000
+committer Not Committed Yet
+committer-mail <not.committed.yet>
+committer-time 1700003600
+committer-tz +0000
+summary Version of main.rs from main.rs
+previous 0123456789abcdef0123456789abcdef01234567 main.rs
+filename main.rs
+\t}
+";
+        let date_format = DateFormat::Absolute("%Y-%m-%d %H:%M %z".to_string());
+        let mut lines = porcelain.as_bytes().lines();
+
+        let (commit, code) = next_blame_entry(&mut lines, 4, &date_format)
+            .unwrap()
+            .unwrap();
+        let commit = commit.unwrap();
+        assert_eq!(code, "fn main() {");
+        assert_eq!(commit.hash, "^0123456789abcdef0123456789abcdef01234567");
+        assert_eq!(commit.author, "Jane (JD) Doe");
+        assert_eq!(commit.date, "2023-11-14 16:43 -0530");
+        assert_eq!(
+            commit.time.offset().local_minus_utc(),
+            -(5 * 3600 + 30 * 60)
+        );
+        assert_eq!(commit.orig_line, 1);
+
+        let (commit, code) = next_blame_entry(&mut lines, 4, &date_format)
+            .unwrap()
+            .unwrap();
+        assert_eq!(code, "    println!(\"(a) b\");");
+        assert_eq!(commit.unwrap().orig_line, 2);
+
+        let (commit, code) = next_blame_entry(&mut lines, 4, &date_format)
+            .unwrap()
+            .unwrap();
+        assert!(commit.is_none());
+        assert_eq!(code, "}");
+
+        assert!(next_blame_entry(&mut lines, 4, &date_format)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn truncated_porcelain_is_an_error() {
+        let porcelain = "0123456789abcdef0123456789abcdef01234567 1 1 1\nauthor Jane\n";
+        let mut lines = porcelain.as_bytes().lines();
+        let date_format = DateFormat::Relative;
+        assert!(next_blame_entry(&mut lines, 4, &date_format).is_err());
+    }
+
+    // text of each span
+    fn highlighted(code_line: &str, show_cr: ShowCr) -> Vec<String> {
+        let theme = load_theme(DEFAULT_THEME).unwrap();
+        let syntax = syntax_set().find_syntax_by_extension("rs").unwrap();
+        let mut h = HighlightLines::new(syntax, &theme);
+        highlight_line(&mut h, code_line, show_cr)
+            .spans
+            .into_iter()
+            .map(|span| span.content.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn carriage_returns_are_marked_where_they_were() {
+        let text = |spans: Vec<String>| spans.concat();
+        assert_eq!(text(highlighted("a\rb", ShowCr::Show)), "a^Mb\n");
+        assert_eq!(text(highlighted("ab\r", ShowCr::Show)), "ab^M\n");
+        assert_eq!(text(highlighted("\ra\r\r", ShowCr::Show)), "^Ma^M^M\n");
+        assert_eq!(text(highlighted("a\rb\r", ShowCr::Hide)), "ab\n");
+
+        // the marker is a span of its own, not highlighted as code
+        let spans = highlighted("let s = \"a\rb\";", ShowCr::Highlight);
+        assert!(spans.contains(&"^M".to_string()));
+        assert_eq!(text(spans), "let s = \"a^Mb\";\n");
+    }
+
+    #[test]
+    fn crlf_porcelain_entries() {
+        let porcelain = "\
+0123456789abcdef0123456789abcdef01234567 1 1 2\r
+author Jane\r
+author-time 1700000000\r
+author-tz +0000\r
+filename main.rs\r
+\tfn main() {\r
+0123456789abcdef0123456789abcdef01234567 2 2\r
+author Jane\r
+author-time 1700000000\r
+author-tz +0000\r
+filename main.rs\r
+\tbad\rmiddle\r
+";
+        let date_format = DateFormat::Relative;
+        let mut lines = porcelain.as_bytes().lines();
+        let mut codes = Vec::new();
+        while let Some((commit, code)) = next_blame_entry(&mut lines, 4, &date_format).unwrap() {
+            assert_eq!(commit.unwrap().author, "Jane");
+            codes.push(code);
+        }
+        // the line endings are dropped, the other carriage returns kept to be marked
+        assert_eq!(codes, vec!["fn main() {", "bad\rmiddle"]);
+    }
+
+    #[test]
+    fn gutter_and_code_line_up_at_every_offset() {
+        for height in 1..8 {
+            for scrolloff in [0, 2, 100] {
+                let mut app = test_app(30, scrolloff);
+                let mut terminal = Terminal::new(TestBackend::new(40, height)).unwrap();
+                for offset in 0..30 {
+                    for selected in [0, offset, offset + height as usize / 2, 29] {
+                        *app.state.list_state.offset_mut() = offset;
+                        app.state.list_state.select(Some(selected));
+                        draw(&mut app, &mut terminal);
+                        assert_aligned(&app, &terminal);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gutter_and_code_line_up_when_scrolling() {
+        for height in 1..8 {
+            let mut app = test_app(30, 2);
+            let mut terminal = Terminal::new(TestBackend::new(40, height)).unwrap();
+            draw(&mut app, &mut terminal);
+            for down in [true, false] {
+                for _ in 0..20 {
+                    app.on_scroll(down);
+                    draw(&mut app, &mut terminal);
+                    assert_aligned(&app, &terminal);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn click_selects_the_line_of_the_row_in_both_columns() {
+        let height = 6;
+        let mut app = test_app(30, 0);
+        let mut terminal = Terminal::new(TestBackend::new(40, height)).unwrap();
+        for offset in [0, 10, 24] {
+            for y in 0..height {
+                *app.state.list_state.offset_mut() = offset;
+                app.state.list_state.select(Some(offset));
+                draw(&mut app, &mut terminal);
+                let (rows, _) = rows(&terminal);
+
+                app.state.mouse_position = Position::new(0, y);
+                app.on_click();
+                assert_eq!(
+                    app.state.list_state.selected(),
+                    Some(rows[y as usize].0 - 1)
+                );
+                draw(&mut app, &mut terminal);
+                assert_aligned(&app, &terminal);
+            }
+        }
+    }
+
+    #[test]
+    fn click_below_the_last_line_selects_it() {
+        let mut app = test_app(3, 0);
+        let mut terminal = Terminal::new(TestBackend::new(40, 6)).unwrap();
+        draw(&mut app, &mut terminal);
+        app.state.mouse_position = Position::new(0, 5);
+        app.on_click();
+        assert_eq!(app.state.list_state.selected(), Some(2));
     }
 }
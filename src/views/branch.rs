@@ -0,0 +1,247 @@
+use crate::app::{FileRevLine, GitApp};
+
+use crate::model::{
+    action::{Action, CommandType},
+    app_state::{AppState, NotifChannel},
+    config::MappingScope,
+    errors::Error,
+    git::{git_branch_output, git_diff_is_empty, Branch},
+};
+use crate::ui::utils::highlight_style;
+use crate::views::pager::{PagerApp, PagerCommand};
+
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, Paragraph, StatefulWidget},
+    Frame, Terminal,
+};
+
+struct BranchAppViewModel {
+    branch_list: List<'static>,
+    rect: Rect,
+}
+
+pub struct BranchApp {
+    state: AppState,
+    branches: Vec<Branch>,
+    marked: Option<String>,
+    view_model: BranchAppViewModel,
+}
+
+impl BranchApp {
+    pub fn new() -> Result<Self, Error> {
+        let state = AppState::new()?;
+        let mut r = Self {
+            state,
+            branches: Vec::new(),
+            marked: None,
+            view_model: BranchAppViewModel {
+                branch_list: List::default(),
+                rect: Rect::default(),
+            },
+        };
+        r.reload()?;
+        r.state.select_default();
+        Ok(r)
+    }
+
+    fn update_list(&mut self) {
+        let list_items: Vec<Line> = self
+            .branches
+            .iter()
+            .map(|branch| {
+                let (prefix, style) = match (branch.current, branch.remote) {
+                    (true, _) => ("* ", Style::from(Color::Green).add_modifier(Modifier::BOLD)),
+                    (false, true) => ("  ", Style::from(Color::Red)),
+                    (false, false) => ("  ", Style::from(Color::White)),
+                };
+                let (prefix, style) = match Some(&branch.name) == self.marked.as_ref() {
+                    true => ("+ ", style.add_modifier(Modifier::UNDERLINED)),
+                    false => (prefix, style),
+                };
+                Line::from(vec![
+                    Span::styled(prefix, style),
+                    Span::styled(branch.name.clone(), style),
+                ])
+            })
+            .collect();
+        self.view_model.branch_list = List::new(list_items)
+            .highlight_style(highlight_style())
+            .scroll_padding(self.state.config.scrolloff);
+    }
+
+    fn diff_branch(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> Result<(), Error> {
+        let (_, rev, _) = self.get_file_rev_line()?;
+        let rev = rev.ok_or_else(|| Error::StateIndex)?;
+        let base = self.marked.clone().unwrap_or_else(|| "HEAD".to_string());
+        let range = format!("{}...{}", base, rev);
+        if git_diff_is_empty(&range, &self.state.config)? {
+            let message = format!("no difference between {} and {}", base, rev);
+            self.notif(NotifChannel::Echo, Some(message));
+            return Ok(());
+        }
+        terminal.clear()?;
+        PagerApp::new(Some(PagerCommand::Diff(vec![range])))?.run(terminal)?;
+        terminal.clear()?;
+        Ok(())
+    }
+
+    fn get_branch(&self) -> Result<&Branch, Error> {
+        self.branches
+            .get(self.idx()?)
+            .ok_or_else(|| Error::StateIndex)
+    }
+}
+
+impl GitApp for BranchApp {
+    fn state(&mut self) -> &mut AppState {
+        &mut self.state
+    }
+
+    fn get_state(&self) -> &AppState {
+        &self.state
+    }
+
+    fn reload(&mut self) -> Result<(), Error> {
+        let output = git_branch_output(&self.state.config)?;
+        self.branches = output
+            .lines()
+            // symbolic refs, displayed as `remotes/origin/HEAD -> origin/main`, point to a branch
+            // listed on its own
+            .filter(|line| line.len() > 2 && !line.contains(" -> "))
+            .map(|line| {
+                let current = line.starts_with('*');
+                let name = line[2..].to_string();
+                Branch {
+                    remote: name.starts_with("remotes/"),
+                    current,
+                    name,
+                }
+            })
+            .collect();
+
+        if !self
+            .branches
+            .iter()
+            .any(|branch| Some(&branch.name) == self.marked.as_ref())
+        {
+            self.marked = None;
+        }
+        self.update_list();
+        Ok(())
+    }
+
+    fn get_text_line(&self, idx: usize) -> Option<String> {
+        self.branches.get(idx).map(|branch| branch.name.clone())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        self.view_model.rect = rect;
+        if self.branches.is_empty() {
+            let paragraph = Paragraph::new("No branches yet");
+            frame.render_widget(paragraph, rect);
+            return;
+        }
+        StatefulWidget::render(
+            &self.view_model.branch_list,
+            rect,
+            frame.buffer_mut(),
+            &mut self.state.list_state,
+        );
+
+        self.highlight_search(
+            frame,
+            Rect {
+                x: rect.x + 2,
+                width: rect.width.saturating_sub(2),
+                ..rect
+            },
+        );
+    }
+
+    fn get_mapping_fields(&self) -> Vec<MappingScope> {
+        vec![MappingScope::Branch]
+    }
+
+    fn get_file_rev_line(&self) -> Result<FileRevLine, Error> {
+        let branch = self.get_branch()?;
+        let rev = match branch.name.starts_with('(') {
+            // detached HEAD
+            true => "HEAD".to_string(),
+            false => branch.name.clone(),
+        };
+        Ok((None, Some(rev), None))
+    }
+
+    fn run_action(
+        &mut self,
+        action: &Action,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> Result<(), Error> {
+        match action {
+            Action::BranchCheckout | Action::BranchDelete => {
+                let branch = self.get_branch()?;
+                if branch.current {
+                    let message = match action {
+                        Action::BranchCheckout => format!("already on {}", branch.name),
+                        _ => format!("cannot delete the current branch {}", branch.name),
+                    };
+                    return Err(Error::Global(message));
+                }
+                let (command, rev) = match (action, branch.remote) {
+                    // `git checkout <name>` creates the matching local tracking branch
+                    (Action::BranchCheckout, true) => {
+                        ("%(git) checkout %(rev)", branch.name.splitn(3, '/').nth(2))
+                    }
+                    (Action::BranchCheckout, false) => {
+                        ("%(git) checkout %(rev)", Some(branch.name.as_str()))
+                    }
+                    (_, true) => (
+                        "%(git) branch -d -r %(rev)",
+                        branch.name.strip_prefix("remotes/"),
+                    ),
+                    (_, false) => ("%(git) branch -d %(rev)", Some(branch.name.as_str())),
+                };
+                let command = command.to_string();
+                let rev = rev.map(|rev| rev.to_string());
+                self.run_command(terminal, &CommandType::Sync, command, None, rev, None)?;
+            }
+            Action::BranchMark => {
+                let name = self.get_branch()?.name.clone();
+                self.marked = match self.marked.as_ref() == Some(&name) {
+                    true => None,
+                    false => Some(name),
+                };
+                self.update_list();
+            }
+            Action::DiffBranch => self.diff_branch(terminal)?,
+            _ => {
+                self.run_action_generic(action, self.view_model.rect.height as usize, terminal)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_click(&mut self) {
+        if self.view_model.rect.contains(self.state.mouse_position) {
+            let delta = (self.state.mouse_position.y - self.view_model.rect.y) as usize;
+            let idx =
+                (self.state.list_state.offset() + delta).min(self.branches.len().saturating_sub(1));
+            self.state.list_state.select(Some(idx));
+        }
+    }
+
+    fn on_scroll(&mut self, down: bool) {
+        self.on_scroll_generic(
+            down,
+            self.view_model.rect.height as usize,
+            self.branches.len(),
+        );
+    }
+}
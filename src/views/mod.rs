@@ -1,5 +1,9 @@
 pub mod blame;
+pub mod branch;
 pub mod pager;
+pub mod remote;
 pub mod show;
 pub mod stash;
 pub mod status;
+pub mod tag;
+pub mod tree;
@@ -4,26 +4,42 @@ use std::path::Path;
 use std::process::ChildStdout;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use std::{env, io, thread};
 
-use ratatui::{backend::CrosstermBackend, layout::Rect, widgets::Clear, Frame, Terminal};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Position, Rect},
+    widgets::{Clear, Paragraph},
+    Frame, Terminal,
+};
 
 use regex::Regex;
 
 use crate::app::{FileRevLine, GitApp};
 use crate::model::{
     action::Action,
-    app_state::{AppState, NotifChannel},
-    config::MappingScope,
+    app_state::{AppState, InputState, NotifChannel},
+    config::{Config, DefaultPosition, LineNumbers, MappingScope, ShowCr},
     errors::Error,
-    git::{git_pager_output, is_valid_git_rev, set_git_dir},
+    git::{
+        git_apply_cached, git_child_error, git_commit_hash, git_hunk_patch, git_pager_output,
+        is_valid_git_rev, set_git_dir, GitChild,
+    },
+};
+use crate::ui::{
+    line_filter::LineFilter,
+    pager_widget::{PagerWidget, RowCounts},
+    side_by_side::SideBySide,
+    utils::{clean_buggy_characters, mouse_selection_style},
 };
-use crate::ui::{pager_widget::PagerWidget, utils::clean_buggy_characters};
 
 struct PagerAppViewModel {
     list: PagerWidget,
     rect: Rect,
     scroll: Option<bool>,
+    // cleared when the displayed lines change or the view is resized
+    row_counts: RowCounts,
 }
 
 #[derive(PartialEq, Debug)]
@@ -51,40 +67,94 @@ impl fmt::Display for LogStyle {
     }
 }
 
+#[derive(Clone)]
 pub enum PagerCommand {
     Log(Vec<String>),
     Show(Vec<String>),
     Diff(Vec<String>),
+    Reflog(Vec<String>),
 }
 
 pub struct PagerApp {
     state: AppState,
+    pager_command: Option<PagerCommand>,
     mapping_scopes: Vec<MappingScope>,
     lines: Arc<Mutex<Vec<String>>>,
+    // width of the `--graph` drawing starting each line
+    graph_widths: Arc<Mutex<Vec<usize>>>,
     log_style: LogStyle,
     loaded: Arc<AtomicBool>,
+    // start of the loading, its duration being displayed until all the lines are read
+    loading_since: Instant,
+    // failure of the command, reported once its output is read
+    error: Arc<Mutex<Option<String>>>,
     original_dir: std::path::PathBuf,
     graph: bool,
+    // keep the last line selected while lines are being loaded
+    follow: bool,
+    // summary of the changes with `--stat` instead of the command output
+    stat: bool,
+    // revision looked for with `go_to_commit`, its hash and the next line to scan as lines load
+    go_to_commit: Option<(String, String, usize)>,
+    // rows of the side-by-side view when enabled, the list state then indexing them
+    side_by_side: Option<SideBySide>,
+    // lines matching `filter_lines` when set, the list state then indexing them
+    filter: Option<LineFilter>,
     view_model: PagerAppViewModel,
 }
 
 pub enum LogInput {
-    Command(Lines<BufReader<ChildStdout>>),
+    Command(Lines<BufReader<ChildStdout>>, GitChild),
     Stdin,
 }
 
-fn remove_graph_symbols(line: &mut String) {
-    // remove | and * graph chars
-    loop {
-        if let Some(first_char) = line.chars().next() {
-            let second_char = line.chars().next();
-            if first_char == '*' || first_char == '|' || second_char == Some(' ') {
-                *line = line.chars().skip(2).collect();
-                continue;
-            }
+// `--graph` draws columns two characters wide, a lane then the link to the next one
+const GRAPH_LANES: &str = "|/\\_ ";
+const GRAPH_LINKS: &str = " /\\_";
+
+// width of the graph columns starting a stripped line, and whether the line is a commit one
+fn graph_columns(line: &str) -> (usize, bool) {
+    // graph characters are ascii, so the graph ends on a char boundary
+    let bytes = line.as_bytes();
+    let (mut width, mut commit, mut octopus, mut blank) = (0, false, false, false);
+    while width + 1 < bytes.len() {
+        let (lane, link) = (bytes[width] as char, bytes[width + 1] as char);
+        if lane == '*' && !commit && !blank && (link == ' ' || link == '-') {
+            // `*-.` starts the dashes of a merge with more than two parents
+            commit = true;
+            octopus = link == '-';
+        } else if octopus && (lane == '-' || lane == '.') {
+            octopus = lane == '-';
+        } else if GRAPH_LANES.contains(lane) && GRAPH_LINKS.contains(link) {
+            blank |= lane == ' ' && link == ' ';
+        } else {
+            break;
         }
-        break;
+        width += 2;
+    }
+    (width, commit)
+}
+
+// git pads the lines following a commit one to its graph width, so that blank lanes are
+// told apart from the indentation of the content
+fn graph_width(line: &str, region: &mut usize) -> usize {
+    let (width, commit) = graph_columns(line);
+    if commit {
+        *region = width;
+        return width;
     }
+    width.min(*region)
+}
+
+// abbreviated or full object name, decorations or graph leftovers are not
+fn looks_like_hash(word: &str) -> bool {
+    (4..=64).contains(&word.len()) && word.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// the hash is the first word of a `--oneline` line, lines only drawing the graph having none
+fn oneline_commit(content: &str) -> Option<&str> {
+    let (commit, _) = content.split_once(' ').unwrap_or((content, ""));
+    looks_like_hash(commit).then_some(commit)
 }
 
 fn guess_log_style(line: &mut str) -> LogStyle {
@@ -99,7 +169,7 @@ fn guess_log_style(line: &mut str) -> LogStyle {
                 LogStyle::StashPager
             } else if line.contains(" 1) ") {
                 LogStyle::Unknown
-            } else if words.next().is_some() && is_valid_git_rev(rev) {
+            } else if words.next().is_some() && looks_like_hash(rev) && is_valid_git_rev(rev) {
                 LogStyle::OneLine
             } else {
                 LogStyle::Unknown
@@ -109,139 +179,333 @@ fn guess_log_style(line: &mut str) -> LogStyle {
     }
 }
 
+fn spawn_pager_command(
+    pager_command: &PagerCommand,
+    stat: bool,
+    config: &Config,
+) -> Result<(LogInput, LogStyle), Error> {
+    let (git_command, args, style) = match pager_command {
+        PagerCommand::Log(args) => ("log", args, LogStyle::Unknown),
+        PagerCommand::Show(args) => ("show", args, LogStyle::Standard),
+        PagerCommand::Diff(args) => ("diff", args, LogStyle::Diff),
+        PagerCommand::Reflog(args) => ("reflog", args, LogStyle::Reflog),
+    };
+    let mut args = args.clone();
+    if stat {
+        args.insert(0, "--stat".to_string());
+    }
+    let (bufreader, child) = git_pager_output(git_command, args, config)?;
+    Ok((LogInput::Command(bufreader.lines(), child), style))
+}
+
+// message of the command once its output is read, if it failed
+fn command_error(iterator: LogInput) -> Option<String> {
+    let LogInput::Command(_, child) = iterator else {
+        return None;
+    };
+    git_child_error(child)
+}
+
+fn first_line(
+    iterator: &mut LogInput,
+    show_cr: ShowCr,
+    tab_width: usize,
+) -> Option<Result<String, Error>> {
+    let first_line_ansi = match iterator {
+        LogInput::Command(ref mut lines, _) => lines.by_ref().next(),
+        LogInput::Stdin => {
+            let stdin = io::stdin();
+            let handle = stdin.lock();
+            let mut lines = handle.lines();
+            lines.next()
+        }
+    }?;
+    Some(
+        first_line_ansi
+            .map(|line| clean_buggy_characters(&line, show_cr, tab_width))
+            .map_err(Error::from),
+    )
+}
+
+// lines read so far, the width of their graph, whether all of them are read, and the error
+// of the command once they are
+type LoadedLines = (
+    Arc<Mutex<Vec<String>>>,
+    Arc<Mutex<Vec<usize>>>,
+    Arc<AtomicBool>,
+    Arc<Mutex<Option<String>>>,
+);
+
+// read the remaining lines in the background
+fn load_lines(
+    mut iterator: LogInput,
+    first_line_ansi: Option<String>,
+    show_cr: ShowCr,
+    tab_width: usize,
+    graph: bool,
+) -> LoadedLines {
+    let Some(first_line_ansi) = first_line_ansi else {
+        // nothing to read, only the failure of the command to report
+        let error = command_error(iterator);
+        return (
+            Arc::new(Mutex::new(Vec::new())),
+            Arc::new(Mutex::new(Vec::new())),
+            Arc::new(AtomicBool::new(true)),
+            Arc::new(Mutex::new(error)),
+        );
+    };
+    // graph width of the commit being read
+    let mut region = 0;
+    let mut line_graph_width = move |line: &str| match graph {
+        true => graph_width(
+            &String::from_utf8_lossy(&strip_ansi_escapes::strip(line)),
+            &mut region,
+        ),
+        false => 0,
+    };
+
+    let graph_widths = Arc::new(Mutex::new(vec![line_graph_width(&first_line_ansi)]));
+    let graph_widths_clone = Arc::clone(&graph_widths);
+
+    let lines = Arc::new(Mutex::new(vec![first_line_ansi]));
+    let lines_clone = Arc::clone(&lines);
+
+    let loaded = Arc::new(AtomicBool::new(false));
+    let loaded_clone = Arc::clone(&loaded);
+
+    let error = Arc::new(Mutex::new(None));
+    let error_clone = Arc::clone(&error);
+
+    thread::spawn(move || {
+        let n = 100;
+        let mut stdin_lines = match iterator {
+            LogInput::Stdin => Some(io::stdin().lock().lines()),
+            LogInput::Command(..) => None,
+        };
+        loop {
+            let mut chunk = Vec::with_capacity(n);
+            for _ in 0..n {
+                let next = match iterator {
+                    LogInput::Command(ref mut lines, _) => lines.by_ref().next(),
+                    LogInput::Stdin => stdin_lines.as_mut().unwrap().next(),
+                };
+                match next {
+                    Some(res_line) => chunk.push(match res_line {
+                        Ok(line) => clean_buggy_characters(&line, show_cr, tab_width),
+                        Err(_) => {
+                            "\x1b[31m/!\\ *** ERROR *** /!\\: gitrs could not read that line\x1b[0m"
+                                .to_string()
+                        }
+                    }),
+                    None => {
+                        // widths first, a line is never read without its own
+                        let widths = chunk.iter().map(|line| line_graph_width(line));
+                        graph_widths_clone.lock().unwrap().extend(widths);
+                        lines_clone.lock().unwrap().extend(chunk);
+                        *error_clone.lock().unwrap() = command_error(iterator);
+                        loaded_clone.store(true, Ordering::SeqCst);
+                        return;
+                    }
+                }
+            }
+            let widths = chunk.iter().map(|line| line_graph_width(line));
+            graph_widths_clone.lock().unwrap().extend(widths);
+            lines_clone.lock().unwrap().extend(chunk);
+        }
+    });
+    (lines, graph_widths, loaded, error)
+}
+
 impl PagerApp {
     pub fn new(pager_command: Option<PagerCommand>) -> Result<Self, Error> {
         let state = AppState::new()?;
-        let git_exe = state.config.git_exe.clone();
+        let show_cr = state.config.show_cr;
+        // captured before the reader thread is spawned
+        let tab_width = state.config.tab_width;
         let mut log_style = LogStyle::Unknown;
 
-        let mut iterator = match pager_command {
+        let mut iterator = match &pager_command {
             Some(pager_command) => {
-                let (git_command, args, style) = match pager_command {
-                    PagerCommand::Log(args) => ("log", args, LogStyle::Unknown),
-                    PagerCommand::Show(args) => ("show", args, LogStyle::Standard),
-                    PagerCommand::Diff(args) => ("diff", args, LogStyle::Diff),
-                };
+                let (iterator, style) = spawn_pager_command(pager_command, false, &state.config)?;
                 log_style = style;
-                let bufreader: BufReader<ChildStdout> =
-                    git_pager_output(git_command, git_exe, args)?;
-                LogInput::Command(bufreader.lines())
+                iterator
             }
             None => LogInput::Stdin,
         };
-        let mut first_line_ansi = match iterator {
-            LogInput::Command(ref mut lines) => lines.by_ref().next(),
-            LogInput::Stdin => {
-                let stdin = io::stdin();
-                let handle = stdin.lock();
-                let mut lines = handle.lines();
-                lines.next()
-            }
-        }
-        .ok_or_else(|| Error::Global("no data provided to the pager".to_string()))??;
-        first_line_ansi = clean_buggy_characters(&first_line_ansi);
+        // an empty output is displayed as such, with the error of the command if any
+        let first_line_ansi = first_line(&mut iterator, show_cr, tab_width).transpose()?;
 
-        let first_line = String::from_utf8(strip_ansi_escapes::strip(first_line_ansi.as_bytes()))?;
+        let stripped = strip_ansi_escapes::strip(first_line_ansi.as_deref().unwrap_or_default());
+        let first_line = String::from_utf8(stripped)?;
 
         // Test if there is a graph mode
-        let graph = Some("*") == first_line.split(' ').next();
+        let (width, graph) = graph_columns(&first_line);
 
-        let mut line = first_line.clone();
-        if graph {
-            remove_graph_symbols(&mut line);
-        }
+        let mut line = first_line[width..].to_string();
         if log_style == LogStyle::Unknown {
             log_style = guess_log_style(&mut line);
         }
 
         let mapping_scope = match log_style {
             LogStyle::Diff => MappingScope::Diff,
-            LogStyle::Reflog => MappingScope::Log,
+            LogStyle::Reflog => MappingScope::Reflog,
             LogStyle::Standard => MappingScope::Log,
             LogStyle::OneLine => MappingScope::Log,
             LogStyle::StashPager => MappingScope::Log,
             _ => MappingScope::Pager,
         };
-        let mapping_scopes = vec![mapping_scope];
+        let mapping_scopes = match mapping_scope {
+            // log mappings also apply to the reflog
+            MappingScope::Reflog => vec![MappingScope::Reflog, MappingScope::Log],
+            mapping_scope => vec![mapping_scope],
+        };
 
-        let lines = Arc::new(Mutex::new(vec![first_line_ansi]));
-        let lines_clone = Arc::clone(&lines);
+        let (lines, graph_widths, loaded, error) =
+            load_lines(iterator, first_line_ansi, show_cr, tab_width, graph);
 
-        let loaded = Arc::new(AtomicBool::new(false));
-        let loaded_clone = Arc::clone(&loaded);
-
-        thread::spawn(move || {
-            let n = 100;
-            let mut stdin_lines = match iterator {
-                LogInput::Stdin => Some(io::stdin().lock().lines()),
-                LogInput::Command(_) => None,
-            };
-            loop {
-                let mut chunk = Vec::with_capacity(n);
-                for _ in 0..n {
-                    let next = match iterator {
-                        LogInput::Command(ref mut lines) => lines.by_ref().next(),
-                        LogInput::Stdin => stdin_lines.as_mut().unwrap().next(),
-                    };
-                    match next {
-                        Some(res_line) => {
-                            chunk.push(match res_line {
-                                Ok(line) => clean_buggy_characters(&line),
-                                Err(_) => "\x1b[31m/!\\ *** ERROR *** /!\\: gitrs could not read that line\x1b[0m".to_string(),
-                            })
-                        }
-                        None => {
-                            lines_clone.lock().unwrap().extend(chunk);
-                            loaded_clone.store(true, Ordering::SeqCst);
-                            return;
-                        },
-                    }
-                }
-                lines_clone.lock().unwrap().extend(chunk);
-            }
-        });
-
-        let original_dir = env::current_dir()?;
-        set_git_dir(&state.config)?;
+        let original_dir = set_git_dir(&state.config)?;
+        // the bottom is followed as the lines load
+        let follow = state.config.default_position == DefaultPosition::Bottom;
 
         let mut r = Self {
             state,
+            pager_command,
             mapping_scopes,
             lines,
+            graph_widths,
             log_style,
             loaded,
+            loading_since: Instant::now(),
+            error,
             original_dir,
             graph,
+            follow,
+            stat: false,
+            go_to_commit: None,
+            side_by_side: None,
+            filter: None,
             view_model: PagerAppViewModel {
                 list: PagerWidget::default(),
                 rect: Rect::default(),
                 scroll: None,
+                row_counts: RowCounts::new(),
             },
         };
-        r.state.list_state.select_first();
+        r.state.select_default();
         Ok(r)
     }
 
-    fn get_stripped_line(&self, idx: usize) -> Result<String, Error> {
-        let s = self
-            .lines
+    // index of the line displayed on the given row
+    fn line_of_row(&self, row: usize) -> Option<usize> {
+        match (&self.side_by_side, &self.filter) {
+            (Some(side_by_side), _) => side_by_side.line_idx(row),
+            (_, Some(filter)) => filter.line_idx(row),
+            _ => Some(row),
+        }
+    }
+
+    // row displaying the given line, if any
+    fn row_of_line(&self, idx: usize) -> Option<usize> {
+        match (&self.side_by_side, &self.filter) {
+            (Some(side_by_side), _) => side_by_side.row_of_line(idx),
+            (_, Some(filter)) => filter.row_of_line(idx),
+            _ => Some(idx),
+        }
+    }
+
+    fn line_idx(&self) -> Result<usize, Error> {
+        self.line_of_row(self.idx()?)
+            .ok_or_else(|| Error::StateIndex)
+    }
+
+    fn select_line(&mut self, idx: usize) {
+        if let Some(row) = self.row_of_line(idx) {
+            self.state.list_state.select(Some(row));
+        }
+    }
+
+    fn toggle_side_by_side(&mut self) -> Result<(), Error> {
+        if !matches!(self.log_style, LogStyle::Diff | LogStyle::Standard) {
+            let message = "the side-by-side view is only available for diffs";
+            return Err(Error::Global(message.to_string()));
+        }
+        if self.filter.is_some() {
+            let message = "the side-by-side view is not available while lines are filtered";
+            return Err(Error::Global(message.to_string()));
+        }
+        let idx = self.line_idx().unwrap_or(0);
+        self.side_by_side = match self.side_by_side {
+            Some(_) => None,
+            None => {
+                let mut side_by_side = SideBySide::default();
+                let width = self.view_model.rect.width as usize;
+                side_by_side.update(&self.lines.lock().unwrap(), self.loaded(), width);
+                Some(side_by_side)
+            }
+        };
+        self.select_line(idx);
+        Ok(())
+    }
+
+    fn filter_lines(&mut self, pattern: &Option<String>) -> Result<(), Error> {
+        let Some(pattern) = pattern else {
+            self.state.command_string.clear();
+            self.state.edit_cursor = 0;
+            self.state.input_state = InputState::FilterLines;
+            return Ok(());
+        };
+        if self.side_by_side.is_some() {
+            let message = "lines cannot be filtered in the side-by-side view";
+            return Err(Error::Global(message.to_string()));
+        }
+        let regex = self.case_regex(pattern)?;
+        let idx = self.line_idx().unwrap_or(0);
+        let mut filter = LineFilter::new(regex, self.state.config.filter_context);
+        filter.update(&self.lines.lock().unwrap());
+        let row = filter.nearest_row(idx);
+        self.filter = Some(filter);
+        self.view_model.row_counts.clear();
+        self.follow = false;
+        self.state.list_state.select(Some(row));
+        Ok(())
+    }
+
+    fn clear_filter(&mut self) {
+        let idx = self.line_idx();
+        if self.filter.take().is_some() {
+            self.view_model.row_counts.clear();
+            self.select_line(idx.unwrap_or(0));
+        }
+    }
+
+    // line as read, with its escape sequences
+    pub fn get_raw_line(&self, idx: usize) -> Result<String, Error> {
+        self.lines
             .lock()
             .unwrap()
             .get(idx)
             .cloned()
-            .ok_or_else(|| Error::StateIndex)?;
+            .ok_or_else(|| Error::StateIndex)
+    }
+
+    fn get_stripped_line(&self, idx: usize) -> Result<String, Error> {
+        let s = self.get_raw_line(idx)?;
         let bytes = strip_ansi_escapes::strip(s.as_bytes());
         let str = String::from_utf8(bytes)?;
         Ok(str)
     }
 
-    fn file_in_line(&self, mut line: String) -> Option<String> {
+    // stripped line without its graph
+    fn get_content_line(&self, idx: usize) -> Result<String, Error> {
+        let line = self.get_stripped_line(idx)?;
+        let width = self.graph_widths.lock().unwrap().get(idx).copied();
+        Ok(line[width.unwrap_or(0)..].to_string())
+    }
+
+    fn file_in_line(&self, line: String) -> Option<String> {
         if self.log_style == LogStyle::OneLine {
             return None;
         }
-        if self.graph {
-            remove_graph_symbols(&mut line);
-        }
         if line.starts_with("diff --git a/") {
             if let Some((_, file)) = line.split_once(" b/") {
                 return Some(file.to_string());
@@ -250,13 +514,10 @@ impl PagerApp {
         None
     }
 
-    fn line_number_in_line(&self, mut line: String) -> Option<usize> {
+    fn line_number_in_line(&self, line: String) -> Option<usize> {
         if self.log_style == LogStyle::OneLine {
             return None;
         }
-        if self.graph {
-            remove_graph_symbols(&mut line);
-        }
         if line.starts_with("@@ -") {
             if let Some((_, line)) = line.split_once(" +") {
                 let line: String = line.chars().take_while(|c| c.is_ascii_digit()).collect();
@@ -268,10 +529,134 @@ impl PagerApp {
         None
     }
 
-    fn commit_in_line(&self, mut line: String) -> Option<String> {
-        if self.graph {
-            remove_graph_symbols(&mut line);
+    // only the changes of the working tree compared to the index can be staged
+    fn stages_hunks(&self) -> bool {
+        let args = match &self.pager_command {
+            Some(PagerCommand::Diff(args)) => args,
+            _ => return false,
+        };
+        let paths = match args.iter().position(|arg| arg == "--") {
+            Some(0) => &args[1..],
+            Some(_) => return false,
+            None => &args[..],
+        };
+        paths
+            .iter()
+            .all(|path| !path.starts_with('-') && self.original_dir.join(path).exists())
+    }
+
+    fn stage_hunk(&mut self) -> Result<(), Error> {
+        if !self.stages_hunks() {
+            let message = "hunks can only be staged from a diff of the working tree";
+            return Err(Error::Global(message.to_string()));
+        }
+        // find the file and the number of the hunk under the cursor
+        let mut idx = self.line_idx()?;
+        let mut hunk_count = 0;
+        let file = loop {
+            let line = self.get_content_line(idx)?;
+            if let Some(file) = self.file_in_line(line.clone()) {
+                break file;
+            }
+            if line.starts_with("@@ ") {
+                hunk_count += 1;
+            }
+            if idx == 0 {
+                return Err(Error::Global("no hunk under the cursor".to_string()));
+            }
+            idx -= 1;
+        };
+        if hunk_count == 0 {
+            return Err(Error::Global("no hunk under the cursor".to_string()));
+        }
+
+        let patch = git_hunk_patch(&file, hunk_count - 1, &self.state.config)?;
+        git_apply_cached(&patch, &self.state.config)?;
+        self.reload()?;
+        self.notif(NotifChannel::Echo, Some(format!("hunk of {} staged", file)));
+        Ok(())
+    }
+
+    fn jump_to_line(
+        &mut self,
+        down: bool,
+        matches: impl Fn(&Self, String) -> bool,
+    ) -> Result<(), Error> {
+        let mut idx = self.line_idx()?;
+        loop {
+            match down {
+                true => idx += 1,
+                false if idx == 0 => break,
+                false => idx -= 1,
+            }
+            let line = self
+                .get_content_line(idx)
+                .map_err(|_| Error::ReachedLastMachted)?;
+            // lines hidden by the filter are skipped
+            if self.row_of_line(idx).is_some() && matches(self, line) {
+                self.select_line(idx);
+                break;
+            }
         }
+        *self.state.list_state.offset_mut() = self.idx()?;
+        Ok(())
+    }
+
+    // select the line of the commit looked for, continuing on the next draw if not loaded yet
+    fn continue_go_to_commit(&mut self) {
+        let Some((rev, hash, mut idx)) = self.go_to_commit.take() else {
+            return;
+        };
+        loop {
+            // checked before reading, lines may be loaded in between
+            let loaded = self.loaded();
+            let Ok(line) = self.get_content_line(idx) else {
+                if loaded {
+                    let message = format!("commit {} is not in this log", rev);
+                    self.notif(NotifChannel::Error, Some(message));
+                    self.notif(NotifChannel::Search, None);
+                } else {
+                    self.go_to_commit = Some((rev, hash, idx));
+                }
+                return;
+            };
+            if let Some(commit) = self.commit_in_line(line) {
+                if (looks_like_hash(&commit) && hash.starts_with(&commit)) || commit == rev {
+                    self.select_line(idx);
+                    if let Ok(row) = self.idx() {
+                        *self.state.list_state.offset_mut() = row;
+                    }
+                    self.notif(NotifChannel::Search, None);
+                    return;
+                }
+            }
+            idx += 1;
+        }
+    }
+
+    fn go_to_commit(&mut self, rev: &Option<String>) -> Result<(), Error> {
+        let Some(rev) = rev else {
+            self.state.command_string.clear();
+            self.state.edit_cursor = 0;
+            self.state.input_state = InputState::CommitRev;
+            return Ok(());
+        };
+        let rev = rev.trim();
+        if !is_valid_git_rev(rev) {
+            return Err(Error::Global(format!("invalid revision '{}'", rev)));
+        }
+        let hash = git_commit_hash(rev, &self.state.config)?;
+        self.follow = false;
+        self.notif(
+            NotifChannel::Search,
+            Some(format!("looking for commit {}...", rev)),
+        );
+        self.go_to_commit = Some((rev.to_string(), hash, 0));
+        self.continue_go_to_commit();
+        Ok(())
+    }
+
+    fn commit_in_line(&self, line: String) -> Option<String> {
         match self.log_style {
             LogStyle::Standard => {
                 let (first, rest) = line.split_once(' ').unwrap_or(("", ""));
@@ -282,12 +667,7 @@ impl PagerApp {
                     }
                 }
             }
-            LogStyle::OneLine => {
-                // assume this is the first word
-                if let Some((commit, _)) = line.split_once(' ') {
-                    return Some(commit.to_string());
-                }
-            }
+            LogStyle::OneLine => return oneline_commit(&line).map(str::to_string),
             LogStyle::StashPager => {
                 if line.starts_with("stash@{") {
                     if let Some((commit, _)) = line.split_once(':') {
@@ -297,7 +677,8 @@ impl PagerApp {
                 return None;
             }
             LogStyle::Reflog => {
-                if line.contains("HEAD@{") {
+                // `HEAD@{n}` or `<branch>@{n}` with `git reflog show <branch>`
+                if line.contains("@{") {
                     if let Some((commit, _)) = line.split_once(' ') {
                         return Some(commit.to_string());
                     }
@@ -331,6 +712,46 @@ impl GitApp for PagerApp {
     }
 
     fn reload(&mut self) -> Result<(), Error> {
+        // the standard input cannot be read twice
+        let pager_command = match &self.pager_command {
+            Some(pager_command) => pager_command,
+            None => return Ok(()),
+        };
+        // paths given to the command are relative to the initial directory
+        let root = env::current_dir()?;
+        env::set_current_dir(&self.original_dir)?;
+        let spawned = spawn_pager_command(pager_command, self.stat, &self.state.config);
+        env::set_current_dir(root)?;
+        let (mut iterator, _) = spawned?;
+
+        let (show_cr, tab_width) = (self.state.config.show_cr, self.state.config.tab_width);
+        match first_line(&mut iterator, show_cr, tab_width) {
+            Some(first_line_ansi) => {
+                (self.lines, self.graph_widths, self.loaded, self.error) = load_lines(
+                    iterator,
+                    Some(first_line_ansi?),
+                    show_cr,
+                    tab_width,
+                    self.graph,
+                );
+                self.loading_since = Instant::now();
+                self.view_model.row_counts.clear();
+                if self.side_by_side.is_some() {
+                    self.side_by_side = Some(SideBySide::default());
+                }
+                if let Some(filter) = self.filter.take() {
+                    let regex = filter.regex().clone();
+                    self.filter = Some(LineFilter::new(regex, filter.context()));
+                }
+            }
+            None => {
+                if let Some(message) = command_error(iterator) {
+                    return Err(Error::Global(message));
+                }
+                // nothing left to display
+                self.state.quit = true;
+            }
+        }
         Ok(())
     }
 
@@ -339,32 +760,163 @@ impl GitApp for PagerApp {
     }
 
     fn get_text_line(&self, idx: usize) -> Option<String> {
-        self.get_stripped_line(idx).ok()
+        self.get_stripped_line(self.line_of_row(idx)?).ok()
+    }
+
+    fn get_copy_line(&self, idx: usize) -> Option<String> {
+        match self.state.config.copy_strip_ansi {
+            true => self.get_text_line(idx),
+            false => self.get_raw_line(self.line_of_row(idx)?).ok(),
+        }
+    }
+
+    fn get_search_line(&self, idx: usize) -> Option<String> {
+        match &self.side_by_side {
+            Some(side_by_side) => side_by_side.search_line(idx),
+            None => self.get_text_line(idx),
+        }
+    }
+
+    fn wraps_lines(&self) -> bool {
+        self.state.wrap && self.side_by_side.is_none()
     }
 
     fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        if rect.width != self.view_model.rect.width {
+            self.view_model.row_counts.clear();
+        }
         self.view_model.rect = rect;
-        let idx = self.idx().unwrap_or(0);
+        self.continue_go_to_commit();
+        let error = self.error.lock().unwrap().take();
+        if let Some(message) = error {
+            self.notif(NotifChannel::Error, Some(message));
+        }
+        let loaded = self.loaded();
+        let lines = Arc::clone(&self.lines);
+        let lines = lines.lock().unwrap();
+        let len = lines.len();
+        if let Some(side_by_side) = &mut self.side_by_side {
+            side_by_side.update(&lines, loaded, rect.width as usize);
+        }
+        if let Some(filter) = &mut self.filter {
+            filter.update(&lines);
+        }
+        if self.follow {
+            let row_count = match (&self.side_by_side, &self.filter) {
+                (Some(side_by_side), _) => side_by_side.rendered().len(),
+                (_, Some(filter)) => filter.rendered().len(),
+                _ => len,
+            };
+            self.state
+                .list_state
+                .select(Some(row_count.saturating_sub(1)));
+        }
+        let idx = self.line_idx().unwrap_or(0);
         let idx = idx.checked_add(1).unwrap_or(0);
-        let message = format!(
-            "{} - line {} of {}",
-            self.log_style,
-            idx,
-            self.lines.lock().unwrap().len(),
-        );
+        let mut message = match loaded {
+            true => format!("{} - line {} of {}", self.log_style, idx, len),
+            // the total is not known while the output streams
+            false => format!(
+                "{} - line {} of {}+ ({:.1}s)",
+                self.log_style,
+                idx,
+                len,
+                self.loading_since.elapsed().as_secs_f32()
+            ),
+        };
+        if let Some(filter) = &self.filter {
+            let count = filter.rendered().len();
+            message.push_str(&format!(
+                " [filter /{}/: {} lines]",
+                filter.pattern(),
+                count
+            ));
+        }
+        if self.follow {
+            message.push_str(" (following)");
+        }
         self.notif(NotifChannel::Line, Some(message));
-        let scroll_step = self.state.config.scroll_step;
+        frame.render_widget(Clear, rect);
+        if len == 0 && loaded {
+            self.view_model.list = PagerWidget::default();
+            frame.render_widget(Paragraph::new("No output"), rect);
+            return;
+        }
+        // rows do not match lines in the side-by-side view nor when filtered
+        let line_numbers = match self.side_by_side.is_some() || self.filter.is_some() {
+            true => LineNumbers::Hide,
+            false => self.state.config.pager_line_numbers,
+        };
+        let (gutter_rect, rect) = match line_numbers {
+            LineNumbers::Hide => (Rect::default(), rect),
+            _ => {
+                let gutter_width = len.to_string().len() as u16 + 1;
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Length(gutter_width), Constraint::Min(0)])
+                    .split(rect);
+                (chunks[0], chunks[1])
+            }
+        };
+        let scroll_step = self.state.config.mouse_scroll_step();
+        let wrap = self
+            .wraps_lines()
+            .then_some((rect.width as usize, &mut self.view_model.row_counts));
+        let word_diff = self.state.config.word_diff
+            && self.side_by_side.is_none()
+            && matches!(self.log_style, LogStyle::Diff | LogStyle::Standard);
+        let decorations = self.side_by_side.is_none()
+            && matches!(self.log_style, LogStyle::Standard | LogStyle::OneLine);
+        let rows: &[String] = match (&self.side_by_side, &self.filter) {
+            (Some(side_by_side), _) => side_by_side.rendered(),
+            (_, Some(filter)) => filter.rendered(),
+            _ => &lines,
+        };
         self.view_model.list = PagerWidget::new(
-            &self.lines.lock().unwrap(),
+            rows,
             rect.height as usize,
             &mut self.state,
             self.view_model.scroll,
             scroll_step,
+            wrap,
+            (word_diff, decorations),
         );
+        drop(lines);
         self.view_model.scroll = None;
-        frame.render_widget(Clear, rect);
+        if !gutter_rect.is_empty() {
+            let relative = line_numbers == LineNumbers::Relative;
+            self.view_model.list.render_line_numbers(
+                gutter_rect,
+                frame.buffer_mut(),
+                self.idx().unwrap_or(0),
+                relative,
+            );
+        }
         self.view_model.list.render(rect, frame.buffer_mut());
         self.highlight_search(frame, rect);
+
+        if let Some((anchor, current)) = self.state.mouse_selection {
+            if let Some((first, last)) = self.lines_between(anchor, current) {
+                let area = self.view_model.rect;
+                for row in 0..area.height {
+                    let selected = self
+                        .view_model
+                        .list
+                        .line_at_row(row as usize)
+                        .is_some_and(|idx| first <= idx && idx <= last);
+                    if selected {
+                        let row_rect = Rect {
+                            y: area.y + row,
+                            height: 1,
+                            ..area
+                        };
+                        frame
+                            .buffer_mut()
+                            .set_style(row_rect, mouse_selection_style());
+                    }
+                }
+            }
+        }
     }
 
     fn get_mapping_fields(&self) -> Vec<MappingScope> {
@@ -372,18 +924,15 @@ impl GitApp for PagerApp {
     }
 
     fn get_file_rev_line(&self) -> Result<FileRevLine, Error> {
-        let mut idx = self.idx()?;
+        let mut idx = self.line_idx()?;
         let mut file = None;
         let mut commit = None;
         let mut line_number = None;
 
         // Test if current line describes a file
         if self.log_style == LogStyle::Standard {
-            let idx = self.idx()?;
-            let mut line = self.get_stripped_line(idx).map_err(|_| Error::GitParsing)?;
-            if self.graph {
-                remove_graph_symbols(&mut line);
-            }
+            let idx = self.line_idx()?;
+            let line = self.get_content_line(idx).map_err(|_| Error::GitParsing)?;
             let stat_re =
                 Regex::new(r"^\s*(?P<file>[^|]+)\s+\|\s+(?P<changes>\d+)\s+(?P<diff>[+\-]+)")
                     .unwrap();
@@ -397,7 +946,7 @@ impl GitApp for PagerApp {
         }
 
         loop {
-            let line = self.get_stripped_line(idx).map_err(|_| Error::GitParsing)?;
+            let line = self.get_content_line(idx).map_err(|_| Error::GitParsing)?;
             if file.is_none() {
                 if let Some(line_file) = self.file_in_line(line.clone()) {
                     file = Some(line_file);
@@ -429,37 +978,38 @@ impl GitApp for PagerApp {
         action: &Action,
         terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     ) -> Result<(), Error> {
+        if action.is_motion() {
+            self.follow = false;
+        }
+        // stop looking for a commit in case there is a new action
+        if self.go_to_commit.take().is_some() {
+            self.notif(NotifChannel::Search, None);
+        }
         match action {
-            Action::PagerNextCommit => {
-                let mut idx = self.idx()? + 1;
-                loop {
-                    let line = self
-                        .get_stripped_line(idx)
-                        .map_err(|_| Error::ReachedLastMachted)?;
-                    if self.commit_in_line(line).is_some() {
-                        self.state.list_state.select(Some(idx));
-                        break;
-                    }
-                    idx += 1;
-                }
-                *self.state.list_state.offset_mut() = self.idx()?;
+            Action::GoToCommit(rev) => self.go_to_commit(rev)?,
+            Action::PagerNextCommit | Action::PreviousCommit => {
+                let down = *action == Action::PagerNextCommit;
+                self.jump_to_line(down, |app, line| app.commit_in_line(line).is_some())?;
             }
-            Action::PreviousCommit => {
-                let mut idx = self.idx()?;
-                loop {
-                    if idx == 0 {
-                        break;
-                    }
-                    idx -= 1;
-                    let line = self
-                        .get_stripped_line(idx)
-                        .map_err(|_| Error::ReachedLastMachted)?;
-                    if self.commit_in_line(line).is_some() {
-                        self.state.list_state.select(Some(idx));
-                        break;
-                    }
+            Action::NextHunk | Action::PreviousHunk => {
+                let down = *action == Action::NextHunk;
+                self.jump_to_line(down, |app, line| app.line_number_in_line(line).is_some())?;
+            }
+            Action::StageHunk => self.stage_hunk()?,
+            Action::ToggleWrap => self.state.wrap = !self.state.wrap,
+            Action::Follow => self.follow = !self.follow,
+            Action::ToggleSideBySide => self.toggle_side_by_side()?,
+            Action::FilterLines(pattern) => self.filter_lines(pattern)?,
+            Action::ClearFilter => self.clear_filter(),
+            Action::ToggleStat => {
+                if self.pager_command.is_none() {
+                    let message = "the standard input cannot be run again";
+                    return Err(Error::Global(message.to_string()));
                 }
-                *self.state.list_state.offset_mut() = self.idx()?;
+                self.stat = !self.stat;
+                self.reload()?;
+                self.state.list_state.select_first();
+                *self.state.list_state.offset_mut() = 0;
             }
             action => {
                 self.run_action_generic(action, self.view_model.rect.height as usize, terminal)?;
@@ -474,16 +1024,248 @@ impl GitApp for PagerApp {
     }
 
     fn on_scroll(&mut self, down: bool) {
+        self.follow = false;
         self.view_model.scroll = Some(down);
     }
 
+    fn lines_between(&self, anchor: Position, current: Position) -> Option<(usize, usize)> {
+        let rect = self.view_model.rect;
+        if rect.is_empty() {
+            return None;
+        }
+        // positions out of the view are clamped, and rows past the end give the last line
+        let line_at = |position: Position| {
+            let row = position.y.clamp(rect.y, rect.bottom() - 1) - rect.y;
+            (0..=row as usize)
+                .rev()
+                .find_map(|row| self.view_model.list.line_at_row(row))
+        };
+        let (a, b) = (line_at(anchor)?, line_at(current)?);
+        Some((a.min(b), a.max(b)))
+    }
+
     fn on_click(&mut self) {
         let rect = self.view_model.rect;
         if rect.contains(self.state.mouse_position) {
             let delta = (self.state.mouse_position.y - rect.y) as usize;
-            self.state
-                .list_state
-                .select(Some(self.state.list_state.offset() + delta));
+            if let Some(idx) = self.view_model.list.line_at_row(delta) {
+                self.state.list_state.select(Some(idx));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // commit found on each line once the graph is skipped
+    fn oneline_commits(log: &str) -> Vec<Option<&str>> {
+        let mut region = 0;
+        log.lines()
+            .map(|line| oneline_commit(&line[graph_width(line, &mut region)..]))
+            .collect()
+    }
+
+    #[test]
+    fn oneline_graph_with_merges_and_decorations() {
+        let log = "\
+*   c182c05 (HEAD -> main) Merge branch 'side' into main
+|\\  
+| * 41d34fc (side) side work
+* |   7f5fb46 (tag: v1.0) Merge branch 'hotfix'
+|\\ \\  
+| * | 627ffb0 (hotfix) hot (fix)
+| |/  
+* |   29795ec Merge branch 'feature'
+|\\ \\  
+| |/  
+|/|   
+| * fdb258b (feature, origin/feature) feat two
+| * 5aa2cb2 feat one
+* | a755abc main fix
+|/  
+* 66f218a init
+";
+        assert_eq!(
+            oneline_commits(log),
+            vec![
+                Some("c182c05"),
+                None,
+                Some("41d34fc"),
+                Some("7f5fb46"),
+                None,
+                Some("627ffb0"),
+                None,
+                Some("29795ec"),
+                None,
+                None,
+                None,
+                Some("fdb258b"),
+                Some("5aa2cb2"),
+                Some("a755abc"),
+                None,
+                Some("66f218a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn oneline_without_graph() {
+        let log = "\
+c182c05 (HEAD -> main, tag: v1.0) Merge branch 'side' into main
+41d34fc (side) side work
+66f218a init
+";
+        assert_eq!(
+            oneline_commits(log),
+            vec![Some("c182c05"), Some("41d34fc"), Some("66f218a")]
+        );
+    }
+
+    #[test]
+    fn decorations_and_words_are_not_hashes() {
+        for word in [
+            "(HEAD",
+            "main)",
+            "(tag:",
+            "abc",
+            "v1.0",
+            "feed-beef",
+            "g1234567",
+            "",
+        ] {
+            assert!(!looks_like_hash(word), "{}", word);
+        }
+        for word in [
+            "abcd",
+            "c182c05",
+            "0123456789abcdef0123456789abcdef01234567",
+        ] {
+            assert!(looks_like_hash(word), "{}", word);
         }
     }
+
+    // lines once the graph is skipped
+    fn contents(log: &str) -> Vec<&str> {
+        let mut region = 0;
+        log.lines()
+            .map(|line| &line[graph_width(line, &mut region)..])
+            .collect()
+    }
+
+    #[test]
+    fn graph_with_parallel_lanes() {
+        let log = "\
+*   commit c182c05
+|\\      Merge branch 'side' into main
+| * commit 41d34fc
+| |     side work
+* |   commit 7f5fb46
+|\\ \\      Merge branch 'hotfix'
+| * | commit 627ffb0
+| |/      hot (fix)
+* |   commit 29795ec
+|\\ \\      Merge branch 'feature'
+| |/  
+|/|   
+| * commit fdb258b
+| |     feat two
+| * commit 5aa2cb2
+| |     feat one
+* | commit a755abc
+|/      main fix
+* commit 66f218a
+      init
+";
+        assert_eq!(
+            contents(log),
+            vec![
+                "commit c182c05",
+                "    Merge branch 'side' into main",
+                "commit 41d34fc",
+                "    side work",
+                "commit 7f5fb46",
+                "    Merge branch 'hotfix'",
+                "commit 627ffb0",
+                "    hot (fix)",
+                "commit 29795ec",
+                "    Merge branch 'feature'",
+                "",
+                "",
+                "commit fdb258b",
+                "    feat two",
+                "commit 5aa2cb2",
+                "    feat one",
+                "commit a755abc",
+                "    main fix",
+                "commit 66f218a",
+                "    init",
+            ]
+        );
+    }
+
+    #[test]
+    fn graph_of_an_octopus_merge() {
+        let log = "\
+*---.   commit fd7d858
+|\\ \\ \\      Octopus merge
+| | | * commit 7bd6a0f
+| | | |     more c
+| | | * commit e9ee57c
+| | | |     work c
+| | * | commit 031bb13
+| | | |     more b
+| | * | commit f043f43
+| | |/      work b
+| * | commit 5dc822f
+| | |     more a
+| * | commit 464ab4e
+| |/      work a
+* / commit b886ae5
+|/      main work
+* commit 7e4b272
+      init
+";
+        assert_eq!(
+            contents(log),
+            vec![
+                "commit fd7d858",
+                "    Octopus merge",
+                "commit 7bd6a0f",
+                "    more c",
+                "commit e9ee57c",
+                "    work c",
+                "commit 031bb13",
+                "    more b",
+                "commit f043f43",
+                "    work b",
+                "commit 5dc822f",
+                "    more a",
+                "commit 464ab4e",
+                "    work a",
+                "commit b886ae5",
+                "    main work",
+                "commit 7e4b272",
+                "    init",
+            ]
+        );
+    }
+
+    #[test]
+    fn content_looking_like_a_graph_is_kept() {
+        let log = "\
+* commit 66f218a
+|     | a table | in the message |
+|     * a list
+";
+        assert_eq!(
+            contents(log),
+            vec![
+                "commit 66f218a",
+                "    | a table | in the message |",
+                "    * a list"
+            ]
+        );
+    }
 }
@@ -0,0 +1,199 @@
+use crate::app::{FileRevLine, GitApp};
+
+use crate::model::{
+    action::{Action, CommandType},
+    app_state::AppState,
+    config::MappingScope,
+    editor::shell_quote,
+    errors::Error,
+    git::{git_current_branch, git_remote_output, Remote},
+};
+use crate::ui::utils::highlight_style;
+
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, Paragraph, StatefulWidget},
+    Frame, Terminal,
+};
+
+struct RemoteAppViewModel {
+    remote_list: List<'static>,
+    rect: Rect,
+}
+
+pub struct RemoteApp {
+    state: AppState,
+    remotes: Vec<Remote>,
+    view_model: RemoteAppViewModel,
+}
+
+impl RemoteApp {
+    pub fn new() -> Result<Self, Error> {
+        let state = AppState::new()?;
+        let mut r = Self {
+            state,
+            remotes: Vec::new(),
+            view_model: RemoteAppViewModel {
+                remote_list: List::default(),
+                rect: Rect::default(),
+            },
+        };
+        r.reload()?;
+        r.state.select_default();
+        Ok(r)
+    }
+
+    fn get_remote(&self) -> Result<&Remote, Error> {
+        self.remotes
+            .get(self.idx()?)
+            .ok_or_else(|| Error::StateIndex)
+    }
+}
+
+impl GitApp for RemoteApp {
+    fn state(&mut self) -> &mut AppState {
+        &mut self.state
+    }
+
+    fn get_state(&self) -> &AppState {
+        &self.state
+    }
+
+    fn reload(&mut self) -> Result<(), Error> {
+        let output = git_remote_output(&self.state.config)?;
+        let mut remotes: Vec<Remote> = Vec::new();
+        // each remote is listed twice, as `<name>\t<url> (fetch)` and `<name>\t<url> (push)`
+        for line in output.lines() {
+            let (name, rest) = line.split_once('\t').ok_or_else(|| Error::GitParsing)?;
+            let (url, kind) = rest.rsplit_once(' ').unwrap_or((rest, ""));
+            let idx = match remotes.iter().position(|remote| remote.name == name) {
+                Some(idx) => idx,
+                None => {
+                    remotes.push(Remote {
+                        name: name.to_string(),
+                        fetch_url: String::new(),
+                        push_url: String::new(),
+                    });
+                    remotes.len() - 1
+                }
+            };
+            match kind {
+                "(push)" => remotes[idx].push_url = url.to_string(),
+                _ => remotes[idx].fetch_url = url.to_string(),
+            }
+        }
+        self.remotes = remotes;
+
+        let name_width = self
+            .remotes
+            .iter()
+            .map(|remote| remote.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let list_items: Vec<Line> = self
+            .remotes
+            .iter()
+            .map(|remote| {
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{:width$} ", remote.name, width = name_width),
+                        Style::from(Color::Yellow).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(remote.fetch_url.clone(), Style::from(Color::White)),
+                ];
+                if remote.push_url != remote.fetch_url {
+                    spans.push(Span::styled(
+                        format!(" (push: {})", remote.push_url),
+                        Style::from(Color::DarkGray),
+                    ));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        self.view_model.remote_list = List::new(list_items)
+            .highlight_style(highlight_style())
+            .scroll_padding(self.state.config.scrolloff);
+        Ok(())
+    }
+
+    fn get_text_line(&self, idx: usize) -> Option<String> {
+        self.remotes
+            .get(idx)
+            .map(|remote| format!("{} {}", remote.name, remote.fetch_url))
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        self.view_model.rect = rect;
+        if self.remotes.is_empty() {
+            let paragraph =
+                Paragraph::new("No remotes yet, add one with `git remote add <name> <url>`");
+            frame.render_widget(paragraph, rect);
+            return;
+        }
+        StatefulWidget::render(
+            &self.view_model.remote_list,
+            rect,
+            frame.buffer_mut(),
+            &mut self.state.list_state,
+        );
+
+        self.highlight_search(frame, rect);
+    }
+
+    fn get_mapping_fields(&self) -> Vec<MappingScope> {
+        vec![MappingScope::Remote]
+    }
+
+    fn get_file_rev_line(&self) -> Result<FileRevLine, Error> {
+        Ok((None, Some(self.get_remote()?.name.clone()), None))
+    }
+
+    fn run_action(
+        &mut self,
+        action: &Action,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> Result<(), Error> {
+        match action {
+            Action::RemoteFetch | Action::RemotePull | Action::RemotePush => {
+                let remote = shell_quote(&self.get_remote()?.name);
+                let command = match action {
+                    Action::RemoteFetch => format!("%(git) fetch {}", remote),
+                    _ => {
+                        let branch = git_current_branch(&self.state.config)?;
+                        let command = match action {
+                            Action::RemotePull => "pull",
+                            _ => "push",
+                        };
+                        format!("%(git) {} {} {}", command, remote, shell_quote(&branch))
+                    }
+                };
+                // synchronous, the progress of the command is displayed
+                self.run_command(terminal, &CommandType::Sync, command, None, None, None)?;
+            }
+            _ => {
+                self.run_action_generic(action, self.view_model.rect.height as usize, terminal)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_click(&mut self) {
+        if self.view_model.rect.contains(self.state.mouse_position) {
+            let delta = (self.state.mouse_position.y - self.view_model.rect.y) as usize;
+            let idx =
+                (self.state.list_state.offset() + delta).min(self.remotes.len().saturating_sub(1));
+            self.state.list_state.select(Some(idx));
+        }
+    }
+
+    fn on_scroll(&mut self, down: bool) {
+        self.on_scroll_generic(
+            down,
+            self.view_model.rect.height as usize,
+            self.remotes.len(),
+        );
+    }
+}
@@ -2,66 +2,268 @@ use crate::app::{FileRevLine, GitApp};
 
 use crate::model::{
     action::Action,
-    app_state::AppState,
-    config::MappingScope,
+    app_state::{AppState, NotifChannel},
+    clipboard::copy_to_clipboard,
+    config::{MappingScope, StatusColors},
     errors::Error,
-    git::{git_parse_commit, git_show_output, set_git_dir, Commit, FileStatus},
+    git::{
+        git_child_error, git_numstat, git_parse_commit, git_show_output, set_git_dir, Commit,
+        FileStatus,
+    },
 };
+use crate::ui::pager_widget::wrap_line;
 
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
     style::Color,
     style::{Modifier, Style},
-    text::{Line, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph, StatefulWidget, Widget},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
     Frame, Terminal,
 };
 
+use std::collections::HashMap;
 use std::env;
+use std::io::BufRead as _;
+use unicode_width::UnicodeWidthStr;
 
 struct ShowAppViewModel {
-    file_list: List<'static>,
-    commit_paragraph: Paragraph<'static>,
+    commit_lines: Vec<Line<'static>>,
+    metadata_rect: Rect,
+    // number of rows of the wrapped commit message
+    metadata_len: usize,
     files_rect: Rect,
 }
 
+// lines changed in each file, displayed after it as with `git show --stat`
+struct Diffstat {
+    // none for binary files
+    stats: HashMap<String, Option<(usize, usize)>>,
+    // the bars are scaled to the most changed file
+    max_changes: usize,
+    // the bars are aligned after the widest file row
+    label_width: usize,
+}
+
 pub struct ShowApp {
     state: AppState,
     commit: Commit,
     original_dir: std::path::PathBuf,
+    metadata_focused: bool,
+    metadata_scroll: usize,
+    diffstat: Option<Diffstat>,
     view_model: ShowAppViewModel,
 }
 
 impl ShowApp {
     pub fn new(revision: Option<String>) -> Result<Self, Error> {
-        let mut state = AppState::new()?;
-        let original_dir = env::current_dir()?;
-        set_git_dir(&state.config)?;
-
-        let output = git_show_output(&revision, &state.config)?;
-        let mut commit = git_parse_commit(&output)?;
+        let state = AppState::new()?;
+        let (output, child) = git_show_output(&revision, &state.config)?;
+        let mut lines = output.lines().map_while(Result::ok);
+        let commit = git_parse_commit(lines.by_ref());
+        // the rest is read for git to exit, the parsing stopping at the first unknown line
+        lines.for_each(drop);
+        if let Some(message) = git_child_error(child) {
+            return Err(Error::Global(message));
+        }
+        let mut commit = commit?;
         commit
             .files
             .sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        // last, a failure above leaving the working directory unchanged
+        let original_dir = set_git_dir(&state.config)?;
+        Self::with_commit(state, commit, original_dir)
+    }
 
-        state.list_state.select_first();
+    fn with_commit(
+        mut state: AppState,
+        commit: Commit,
+        original_dir: std::path::PathBuf,
+    ) -> Result<Self, Error> {
+        state.select_default();
 
         let mut r = Self {
             state,
             commit,
             original_dir,
+            metadata_focused: false,
+            metadata_scroll: 0,
+            diffstat: None,
             view_model: ShowAppViewModel {
-                file_list: List::default(),
-                commit_paragraph: Paragraph::default(),
+                commit_lines: Vec::new(),
+                metadata_rect: Rect::default(),
+                metadata_len: 0,
                 files_rect: Rect::default(),
             },
         };
+        if r.state.config.show_diffstat {
+            r.toggle_diffstat()?;
+        }
         r.reload()?;
         Ok(r)
     }
 
-    fn display_commit_metadata<'b>(metadata: String) -> Paragraph<'b> {
+    // renamed files are displayed as `old -> new`
+    fn file_label(&self, name: &str) -> String {
+        match self.commit.renames.get(name) {
+            Some(orig_path) => format!("{} -> {}", orig_path, name),
+            None => name.to_string(),
+        }
+    }
+
+    fn scroll_metadata(&mut self, delta: isize) {
+        let height = self.view_model.metadata_rect.height as usize;
+        let max_scroll = self.view_model.metadata_len.saturating_sub(height);
+        self.metadata_scroll = self
+            .metadata_scroll
+            .saturating_add_signed(delta)
+            .min(max_scroll);
+    }
+
+    fn toggle_diffstat(&mut self) -> Result<(), Error> {
+        if self.diffstat.take().is_some() {
+            return Ok(());
+        }
+        let stats = git_numstat(&self.commit.hash, &self.state.config)?;
+        let max_changes = stats
+            .values()
+            .flatten()
+            .map(|(added, deleted)| added + deleted)
+            .max()
+            .unwrap_or(0);
+        let label_width = self
+            .commit
+            .files
+            .iter()
+            .map(|(status, name)| Self::file_label_row(status, &self.file_label(name)).width())
+            .max()
+            .unwrap_or(0);
+        self.diffstat = Some(Diffstat {
+            stats,
+            max_changes,
+            label_width,
+        });
+        Ok(())
+    }
+
+    fn file_label_row(status: &FileStatus, label: &str) -> String {
+        format!("{} {}", status.character(), label)
+    }
+
+    fn file_item(
+        &self,
+        status: &FileStatus,
+        name: &str,
+        colors: &StatusColors,
+        width: usize,
+    ) -> ListItem<'static> {
+        let label = Self::file_label_row(status, &self.file_label(name));
+        let style = Style::from(colors.file(status));
+        let Some(diffstat) = &self.diffstat else {
+            return ListItem::new(label).style(style);
+        };
+        // the file names take at most half of the width
+        let label_width = diffstat.label_width.min(width / 2);
+        let padding = " ".repeat(label_width.saturating_sub(label.width()));
+        let mut spans = vec![Span::styled(label, style), Span::raw(padding)];
+        let count_width = diffstat.max_changes.to_string().len().max(3);
+        match diffstat.stats.get(name) {
+            Some(Some((added, deleted))) => {
+                spans.push(Span::raw(format!(" | {:>count_width$} ", added + deleted)));
+                // scaled down when the most changed file does not fit, as git does
+                let bar_width = width.saturating_sub(label_width + count_width + 4);
+                let scale = |count: usize| match diffstat.max_changes > bar_width && count > 0 {
+                    true => (count * bar_width / diffstat.max_changes).max(1),
+                    false => count,
+                };
+                spans.push(Span::styled("+".repeat(scale(*added)), Color::Green));
+                spans.push(Span::styled("-".repeat(scale(*deleted)), Color::Red));
+            }
+            Some(None) => spans.push(Span::raw(format!(" | {:>count_width$}", "Bin"))),
+            None => (),
+        }
+        ListItem::new(Line::from(spans))
+    }
+
+    fn draw_files(&mut self, frame: &mut Frame, rect: Rect) {
+        let files = &self.commit.files;
+        if files.is_empty() {
+            return;
+        }
+        // only build the items around the displayed ones, commits may touch thousands of files
+        let height = rect.height as usize;
+        let selected = self
+            .state
+            .list_state
+            .selected()
+            .unwrap_or(0)
+            .min(files.len() - 1);
+        let offset = self.state.list_state.offset().min(files.len() - 1);
+        let start = offset.min(selected).saturating_sub(height);
+        let end = (offset.max(selected) + 2 * height).min(files.len());
+
+        let file_items: Vec<ListItem> = files[start..end]
+            .iter()
+            .map(|(status, name)| {
+                self.file_item(status, name, &self.state.config.colors, rect.width as usize)
+            })
+            .collect();
+        // the selection is only displayed in the focused region
+        let highlight_style = match self.metadata_focused {
+            true => Style::new(),
+            false => Style::new().add_modifier(Modifier::REVERSED),
+        };
+        let file_list = List::new(file_items)
+            .block(Block::default().borders(Borders::NONE))
+            .style(Style::from(Color::White))
+            .highlight_style(highlight_style)
+            .scroll_padding(self.state.config.scrolloff);
+
+        let mut window_state = ListState::default()
+            .with_offset(offset - start)
+            .with_selected(Some(selected - start));
+        StatefulWidget::render(&file_list, rect, frame.buffer_mut(), &mut window_state);
+
+        *self.state.list_state.offset_mut() = start + window_state.offset();
+        self.state
+            .list_state
+            .select(window_state.selected().map(|idx| start + idx));
+    }
+
+    // message of the commit without the headers before it and the indentation added by git
+    fn message(&self) -> String {
+        let lines: Vec<&str> = self
+            .commit
+            .metadata
+            .lines()
+            .skip_while(|line| !line.is_empty())
+            .skip(1)
+            .take_while(|line| line.is_empty() || line.starts_with("    "))
+            .map(|line| line.strip_prefix("    ").unwrap_or(line))
+            .collect();
+        lines.join("\n").trim().to_string()
+    }
+
+    fn copy_message(&mut self, subject_only: bool) -> Result<(), Error> {
+        let message = self.message();
+        let (text, copied) = match subject_only {
+            true => (
+                message.lines().next().unwrap_or_default().to_string(),
+                "subject",
+            ),
+            false => (message, "message"),
+        };
+        if text.is_empty() {
+            return Err(Error::Global("the commit has no message".to_string()));
+        }
+        copy_to_clipboard(&text, &self.state.config)?;
+        let message = format!("copied the {} of {}", copied, self.commit.hash);
+        self.notif(NotifChannel::Echo, Some(message));
+        Ok(())
+    }
+
+    fn display_commit_metadata(metadata: String) -> Vec<Line<'static>> {
         let mut lines = metadata.lines();
 
         let mut styled_lines: Vec<Line<'static>> = Vec::new();
@@ -82,7 +284,7 @@ impl ShowApp {
             ));
         }
 
-        Paragraph::new(Text::from(styled_lines))
+        styled_lines
     }
 }
 
@@ -96,35 +298,15 @@ impl GitApp for ShowApp {
     }
 
     fn reload(&mut self) -> Result<(), Error> {
-        let file_items: Vec<ListItem> = self
-            .commit
-            .files
-            .iter()
-            .map(|(status, name)| {
-                let label = format!("{} {}", status.character(), name);
-                let color = match status {
-                    FileStatus::New => Color::Green,
-                    FileStatus::Deleted => Color::Red,
-                    FileStatus::Modified => Color::LightBlue,
-                    _ => Color::default(),
-                };
-                ListItem::new(label).style(Style::from(color))
-            })
-            .collect();
-
-        self.view_model.file_list = List::new(file_items)
-            .block(Block::default().borders(Borders::NONE))
-            .style(Style::from(Color::White))
-            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
-            .scroll_padding(self.state.config.scrolloff);
-
-        let metadata = Self::display_commit_metadata(self.commit.metadata.clone());
-        self.view_model.commit_paragraph = metadata.block(Block::default().borders(Borders::NONE));
+        self.view_model.commit_lines = Self::display_commit_metadata(self.commit.metadata.clone());
         Ok(())
     }
 
     fn get_text_line(&self, idx: usize) -> Option<String> {
-        self.commit.files.get(idx).map(|tuple| tuple.1.clone())
+        self.commit
+            .files
+            .get(idx)
+            .map(|(_, name)| self.file_label(name))
     }
 
     fn on_exit(&mut self) -> Result<(), Error> {
@@ -133,23 +315,34 @@ impl GitApp for ShowApp {
     }
 
     fn draw(&mut self, frame: &mut Frame, rect: Rect) {
-        let paragraph_len = self.commit.metadata.lines().count() + 1;
+        // long lines of the message are wrapped, tall messages take at most half of the view
+        let wrapped: Vec<Line> = self
+            .view_model
+            .commit_lines
+            .iter()
+            .flat_map(|line| wrap_line(line.clone(), rect.width as usize))
+            .collect();
+        self.view_model.metadata_len = wrapped.len();
+        let paragraph_len = (self.view_model.metadata_len + 1)
+            .min(rect.height as usize / 2)
+            .min(rect.height.saturating_sub(5) as usize);
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(paragraph_len as u16), Constraint::Min(5)])
             .split(rect);
 
+        // the last row separates the message from the files
+        self.view_model.metadata_rect = Rect {
+            height: chunks[0].height.saturating_sub(1),
+            ..chunks[0]
+        };
+        self.scroll_metadata(0);
         Widget::render(
-            &self.view_model.commit_paragraph,
-            chunks[0],
+            Paragraph::new(wrapped).scroll((self.metadata_scroll as u16, 0)),
+            self.view_model.metadata_rect,
             frame.buffer_mut(),
         );
-        StatefulWidget::render(
-            &self.view_model.file_list,
-            chunks[1],
-            frame.buffer_mut(),
-            &mut self.state.list_state,
-        );
+        self.draw_files(frame, chunks[1]);
         self.view_model.files_rect = chunks[1];
 
         self.highlight_search(
@@ -157,7 +350,7 @@ impl GitApp for ShowApp {
             Rect {
                 x: rect.x + chunks[1].x + 2,
                 y: chunks[1].y,
-                width: chunks[1].width - 1,
+                width: chunks[1].width.saturating_sub(1),
                 height: chunks[1].height,
             },
         );
@@ -188,16 +381,45 @@ impl GitApp for ShowApp {
         action: &Action,
         terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     ) -> Result<(), Error> {
-        self.run_action_generic(action, self.view_model.files_rect.height as usize, terminal)?;
+        let height = self.view_model.metadata_rect.height.max(1) as isize;
+        match action {
+            Action::FocusMetadata => self.metadata_focused = true,
+            Action::FocusFiles => self.metadata_focused = false,
+            Action::CopyMessage => self.copy_message(false)?,
+            Action::CopySubject => self.copy_message(true)?,
+            Action::ToggleDiffstat => self.toggle_diffstat()?,
+            // navigation scrolls the commit message when it is focused
+            Action::Up if self.metadata_focused => self.scroll_metadata(-1),
+            Action::Down if self.metadata_focused => self.scroll_metadata(1),
+            Action::HalfPageUp if self.metadata_focused => self.scroll_metadata(-height / 2),
+            Action::HalfPageDown if self.metadata_focused => self.scroll_metadata(height / 2),
+            Action::First if self.metadata_focused => self.metadata_scroll = 0,
+            Action::Last if self.metadata_focused => self.scroll_metadata(isize::MAX),
+            _ => {
+                self.run_action_generic(
+                    action,
+                    self.view_model.files_rect.height as usize,
+                    terminal,
+                )?;
+            }
+        }
         Ok(())
     }
 
     fn on_click(&mut self) {
+        if self
+            .view_model
+            .metadata_rect
+            .contains(self.state.mouse_position)
+        {
+            self.metadata_focused = true;
+        }
         if self
             .view_model
             .files_rect
             .contains(self.state.mouse_position)
         {
+            self.metadata_focused = false;
             let delta = (self.state.mouse_position.y - self.view_model.files_rect.y) as usize;
             self.state
                 .list_state
@@ -206,6 +428,15 @@ impl GitApp for ShowApp {
     }
 
     fn on_scroll(&mut self, down: bool) {
+        if self
+            .view_model
+            .metadata_rect
+            .contains(self.state.mouse_position)
+        {
+            let step = self.state.config.mouse_scroll_step() as isize;
+            self.scroll_metadata(if down { step } else { -step });
+            return;
+        }
         self.on_scroll_generic(
             down,
             self.view_model.files_rect.height as usize,
@@ -213,3 +444,55 @@ impl GitApp for ShowApp {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{config::Config, history::History};
+    use ratatui::backend::TestBackend;
+
+    // refactor commit touching `len` files
+    fn test_app(len: usize) -> ShowApp {
+        let commit = Commit {
+            metadata: "commit abcdef\nAuthor: author <author@mail>\n\n    refactor\n".to_string(),
+            files: (0..len)
+                .map(|idx| (FileStatus::Modified, format!("src/file{:06}.rs", idx)))
+                .collect(),
+            renames: HashMap::new(),
+            hash: "abcdef".to_string(),
+        };
+        let state = AppState::with_config(Config::default(), History::default());
+        ShowApp::with_commit(state, commit, env::current_dir().unwrap()).unwrap()
+    }
+
+    fn screen(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn every_file_of_a_huge_commit_can_be_selected() {
+        let mut app = test_app(100_000);
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        for idx in [99_999, 50_000, 0, 12_345] {
+            app.state.list_state.select(Some(idx));
+            terminal
+                .draw(|frame| app.draw(frame, frame.area()))
+                .unwrap();
+            assert_eq!(app.state.list_state.selected(), Some(idx));
+            assert!(screen(&terminal).contains(&format!("src/file{:06}.rs", idx)));
+        }
+    }
+
+    #[test]
+    fn search_covers_every_file_of_a_huge_commit() {
+        let mut app = test_app(100_000);
+        app.state.search_string = "file099998".to_string();
+        app.search_result(false).unwrap();
+        assert_eq!(app.state.list_state.selected(), Some(99_998));
+        assert!(app.search_result(false).is_err());
+        app.state.search_string = "file000001".to_string();
+        app.search_result(true).unwrap();
+        assert_eq!(app.state.list_state.selected(), Some(1));
+    }
+}
@@ -1,13 +1,15 @@
 use crate::app::{FileRevLine, GitApp};
 
 use crate::model::{
-    action::Action,
-    app_state::AppState,
+    action::{Action, CommandType},
+    app_state::{AppState, InputState, NotifChannel},
     config::MappingScope,
     errors::Error,
-    git::{git_stash_output, Stash},
+    git::{git_stash_output, git_stash_push, Stash},
 };
-use crate::ui::utils::{date_to_color, highlight_style};
+use crate::ui::utils::{date_to_color, format_date, highlight_style};
+
+use chrono::DateTime;
 
 use ratatui::{
     backend::CrosstermBackend,
@@ -43,7 +45,7 @@ impl StashApp {
             },
         };
         r.reload()?;
-        r.state.list_state.select_first();
+        r.state.select_default();
         Ok(r)
     }
 }
@@ -62,22 +64,33 @@ impl GitApp for StashApp {
         self.stashes = output
             .lines()
             .map(|line| {
-                let (full_date, title) = line.split_once('\t').ok_or_else(|| Error::GitParsing)?;
-                let (date, _) = full_date.split_once(' ').ok_or_else(|| Error::GitParsing)?;
+                let (date, title) = line.split_once('\t').ok_or_else(|| Error::GitParsing)?;
+                let time = DateTime::parse_from_rfc3339(date).map_err(|_| Error::GitParsing)?;
                 let stash = Stash {
                     title: title.to_string(),
-                    date: date.to_string(),
+                    date: format_date(&time, &self.state.config.date_format),
+                    time,
                 };
                 Ok(stash)
             })
             .collect::<Result<Vec<Stash>, Error>>()?;
 
+        // relative dates differ in length
+        let date_len = self
+            .stashes
+            .iter()
+            .map(|stash| stash.date.chars().count())
+            .max()
+            .unwrap_or(0);
         let list_items: Vec<Line> = self
             .stashes
             .iter()
             .map(|stash| {
                 let spans = vec![
-                    Span::styled(stash.date.clone(), Style::from(date_to_color(&stash.date))),
+                    Span::styled(
+                        format!("{:<date_len$}", stash.date),
+                        Style::from(date_to_color(&stash.time)),
+                    ),
                     Span::raw(" "),
                     Span::styled(stash.title.clone(), Style::from(Color::White)),
                 ];
@@ -128,7 +141,46 @@ impl GitApp for StashApp {
         action: &Action,
         terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     ) -> Result<(), Error> {
-        self.run_action_generic(action, self.view_model.height, terminal)?;
+        match action {
+            Action::StashPush(message) | Action::StashPushIncludeUntracked(message) => {
+                let include_untracked = matches!(action, Action::StashPushIncludeUntracked(_));
+                let message = match message {
+                    Some(message) => message,
+                    None => {
+                        self.state.command_string.clear();
+                        self.state.edit_cursor = 0;
+                        self.state.input_state = InputState::StashMessage { include_untracked };
+                        return Ok(());
+                    }
+                };
+                let count = self.stashes.len();
+                git_stash_push(message, include_untracked, &self.state.config)?;
+                self.reload()?;
+                if self.stashes.len() == count {
+                    return Err(Error::Global("no local changes to save".to_string()));
+                }
+                self.state.list_state.select_first();
+                let message = format!("saved stash@{{0}}: {}", self.stashes[0].title);
+                self.notif(NotifChannel::Echo, Some(message));
+            }
+            Action::StashPop | Action::StashApply | Action::StashDrop => {
+                let command = match action {
+                    Action::StashPop => "%(git) stash pop %(rev)",
+                    Action::StashApply => "%(git) stash apply %(rev)",
+                    _ => "%(git) stash drop %(rev)",
+                };
+                let (_, rev, _) = self.get_file_rev_line()?;
+                self.run_command(
+                    terminal,
+                    &CommandType::Sync,
+                    command.to_string(),
+                    None,
+                    rev,
+                    None,
+                )?;
+            }
+            _ => self.run_action_generic(action, self.view_model.height, terminal)?,
+        }
         Ok(())
     }
 
@@ -1,27 +1,69 @@
 use crate::app::{FileRevLine, GitApp};
-use crate::model::action::Action;
-use crate::model::app_state::AppState;
+use crate::model::action::{Action, CommandType};
+use crate::model::app_state::{AppState, InputState, MultiLinePrompt, NotifChannel};
 use crate::model::config::{Config, MappingScope};
+use crate::model::editor::editor_command;
 use crate::model::errors::Error;
-use crate::model::git::{git_add_restore, git_status_output, FileStatus, GitFile, StagedStatus};
+use crate::model::git::{
+    git_add_restore, git_ahead_behind, git_clean, git_commit, git_current_branch, git_diff_output,
+    git_discard, git_head_message, git_mark_resolved, git_status_output, set_git_dir, FileStatus,
+    GitFile, StagedStatus,
+};
+use crate::ui::utils::clean_buggy_characters;
+use crate::views::pager::{PagerApp, PagerCommand};
 
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     prelude::CrosstermBackend,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph, StatefulWidget},
 };
 use ratatui::{Frame, Terminal};
 use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ansi_to_tui::IntoText as _;
+use regex::Regex;
+
+// time the selection has to stay on a file before its diff is computed
+const PREVIEW_DELAY: Duration = Duration::from_millis(150);
+
+// file whose diff is previewed, as it appears in one of the tables
+type PreviewKey = Option<(StagedStatus, String)>;
+
+// compared with untracked files to preview their whole content
+const NULL_DEVICE: &str = if cfg!(windows) { "NUL" } else { "/dev/null" };
+
+fn preview_text(args: &[String], config: &Config) -> Text<'static> {
+    match git_diff_output(args, config) {
+        Ok(output) => {
+            let output: Vec<String> = output
+                .lines()
+                .map(|line| clean_buggy_characters(line, config.show_cr, config.tab_width))
+                .collect();
+            output.join("\n").as_bytes().into_text().unwrap_or_default()
+        }
+        Err(err) => Text::from(err.to_string()),
+    }
+}
 
 fn compute_tables(
     files: &HashMap<String, GitFile>,
+    filter: Option<FileStatus>,
     unstaged_table: &mut Vec<(FileStatus, String)>,
     staged_table: &mut Vec<(FileStatus, String)>,
 ) {
+    let keep = |status: FileStatus| {
+        status != FileStatus::None && (filter.is_none() || filter == Some(status))
+    };
+
     unstaged_table.clear();
     for (filename, git_file) in files {
-        if git_file.unstaged_status != FileStatus::None {
+        if keep(git_file.unstaged_status) {
             unstaged_table.push((git_file.unstaged_status, filename.clone()));
         }
     }
@@ -30,21 +72,13 @@ fn compute_tables(
 
     staged_table.clear();
     for (filename, git_file) in files {
-        if git_file.staged_status != FileStatus::None {
+        if keep(git_file.staged_status) {
             staged_table.push((git_file.staged_status, filename.clone()));
         }
     }
     staged_table.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
 }
 
-fn switch_staged_status(staged_status: &mut StagedStatus, list_state: &mut ListState) {
-    *staged_status = match staged_status {
-        StagedStatus::Unstaged => StagedStatus::Staged,
-        StagedStatus::Staged => StagedStatus::Unstaged,
-    };
-    list_state.select_first();
-}
-
 fn toggle_stage_git_file(git_file: &mut GitFile, staged_status: StagedStatus) {
     if staged_status == StagedStatus::Unstaged && git_file.unstaged_status == FileStatus::Unmerged {
         git_file.set_status(FileStatus::None, FileStatus::Modified);
@@ -56,36 +90,115 @@ fn toggle_stage_git_file(git_file: &mut GitFile, staged_status: StagedStatus) {
     }
 }
 
-fn parse_git_status(files: &mut HashMap<String, GitFile>, config: &Config) -> Result<(), Error> {
+fn parse_status_code(first: char, second: char) -> (FileStatus, FileStatus) {
+    // unmerged paths, see the "short format" section of `git help status`
+    if matches!(
+        (first, second),
+        ('D', 'D') | ('A', 'U') | ('U', 'D') | ('U', 'A') | ('D', 'U') | ('A', 'A') | ('U', 'U')
+    ) {
+        return (FileStatus::Unmerged, FileStatus::None);
+    }
+
+    let unstaged_status = match second {
+        '?' => FileStatus::New,
+        'D' => FileStatus::Deleted,
+        'M' => FileStatus::Modified,
+        'R' => FileStatus::Renamed,
+        _ => FileStatus::None,
+    };
+
+    let staged_status = match first {
+        'A' => FileStatus::New,
+        'D' => FileStatus::Deleted,
+        'M' => FileStatus::Modified,
+        'R' => FileStatus::Renamed,
+        _ => FileStatus::None,
+    };
+    (unstaged_status, staged_status)
+}
+
+// `git status --porcelain -z`, the files whose name is not valid UTF-8 being left out and
+// returned
+fn parse_git_status(
+    files: &mut HashMap<String, GitFile>,
+    git_status: &[u8],
+) -> Result<Vec<String>, Error> {
     files.clear();
-    let git_status = git_status_output(config);
-    for line in git_status?.lines() {
-        let filename: String = line[2..].trim().to_string();
-        let second: char = line.chars().nth(1).ok_or_else(|| Error::GitParsing)?;
-        let first: char = line.chars().next().ok_or_else(|| Error::GitParsing)?;
-
-        let unstaged_status = match second {
-            '?' => FileStatus::New,
-            'D' => FileStatus::Deleted,
-            'M' => FileStatus::Modified,
-            'U' => FileStatus::Unmerged,
-            _ => FileStatus::None,
+    let mut skipped = Vec::new();
+    let mut entries = git_status
+        .split(|&byte| byte == 0)
+        .filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        // `XY <path>`, followed by an entry with the original path of renames
+        let first = *entry.first().ok_or_else(|| Error::GitParsing)? as char;
+        let second = *entry.get(1).ok_or_else(|| Error::GitParsing)? as char;
+        let path = entry.get(3..).ok_or_else(|| Error::GitParsing)?;
+        let orig_path = match first == 'R' || second == 'R' {
+            true => Some(entries.next().ok_or_else(|| Error::GitParsing)?),
+            false => None,
         };
-
-        let staged_status = match first {
-            'A' => FileStatus::New,
-            'D' => FileStatus::Deleted,
-            'M' => FileStatus::Modified,
-            _ => FileStatus::None,
+        let (Ok(filename), Ok(orig_path)) = (
+            std::str::from_utf8(path),
+            orig_path.map(std::str::from_utf8).transpose(),
+        ) else {
+            skipped.push(String::from_utf8_lossy(path).to_string());
+            continue;
         };
-        let git_file = GitFile::new(unstaged_status, staged_status);
-        files.insert(filename.clone(), git_file);
+
+        let (unstaged_status, staged_status) = parse_status_code(first, second);
+        let mut git_file = GitFile::new(unstaged_status, staged_status);
+        git_file.orig_path = orig_path.map(str::to_string);
+        files.insert(filename.to_string(), git_file);
+    }
+    Ok(skipped)
+}
+
+// `/regex/`, or a glob matching the whole path, or the file name when it has no `/`, `**`
+// spanning directories
+fn file_pattern(pattern: &str) -> Result<Regex, Error> {
+    let invalid = |_| Error::Global(format!("invalid pattern '{}'", pattern));
+    if let Some(regex) = pattern
+        .strip_prefix('/')
+        .and_then(|pattern| pattern.strip_suffix('/'))
+    {
+        return Regex::new(regex).map_err(invalid);
+    }
+    let mut regex = match pattern.contains('/') {
+        true => "^".to_string(),
+        false => "(^|/)".to_string(),
+    };
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            // character classes are kept as is
+            '[' | ']' => regex.push(c),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).map_err(invalid)
+}
+
+// renamed files are displayed as `old -> new`
+fn file_label(file_status: FileStatus, filename: &str, files: &HashMap<String, GitFile>) -> String {
+    let orig_path = files
+        .get(filename)
+        .and_then(|git_file| git_file.orig_path.as_ref());
+    match (file_status, orig_path) {
+        (FileStatus::Renamed, Some(orig_path)) => format!("{} -> {}", orig_path, filename),
+        _ => filename.to_string(),
     }
-    Ok(())
 }
 
 fn list_to_draw<'a>(
     table: &'a [(FileStatus, String)],
+    files: &HashMap<String, GitFile>,
     color: Color,
     title: String,
     config: &'a Config,
@@ -94,7 +207,14 @@ fn list_to_draw<'a>(
 
     let r: Vec<ListItem> = table
         .iter()
-        .map(|item| ListItem::new(format!("{} {}", item.0.character(), item.1)).style(style))
+        .map(|item| {
+            let style = match item.0 {
+                FileStatus::Unmerged => Style::from(config.colors.unmerged),
+                _ => style,
+            };
+            let label = file_label(item.0, &item.1, files);
+            ListItem::new(format!("{} {}", item.0.character(), label)).style(style)
+        })
         .collect();
     List::new(r)
         .block(Block::default().title(title).borders(Borders::TOP))
@@ -112,28 +232,92 @@ pub struct StatusAppViewModel {
 pub struct StatusApp {
     state: AppState,
     staged_status: StagedStatus,
+    // selection and offset of the table not focused, restored when focusing it again
+    other_list_state: ListState,
     unstaged_table: Vec<(FileStatus, String)>,
     staged_table: Vec<(FileStatus, String)>,
     git_files: HashMap<String, GitFile>,
+    filter: Option<FileStatus>,
+    preview: Text<'static>,
+    preview_key: PreviewKey,
+    preview_request: Option<(PreviewKey, Instant)>,
+    // diff being computed in the background, not to block the drawing
+    preview_job: Option<(PreviewKey, Arc<Mutex<Option<Text<'static>>>>)>,
+    // current branch and commits ahead and behind its upstream, displayed above the tables
+    branch: String,
+    ahead_behind: Option<(usize, usize)>,
+    original_dir: std::path::PathBuf,
     view_model: StatusAppViewModel,
 }
 
 impl StatusApp {
     pub fn new() -> Result<Self, Error> {
         let mut state = AppState::new()?;
-        state.list_state.select_first();
+        // the paths given by git status are relative to the root of the repository
+        let original_dir = set_git_dir(&state.config)?;
+        state.select_default();
         let mut instance = Self {
             state,
+            original_dir,
             staged_status: StagedStatus::Unstaged,
+            other_list_state: ListState::default(),
             unstaged_table: Vec::new(),
             staged_table: Vec::new(),
             git_files: HashMap::new(),
+            filter: None,
+            preview: Text::default(),
+            preview_key: None,
+            preview_request: None,
+            preview_job: None,
+            branch: String::new(),
+            ahead_behind: None,
             view_model: StatusAppViewModel::default(),
         };
-        instance.reload()?;
+        if let Err(err) = instance.reload() {
+            // back to the initial directory, as when exiting
+            instance.on_exit()?;
+            return Err(err);
+        }
         Ok(instance)
     }
 
+    // like the first line of `git status -sb`
+    fn header(&self) -> Line<'static> {
+        let mut spans = vec![Span::styled(
+            self.branch.clone(),
+            Style::from(Color::Cyan).add_modifier(Modifier::BOLD),
+        )];
+        if let Some((ahead, behind)) = self.ahead_behind {
+            spans.push(Span::styled(
+                format!(" ↑{} ↓{}", ahead, behind),
+                Style::from(Color::Gray),
+            ));
+        }
+        spans.push(match self.git_files.is_empty() {
+            true => Span::styled(" clean", Style::from(Color::Green)),
+            false => Span::styled(" dirty", Style::from(Color::Red)),
+        });
+        Line::from(spans)
+    }
+
+    fn focus(&mut self, staged_status: StagedStatus) {
+        if staged_status != self.staged_status {
+            self.staged_status = staged_status;
+            std::mem::swap(&mut self.state.list_state, &mut self.other_list_state);
+        }
+        // first focus of the table
+        if self.state.list_state.selected().is_none() {
+            self.state.list_state.select_first();
+        }
+    }
+
+    fn switch_staged_status(&mut self) {
+        self.focus(match self.staged_status {
+            StagedStatus::Unstaged => StagedStatus::Staged,
+            StagedStatus::Staged => StagedStatus::Unstaged,
+        });
+    }
+
     fn get_current_table(&self) -> &Vec<(FileStatus, String)> {
         match self.staged_status {
             StagedStatus::Staged => &self.staged_table,
@@ -158,9 +342,176 @@ impl StatusApp {
         Ok(git_file)
     }
 
+    // stage the unstaged files matching `pattern`, prompted for when `None`
+    fn stage_by_pattern(&mut self, pattern: &Option<String>) -> Result<(), Error> {
+        let Some(pattern) = pattern else {
+            self.state.command_string.clear();
+            self.state.edit_cursor = 0;
+            self.state.input_state = InputState::StagePattern;
+            return Ok(());
+        };
+        let regex = file_pattern(pattern.trim())?;
+        let filenames: Vec<String> = self
+            .unstaged_table
+            .iter()
+            .filter(|(_, filename)| regex.is_match(filename.trim_end_matches('/')))
+            .map(|(_, filename)| filename.clone())
+            .collect();
+        if filenames.is_empty() {
+            return Err(Error::Global(format!(
+                "no unstaged file matching '{}'",
+                pattern.trim()
+            )));
+        }
+        for filename in &filenames {
+            if let Some(git_file) = self.git_files.get_mut(filename) {
+                toggle_stage_git_file(git_file, StagedStatus::Unstaged);
+            }
+        }
+        git_add_restore(&mut self.git_files, &self.state.config);
+        self.refresh_tables();
+        let plural = if filenames.len() > 1 { "s" } else { "" };
+        self.notif(
+            NotifChannel::Echo,
+            Some(format!("{} file{} staged", filenames.len(), plural)),
+        );
+        Ok(())
+    }
+
+    // selected file, which has to be conflicted
+    fn get_conflicted_filename(&self) -> Result<String, Error> {
+        let filename = self.get_filename()?;
+        match self.get_git_file()?.unstaged_status {
+            FileStatus::Unmerged => Ok(filename),
+            _ => Err(Error::Global(format!("{} is not conflicted", filename))),
+        }
+    }
+
     fn tables_are_empty(&self) -> bool {
         self.unstaged_table.is_empty() && self.staged_table.is_empty()
     }
+
+    fn refresh_tables(&mut self) {
+        compute_tables(
+            &self.git_files,
+            self.filter,
+            &mut self.unstaged_table,
+            &mut self.staged_table,
+        );
+        if self.filter.is_some() && self.tables_are_empty() {
+            // nothing left to focus on, show everything again
+            self.filter = None;
+            self.refresh_tables();
+        }
+        // files may have moved to the other table
+        let (current_len, other_len) = match self.staged_status {
+            StagedStatus::Unstaged => (self.unstaged_table.len(), self.staged_table.len()),
+            StagedStatus::Staged => (self.staged_table.len(), self.unstaged_table.len()),
+        };
+        for (list_state, len) in [
+            (&mut self.state.list_state, current_len),
+            (&mut self.other_list_state, other_len),
+        ] {
+            if let Some(idx) = list_state.selected() {
+                list_state.select(Some(idx.min(len.saturating_sub(1))));
+            }
+        }
+    }
+
+    fn update_preview(&mut self) {
+        let done = self.preview_job.as_ref().and_then(|(key, result)| {
+            let text = result.lock().unwrap().take()?;
+            Some((key.clone(), text))
+        });
+        if let Some((key, text)) = done {
+            self.preview = text;
+            self.preview_key = key;
+            self.preview_job = None;
+        }
+        let key = self
+            .get_filename()
+            .ok()
+            .map(|filename| (self.staged_status, filename));
+        if key == self.preview_key {
+            // back on the previewed file before the diff of another one is computed
+            self.preview_request = None;
+            self.preview_job = None;
+            return;
+        }
+        if self
+            .preview_job
+            .as_ref()
+            .is_some_and(|(job_key, _)| *job_key == key)
+        {
+            return;
+        }
+        // wait for the selection to settle, not to run git for each file scrolled through
+        match &self.preview_request {
+            Some((requested, since)) if *requested == key => {
+                if since.elapsed() < PREVIEW_DELAY {
+                    return;
+                }
+            }
+            _ => {
+                self.preview_request = Some((key, Instant::now()));
+                return;
+            }
+        }
+        self.preview_request = None;
+        let Some(args) = key
+            .as_ref()
+            .and_then(|(staged_status, filename)| self.preview_args(*staged_status, filename))
+        else {
+            self.preview = Text::default();
+            self.preview_key = key;
+            self.preview_job = None;
+            return;
+        };
+        let result = Arc::new(Mutex::new(None));
+        let result_clone = Arc::clone(&result);
+        let config = self.state.config.clone();
+        thread::spawn(move || {
+            let text = preview_text(&args, &config);
+            *result_clone.lock().unwrap() = Some(text);
+        });
+        self.preview_job = Some((key, result));
+    }
+
+    // arguments of the `git diff` of the file as it appears in the table
+    fn preview_args(&self, staged_status: StagedStatus, filename: &str) -> Option<Vec<String>> {
+        let git_file = self.git_files.get(filename)?;
+        let file_status = match staged_status {
+            StagedStatus::Staged => git_file.staged_status,
+            StagedStatus::Unstaged => git_file.unstaged_status,
+        };
+        let mut args = vec!["diff".to_string(), "--color=always".to_string()];
+        let extra_args: &[&str] = if file_status == FileStatus::New
+            && (staged_status == StagedStatus::Unstaged || git_file.is_pending())
+        {
+            // untracked file
+            &["--no-index", "--", NULL_DEVICE]
+        } else if git_file.is_pending() {
+            // the index is only updated later, compare with what it will be
+            &["HEAD", "--"]
+        } else if staged_status == StagedStatus::Staged {
+            &["--cached", "--"]
+        } else {
+            &["--"]
+        };
+        args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        args.push(filename.to_string());
+        if file_status == FileStatus::Renamed {
+            args.extend(git_file.orig_path.clone());
+        }
+        Some(args)
+    }
+
+    fn table_title(&self, title: &str) -> String {
+        match self.filter {
+            Some(filter) => format!("{} ({} only)", title, filter.name()),
+            None => format!("{}:", title),
+        }
+    }
 }
 
 impl GitApp for StatusApp {
@@ -173,7 +524,7 @@ impl GitApp for StatusApp {
 
     fn on_click(&mut self) {
         if self.view_model.top_rect.contains(self.state.mouse_position) {
-            self.staged_status = StagedStatus::Unstaged;
+            self.focus(StagedStatus::Unstaged);
             let delta = (self.state.mouse_position.y - self.view_model.top_rect.y) as usize;
             if delta > 0 {
                 self.state
@@ -185,7 +536,7 @@ impl GitApp for StatusApp {
             .bottom_rect
             .contains(self.state.mouse_position)
         {
-            self.staged_status = StagedStatus::Staged;
+            self.focus(StagedStatus::Staged);
             let delta = (self.state.mouse_position.y - self.view_model.bottom_rect.y) as usize;
             if delta > 0 {
                 self.state
@@ -198,35 +549,69 @@ impl GitApp for StatusApp {
     fn get_text_line(&self, idx: usize) -> Option<String> {
         self.get_current_table()
             .get(idx)
-            .map(|(_, name)| name.to_string())
+            .map(|(file_status, name)| file_label(*file_status, name, &self.git_files))
+    }
+
+    // the preview is computed once the selection settled
+    fn needs_redraw(&self) -> bool {
+        self.preview_request.is_some() || self.preview_job.is_some()
     }
 
     fn reload(&mut self) -> Result<(), Error> {
         git_add_restore(&mut self.git_files, &self.state.config);
-        parse_git_status(&mut self.git_files, &self.state.config)?;
-        compute_tables(
-            &self.git_files,
-            &mut self.unstaged_table,
-            &mut self.staged_table,
-        );
+        let git_status = git_status_output(&self.state.config)?;
+        let skipped = parse_git_status(&mut self.git_files, &git_status)?;
+        if !skipped.is_empty() {
+            let message = format!("non UTF-8 file names not listed: {}", skipped.join(", "));
+            self.notif(NotifChannel::Error, Some(message));
+        }
+        // no branch is checked out yet in an empty repository
+        self.branch = git_current_branch(&self.state.config).unwrap_or_default();
+        self.ahead_behind = git_ahead_behind(&self.state.config);
+        self.refresh_tables();
+        self.preview_key = None;
+        self.preview_job = None;
         if !self.tables_are_empty() && self.get_current_table().is_empty() {
-            switch_staged_status(&mut self.staged_status, &mut self.state.list_state);
+            self.switch_staged_status();
         }
         Ok(())
     }
 
     fn on_exit(&mut self) -> Result<(), Error> {
         git_add_restore(&mut self.git_files, &self.state.config);
-        Ok(())
+        env::set_current_dir(self.original_dir.clone())
+            .map_err(|_| Error::Global("could not restore initial working directory".to_string()))
     }
 
     fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(rect);
+        frame.render_widget(Paragraph::new(self.header()), chunks[0]);
+        let rect = chunks[1];
         if self.tables_are_empty() {
             let paragraph = Paragraph::new("Nothing to commit, working tree clean");
             frame.render_widget(paragraph, rect);
             return;
         }
 
+        let rect = match self.state.config.status_preview {
+            true => {
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(rect);
+                self.update_preview();
+                let height = chunks[1].height as usize;
+                let lines: Vec<Line> = self.preview.lines.iter().take(height).cloned().collect();
+                let preview = Paragraph::new(lines).block(Block::default().borders(Borders::LEFT));
+                frame.render_widget(preview, chunks[1]);
+                chunks[0]
+            }
+            false => rect,
+        };
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
@@ -236,37 +621,40 @@ impl GitApp for StatusApp {
 
         let top_list = list_to_draw(
             &self.unstaged_table,
-            Color::Red,
-            "Not staged:".to_string(),
+            &self.git_files,
+            self.state.config.colors.unstaged,
+            self.table_title("Not staged"),
             &self.state.config,
         );
-        let mut default = ListState::default();
+        // the table not focused keeps its offset, without its selection displayed
+        let mut other = ListState::default().with_offset(self.other_list_state.offset());
         StatefulWidget::render(
             &top_list,
             chunks[0],
             frame.buffer_mut(),
             match self.staged_status {
                 StagedStatus::Unstaged => &mut self.state.list_state,
-                StagedStatus::Staged => &mut default,
+                StagedStatus::Staged => &mut other,
             },
         );
 
         let bottom_list = list_to_draw(
             &self.staged_table,
-            Color::Green,
-            "Staged:".to_string(),
+            &self.git_files,
+            self.state.config.colors.staged,
+            self.table_title("Staged"),
             &self.state.config,
         );
-        let mut default = ListState::default();
         StatefulWidget::render(
             &bottom_list,
             chunks[1],
             frame.buffer_mut(),
             match self.staged_status {
-                StagedStatus::Unstaged => &mut default,
+                StagedStatus::Unstaged => &mut other,
                 StagedStatus::Staged => &mut self.state.list_state,
             },
         );
+        *self.other_list_state.offset_mut() = other.offset();
 
         let chunk = match self.staged_status {
             StagedStatus::Unstaged => chunks[0],
@@ -277,8 +665,8 @@ impl GitApp for StatusApp {
             Rect {
                 x: rect.x + chunk.x + 2,
                 y: chunk.y + 1,
-                width: chunk.width - 1,
-                height: chunk.height - 1,
+                width: chunk.width.saturating_sub(1),
+                height: chunk.height.saturating_sub(1),
             },
         );
     }
@@ -306,13 +694,13 @@ impl GitApp for StatusApp {
 
     fn on_scroll(&mut self, down: bool) {
         if self.view_model.top_rect.contains(self.state.mouse_position) {
-            self.staged_status = StagedStatus::Unstaged;
+            self.focus(StagedStatus::Unstaged);
         } else if self
             .view_model
             .bottom_rect
             .contains(self.state.mouse_position)
         {
-            self.staged_status = StagedStatus::Staged;
+            self.focus(StagedStatus::Staged);
         };
         let rect = match self.staged_status {
             StagedStatus::Unstaged => self.view_model.top_rect,
@@ -327,15 +715,29 @@ impl GitApp for StatusApp {
         action: &Action,
         terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     ) -> Result<(), Error> {
+        let staged_status = self.staged_status;
         match action {
             Action::StageUnstageFile => {
-                let git_file = self.git_files.get_mut(&self.get_filename()?).unwrap();
-                toggle_stage_git_file(git_file, self.staged_status);
-                compute_tables(
-                    &self.git_files,
-                    &mut self.unstaged_table,
-                    &mut self.staged_table,
-                );
+                // all the files of the visual range
+                let filenames: Vec<String> = match self.visual_range() {
+                    Some((first, last)) => self
+                        .get_current_table()
+                        .iter()
+                        .take(last + 1)
+                        .skip(first)
+                        .map(|(_, filename)| filename.clone())
+                        .collect(),
+                    None => vec![self.get_filename()?],
+                };
+                for filename in filenames {
+                    let git_file = match self.git_files.get_mut(&filename) {
+                        Some(git_file) => git_file,
+                        None => return Err(Error::UnknownFilename(filename)),
+                    };
+                    toggle_stage_git_file(git_file, self.staged_status);
+                }
+                self.state.visual_anchor = None;
+                self.refresh_tables();
             }
             Action::StageUnstageFiles => {
                 let filenames: Vec<_> = self
@@ -350,28 +752,147 @@ impl GitApp for StatusApp {
                     };
                     toggle_stage_git_file(git_file, self.staged_status);
                 }
-                compute_tables(
-                    &self.git_files,
-                    &mut self.unstaged_table,
-                    &mut self.staged_table,
-                );
+                self.refresh_tables();
             }
+            Action::StageByPattern(pattern) => self.stage_by_pattern(pattern)?,
             Action::StatusSwitchView => {
                 let other_len = match self.staged_status {
                     StagedStatus::Staged => self.unstaged_table.len(),
                     StagedStatus::Unstaged => self.staged_table.len(),
                 };
                 if other_len > 0 {
-                    switch_staged_status(&mut self.staged_status, &mut self.state.list_state);
+                    self.switch_staged_status();
                 }
             }
-            Action::FocusUnstagedView => {
-                self.staged_status = StagedStatus::Unstaged;
+            Action::FocusUnstagedView => self.focus(StagedStatus::Unstaged),
+            Action::FocusStagedView => self.focus(StagedStatus::Staged),
+            Action::FilterUnmerged | Action::FilterStatus(_) | Action::ClearFilter => {
+                self.filter = match action {
+                    Action::FilterUnmerged => Some(FileStatus::Unmerged),
+                    Action::FilterStatus(file_status) => Some(*file_status),
+                    _ => None,
+                };
+                let requested = self.filter;
+                self.refresh_tables();
                 self.state().list_state.select_first();
+                self.other_list_state = ListState::default();
+                if let (Some(file_status), None) = (requested, self.filter) {
+                    return Err(Error::Global(format!("no {} file", file_status.name())));
+                }
             }
-            Action::FocusStagedView => {
-                self.staged_status = StagedStatus::Staged;
-                self.state().list_state.select_first();
+            Action::StageHunk => {
+                git_add_restore(&mut self.git_files, &self.state.config);
+                let git_file = self.get_git_file()?;
+                if self.staged_status == StagedStatus::Staged {
+                    return Err(Error::Global("file already staged".to_string()));
+                }
+                if git_file.unstaged_status == FileStatus::New {
+                    let message = "untracked files can only be staged as a whole";
+                    return Err(Error::Global(message.to_string()));
+                }
+                let args = vec!["--".to_string(), self.get_filename()?];
+                terminal.clear()?;
+                PagerApp::new(Some(PagerCommand::Diff(args)))?.run(terminal)?;
+                terminal.clear()?;
+                self.reload()?;
+            }
+            Action::DiscardFile => {
+                // pending stage operations are applied first
+                git_add_restore(&mut self.git_files, &self.state.config);
+                if self.staged_status == StagedStatus::Staged {
+                    let message = "only unstaged changes can be discarded";
+                    return Err(Error::Global(message.to_string()));
+                }
+                let filename = self.get_filename()?;
+                // paths are relative to the root of the repository
+                match self.get_git_file()?.unstaged_status {
+                    FileStatus::New => git_clean(&filename, &self.state.config)?,
+                    _ => git_discard(&filename, &self.state.config)?,
+                }
+                self.reload()?;
+            }
+            Action::MarkResolved => {
+                git_add_restore(&mut self.git_files, &self.state.config);
+                let filename = self.get_conflicted_filename()?;
+                git_mark_resolved(&filename, &self.state.config)?;
+                self.reload()?;
+                self.notif(NotifChannel::Echo, Some(format!("{} resolved", filename)));
+            }
+            Action::OpenConflict => {
+                git_add_restore(&mut self.git_files, &self.state.config);
+                let filename = self.get_conflicted_filename()?;
+                // at the first conflict marker, the file being gone when deleted on one side
+                let line = std::fs::read_to_string(&filename)
+                    .ok()
+                    .and_then(|content| {
+                        content.lines().position(|line| line.starts_with("<<<<<<<"))
+                    })
+                    .map_or(1, |idx| idx + 1);
+                let command = editor_command(&self.state.config, &filename);
+                self.run_command(
+                    terminal,
+                    &CommandType::Sync,
+                    command,
+                    Some(filename),
+                    None,
+                    Some(line),
+                )?;
+            }
+            Action::OpenDiff => {
+                git_add_restore(&mut self.git_files, &self.state.config);
+                let git_file = self.get_git_file()?;
+                let mut args = vec!["--".to_string(), self.get_filename()?];
+                match self.staged_status {
+                    StagedStatus::Staged => args.insert(0, "--cached".to_string()),
+                    StagedStatus::Unstaged if git_file.unstaged_status == FileStatus::New => {
+                        return Err(Error::Global("untracked files have no diff".to_string()));
+                    }
+                    StagedStatus::Unstaged => (),
+                }
+                terminal.clear()?;
+                PagerApp::new(Some(PagerCommand::Diff(args)))?.run(terminal)?;
+                terminal.clear()?;
+            }
+            Action::Commit(message) => {
+                // pending stage operations are part of the commit
+                git_add_restore(&mut self.git_files, &self.state.config);
+                if self
+                    .git_files
+                    .values()
+                    .all(|git_file| git_file.staged_status == FileStatus::None)
+                {
+                    return Err(Error::Global("nothing staged to commit".to_string()));
+                }
+                if message.is_empty() {
+                    self.state.command_string.clear();
+                    self.state.edit_cursor = 0;
+                    self.state.input_state = InputState::MultiLine(MultiLinePrompt::Commit);
+                    return Ok(());
+                }
+                let summary = git_commit(message, false, &self.state.config)?;
+                self.reload()?;
+                self.notif(NotifChannel::Echo, Some(summary));
+            }
+            Action::Amend(message) => {
+                git_add_restore(&mut self.git_files, &self.state.config);
+                let (subject, body) = git_head_message(&self.state.config)?;
+                if message.is_empty() {
+                    self.state.edit_cursor = subject.chars().count();
+                    self.state.command_string = match body.is_empty() {
+                        true => subject,
+                        false => format!("{}\n\n{}", subject, body),
+                    };
+                    self.state.input_state = InputState::MultiLine(MultiLinePrompt::Amend);
+                    return Ok(());
+                }
+                // a single line only replaces the subject, the body being kept as is
+                let message = match message.contains('\n') || body.is_empty() {
+                    true => message.clone(),
+                    false => format!("{}\n\n{}", message, body),
+                };
+                let summary = git_commit(&message, true, &self.state.config)?;
+                self.reload()?;
+                self.notif(NotifChannel::Echo, Some(summary));
             }
             action => {
                 if matches!(action, Action::Command(_, _)) {
@@ -385,8 +906,82 @@ impl GitApp for StatusApp {
             }
         }
         if !self.tables_are_empty() && self.get_current_table().is_empty() {
-            switch_staged_status(&mut self.staged_status, &mut self.state.list_state);
+            self.switch_staged_status();
+        }
+        // the range was in the other table
+        if self.staged_status != staged_status {
+            self.state.visual_anchor = None;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflict_codes_are_unmerged() {
+        for code in ["DD", "AU", "UD", "UA", "DU", "AA", "UU"] {
+            let mut chars = code.chars();
+            let (first, second) = (chars.next().unwrap(), chars.next().unwrap());
+            assert_eq!(
+                parse_status_code(first, second),
+                (FileStatus::Unmerged, FileStatus::None),
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn other_codes_split_unstaged_and_staged() {
+        assert_eq!(
+            parse_status_code('M', 'M'),
+            (FileStatus::Modified, FileStatus::Modified)
+        );
+        assert_eq!(
+            parse_status_code('A', 'D'),
+            (FileStatus::Deleted, FileStatus::New)
+        );
+        assert_eq!(
+            parse_status_code('?', '?'),
+            (FileStatus::New, FileStatus::None)
+        );
+        assert_eq!(
+            parse_status_code(' ', 'M'),
+            (FileStatus::Modified, FileStatus::None)
+        );
+    }
+
+    #[test]
+    fn file_names_with_spaces_quotes_and_utf8() {
+        let mut files = HashMap::new();
+        let git_status = "M  with spaces.txt\0?? \"quoted\" 'name'.txt\0 M café/日本語.rs\0\
+                          R  new name.rs\0old \"name\".rs\0";
+        let skipped = parse_git_status(&mut files, git_status.as_bytes()).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(files.len(), 4);
+        assert_eq!(files["with spaces.txt"].staged_status, FileStatus::Modified);
+        assert_eq!(
+            files["\"quoted\" 'name'.txt"].unstaged_status,
+            FileStatus::New
+        );
+        assert_eq!(
+            files["café/日本語.rs"].unstaged_status,
+            FileStatus::Modified
+        );
+        let renamed = &files["new name.rs"];
+        assert_eq!(renamed.staged_status, FileStatus::Renamed);
+        assert_eq!(renamed.orig_path.as_deref(), Some("old \"name\".rs"));
+    }
+
+    #[test]
+    fn non_utf8_file_names_are_skipped() {
+        let mut files = HashMap::new();
+        let git_status = b"?? bad\xff.txt\0R  new.rs\0old\xfe.rs\0 M good.rs\0";
+        let skipped = parse_git_status(&mut files, git_status).unwrap();
+        assert_eq!(skipped, vec!["bad\u{fffd}.txt", "new.rs"]);
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["good.rs"]);
+    }
+}
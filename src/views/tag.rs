@@ -0,0 +1,211 @@
+use crate::app::{FileRevLine, GitApp};
+
+use crate::model::{
+    action::{Action, CommandType},
+    app_state::{AppState, InputState, NotifChannel},
+    config::MappingScope,
+    editor::shell_quote,
+    errors::Error,
+    git::{git_tag_create, git_tag_output, Tag},
+};
+use crate::ui::utils::{date_to_color, format_date, highlight_style};
+
+use chrono::DateTime;
+
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{List, Paragraph, StatefulWidget},
+    Frame, Terminal,
+};
+
+struct TagAppViewModel {
+    tag_list: List<'static>,
+    rect: Rect,
+}
+
+pub struct TagApp {
+    state: AppState,
+    tags: Vec<Tag>,
+    view_model: TagAppViewModel,
+}
+
+impl TagApp {
+    pub fn new() -> Result<Self, Error> {
+        let state = AppState::new()?;
+        let mut r = Self {
+            state,
+            tags: Vec::new(),
+            view_model: TagAppViewModel {
+                tag_list: List::default(),
+                rect: Rect::default(),
+            },
+        };
+        r.reload()?;
+        r.state.select_default();
+        Ok(r)
+    }
+
+    fn get_tag(&self) -> Result<&Tag, Error> {
+        self.tags.get(self.idx()?).ok_or_else(|| Error::StateIndex)
+    }
+}
+
+impl GitApp for TagApp {
+    fn state(&mut self) -> &mut AppState {
+        &mut self.state
+    }
+
+    fn get_state(&self) -> &AppState {
+        &self.state
+    }
+
+    fn reload(&mut self) -> Result<(), Error> {
+        let output = git_tag_output(&self.state.config)?;
+        self.tags = output
+            .lines()
+            .map(|line| {
+                let mut split = line.splitn(4, '\t');
+                let (Some(date), Some(object_type), Some(name)) =
+                    (split.next(), split.next(), split.next())
+                else {
+                    return Err(Error::GitParsing);
+                };
+                // the subject of lightweight tags is the one of the tagged commit
+                let message = (object_type == "tag")
+                    .then(|| split.next().unwrap_or("").to_string())
+                    .filter(|message| !message.is_empty());
+                let time = DateTime::parse_from_rfc3339(date).map_err(|_| Error::GitParsing)?;
+                Ok(Tag {
+                    date: format_date(&time, &self.state.config.date_format),
+                    time,
+                    name: name.to_string(),
+                    message,
+                })
+            })
+            .collect::<Result<Vec<Tag>, Error>>()?;
+
+        // relative dates differ in length
+        let date_len = self
+            .tags
+            .iter()
+            .map(|tag| tag.date.chars().count())
+            .max()
+            .unwrap_or(0);
+        let list_items: Vec<Line> = self
+            .tags
+            .iter()
+            .map(|tag| {
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{:<date_len$}", tag.date),
+                        Style::from(date_to_color(&tag.time)),
+                    ),
+                    Span::raw(" "),
+                    Span::styled(tag.name.clone(), Style::from(Color::White)),
+                ];
+                if let Some(message) = &tag.message {
+                    spans.push(Span::styled(
+                        format!(" {}", message),
+                        Style::from(Color::DarkGray),
+                    ));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        self.view_model.tag_list = List::new(list_items)
+            .highlight_style(highlight_style())
+            .scroll_padding(self.state.config.scrolloff);
+        Ok(())
+    }
+
+    fn get_text_line(&self, idx: usize) -> Option<String> {
+        self.tags.get(idx).map(|tag| match &tag.message {
+            Some(message) => format!("{} {} {}", tag.date, tag.name, message),
+            None => format!("{} {}", tag.date, tag.name),
+        })
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        self.view_model.rect = rect;
+        if self.tags.is_empty() {
+            let paragraph = Paragraph::new("No tags yet");
+            frame.render_widget(paragraph, rect);
+            return;
+        }
+        StatefulWidget::render(
+            &self.view_model.tag_list,
+            rect,
+            frame.buffer_mut(),
+            &mut self.state.list_state,
+        );
+
+        self.highlight_search(frame, rect);
+    }
+
+    fn get_mapping_fields(&self) -> Vec<MappingScope> {
+        vec![MappingScope::Tag]
+    }
+
+    fn get_file_rev_line(&self) -> Result<FileRevLine, Error> {
+        Ok((None, Some(self.get_tag()?.name.clone()), None))
+    }
+
+    fn run_action(
+        &mut self,
+        action: &Action,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> Result<(), Error> {
+        match action {
+            Action::TagCreate(name) => {
+                let Some(name) = name else {
+                    self.state.command_string.clear();
+                    self.state.edit_cursor = 0;
+                    self.state.input_state = InputState::TagName;
+                    return Ok(());
+                };
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err(Error::Global("aborting due to empty tag name".to_string()));
+                }
+                git_tag_create(name, &self.state.config)?;
+                self.reload()?;
+                if let Some(idx) = self.tags.iter().position(|tag| tag.name == name) {
+                    self.state.list_state.select(Some(idx));
+                }
+                self.notif(NotifChannel::Echo, Some(format!("created tag {}", name)));
+            }
+            Action::TagCheckout => {
+                // a branch of the same name would be checked out otherwise
+                let tag = shell_quote(&format!("refs/tags/{}", self.get_tag()?.name));
+                self.run_command(
+                    terminal,
+                    &CommandType::Sync,
+                    format!("%(git) checkout {}", tag),
+                    None,
+                    None,
+                    None,
+                )?;
+            }
+            _ => {
+                self.run_action_generic(action, self.view_model.rect.height as usize, terminal)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_click(&mut self) {
+        if self.view_model.rect.contains(self.state.mouse_position) {
+            let delta = (self.state.mouse_position.y - self.view_model.rect.y) as usize;
+            let idx =
+                (self.state.list_state.offset() + delta).min(self.tags.len().saturating_sub(1));
+            self.state.list_state.select(Some(idx));
+        }
+    }
+
+    fn on_scroll(&mut self, down: bool) {
+        self.on_scroll_generic(down, self.view_model.rect.height as usize, self.tags.len());
+    }
+}
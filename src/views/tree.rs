@@ -0,0 +1,254 @@
+use std::{collections::HashSet, env};
+
+use crate::app::{FileRevLine, GitApp};
+
+use crate::model::{
+    action::Action,
+    app_state::AppState,
+    config::MappingScope,
+    errors::Error,
+    git::{git_ls_files, set_git_dir},
+};
+use crate::ui::utils::highlight_style;
+use crate::views::blame::BlameApp;
+
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, Paragraph, StatefulWidget},
+    Frame, Terminal,
+};
+
+struct TreeRow {
+    // relative to the repository root
+    path: String,
+    depth: usize,
+    folder: bool,
+}
+
+impl TreeRow {
+    fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+struct TreeAppViewModel {
+    tree_list: List<'static>,
+    rect: Rect,
+}
+
+pub struct TreeApp {
+    state: AppState,
+    // tracked files, sorted so that the files of a folder follow each other
+    files: Vec<String>,
+    // folders are collapsed unless expanded
+    expanded: HashSet<String>,
+    rows: Vec<TreeRow>,
+    original_dir: std::path::PathBuf,
+    view_model: TreeAppViewModel,
+}
+
+impl TreeApp {
+    pub fn new() -> Result<Self, Error> {
+        let state = AppState::new()?;
+        let original_dir = set_git_dir(&state.config)?;
+        let mut r = Self {
+            state,
+            files: Vec::new(),
+            expanded: HashSet::new(),
+            rows: Vec::new(),
+            original_dir,
+            view_model: TreeAppViewModel {
+                tree_list: List::default(),
+                rect: Rect::default(),
+            },
+        };
+        if let Err(err) = r.reload() {
+            // back to the initial directory, as when exiting
+            r.on_exit()?;
+            return Err(err);
+        }
+        r.state.select_default();
+        Ok(r)
+    }
+
+    // displayed if all the folders containing it are expanded
+    fn is_visible(&self, path: &str) -> bool {
+        path.match_indices('/')
+            .all(|(idx, _)| self.expanded.contains(&path[..idx]))
+    }
+
+    fn update_rows(&mut self) {
+        let mut rows = Vec::new();
+        let mut folders: Vec<&str> = Vec::new();
+        for file in &self.files {
+            let parts: Vec<&str> = file.split('/').collect();
+            let parents = &parts[..parts.len() - 1];
+            // folders opened by the previous files and still containing this one
+            let common = folders
+                .iter()
+                .zip(parents)
+                .take_while(|(a, b)| a == b)
+                .count();
+            folders.truncate(common);
+            for folder in &parents[common..] {
+                folders.push(folder);
+                let path = folders.join("/");
+                if self.is_visible(&path) {
+                    rows.push(TreeRow {
+                        path,
+                        depth: folders.len() - 1,
+                        folder: true,
+                    });
+                }
+            }
+            if self.is_visible(file) {
+                rows.push(TreeRow {
+                    path: file.clone(),
+                    depth: parents.len(),
+                    folder: false,
+                });
+            }
+        }
+        self.rows = rows;
+
+        let list_items: Vec<Line> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let indent = "  ".repeat(row.depth);
+                let (prefix, style) = match (row.folder, self.expanded.contains(&row.path)) {
+                    (true, true) => ("▾ ", Style::from(Color::Blue).add_modifier(Modifier::BOLD)),
+                    (true, false) => ("▸ ", Style::from(Color::Blue).add_modifier(Modifier::BOLD)),
+                    (false, _) => ("  ", Style::from(Color::White)),
+                };
+                Line::from(vec![
+                    Span::raw(indent),
+                    Span::styled(prefix, style),
+                    Span::styled(row.name().to_string(), style),
+                ])
+            })
+            .collect();
+        self.view_model.tree_list = List::new(list_items)
+            .highlight_style(highlight_style())
+            .scroll_padding(self.state.config.scrolloff);
+    }
+
+    fn get_row(&self) -> Result<&TreeRow, Error> {
+        self.rows.get(self.idx()?).ok_or_else(|| Error::StateIndex)
+    }
+
+    // expand or collapse folders, blame files
+    fn open(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> Result<(), Error> {
+        let row = self.get_row()?;
+        let path = row.path.clone();
+        if !row.folder {
+            terminal.clear()?;
+            BlameApp::new(path, None, None)?.run(terminal)?;
+            terminal.clear()?;
+            return Ok(());
+        }
+        if !self.expanded.remove(&path) {
+            self.expanded.insert(path);
+        }
+        self.update_rows();
+        Ok(())
+    }
+}
+
+impl GitApp for TreeApp {
+    fn state(&mut self) -> &mut AppState {
+        &mut self.state
+    }
+
+    fn get_state(&self) -> &AppState {
+        &self.state
+    }
+
+    fn on_exit(&mut self) -> Result<(), Error> {
+        env::set_current_dir(self.original_dir.clone())
+            .map_err(|_| Error::Global("could not restore initial working directory".to_string()))
+    }
+
+    fn reload(&mut self) -> Result<(), Error> {
+        self.files = git_ls_files(&self.state.config)?;
+        self.update_rows();
+        if let Some(idx) = self.state.list_state.selected() {
+            let last = self.rows.len().saturating_sub(1);
+            self.state.list_state.select(Some(idx.min(last)));
+        }
+        Ok(())
+    }
+
+    fn get_text_line(&self, idx: usize) -> Option<String> {
+        self.rows.get(idx).map(|row| {
+            let prefix = match (row.folder, self.expanded.contains(&row.path)) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                (false, _) => "  ",
+            };
+            format!("{}{}{}", "  ".repeat(row.depth), prefix, row.name())
+        })
+    }
+
+    fn get_copy_line(&self, idx: usize) -> Option<String> {
+        self.rows.get(idx).map(|row| row.path.clone())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, rect: Rect) {
+        self.view_model.rect = rect;
+        if self.rows.is_empty() {
+            let paragraph = Paragraph::new("No tracked files yet");
+            frame.render_widget(paragraph, rect);
+            return;
+        }
+        StatefulWidget::render(
+            &self.view_model.tree_list,
+            rect,
+            frame.buffer_mut(),
+            &mut self.state.list_state,
+        );
+
+        self.highlight_search(frame, rect);
+    }
+
+    fn get_mapping_fields(&self) -> Vec<MappingScope> {
+        vec![MappingScope::Tree]
+    }
+
+    fn get_file_rev_line(&self) -> Result<FileRevLine, Error> {
+        Ok((Some(self.get_row()?.path.clone()), None, None))
+    }
+
+    fn run_action(
+        &mut self,
+        action: &Action,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> Result<(), Error> {
+        match action {
+            Action::TreeOpen => self.open(terminal)?,
+            _ => {
+                self.run_action_generic(action, self.view_model.rect.height as usize, terminal)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_click(&mut self) {
+        if self.view_model.rect.contains(self.state.mouse_position) {
+            let delta = (self.state.mouse_position.y - self.view_model.rect.y) as usize;
+            self.state
+                .list_state
+                .select(Some(self.state.list_state.offset() + delta));
+        }
+    }
+
+    fn on_scroll(&mut self, down: bool) {
+        self.on_scroll_generic(down, self.view_model.rect.height as usize, self.rows.len());
+    }
+}